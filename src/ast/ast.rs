@@ -1,3 +1,7 @@
+use std::cell::{Cell, OnceCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
 use crate::ast::expression::Expression;
 use crate::token::token::Token;
 
@@ -30,22 +34,70 @@ impl AST for Program {
     }
 
     fn string(&self) -> Box<str> {
-        let mut out = String::new();
-
-        for node in self.nodes.iter() {
-            out.push_str(&node.string());
-        }
-
-        Box::from(out)
+        self.nodes.iter()
+            .map(|node| node.string())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .into_boxed_str()
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Node {
     pub expression: Expression,
     // col: u32,
     // row: u32,
     pub(crate) token: Token,
+    pub(crate) resolution: Resolution,
+}
+
+// Where the evaluator finds the value of an identifier, filled in by the resolver
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum Address {
+    // Looked up by name through the chain of environments
+    #[default]
+    Dynamic,
+    // Slot in the frame the given number of scopes out
+    Local(usize, usize),
+    Builtin(usize),
+}
+
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Resolution {
+    pub address: Cell<Address>,
+    // Slots of the frame of the scope this node is the body of
+    pub scope: OnceCell<Rc<Layout>>,
+}
+
+// Names of the slots of a frame, new names get the next slot
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Layout {
+    names: Vec<Rc<str>>,
+    slots: HashMap<Rc<str>, usize>,
+}
+
+impl Layout {
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.slots.get(name).copied()
+    }
+
+    // Slot of the name, added after the others if it has none
+    pub fn add(&mut self, name: &Rc<str>) -> usize {
+        if let Some(slot) = self.slot(name) {
+            return slot;
+        }
+        self.names.push(name.clone());
+        self.slots.insert(name.clone(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    pub fn names(&self) -> &[Rc<str>] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
 }
 
 impl AST for Node {
@@ -53,7 +105,237 @@ impl AST for Node {
         &self.token.literal
     }
 
+    // Source code that parses back to the node
     fn string(&self) -> Box<str> {
-        self.expression.string(&self.token.literal)
+        match &self.expression {
+            Expression::ExpressionLiteral(nodes) => format!("({})", join(nodes.iter())),
+            Expression::Set(variables) => format!("(set {})", variables.iter()
+                .map(|(identifier, value)| format!("({} {})", identifier.string(), value.string()))
+                .collect::<Vec<_>>()
+                .join(" ")),
+            Expression::Identifier(name) => name.to_string(),
+            Expression::Integer(value) => value.to_string(),
+            Expression::Float(value) => float_literal(*value),
+            Expression::Boolean(value) => value.to_string(),
+            Expression::String(value) => string_literal(value),
+            // Expressions are read from the string after it's unescaped, so they're escaped too
+            Expression::Interpolation(parts) => format!("\"{}\"", parts.iter()
+                .map(|part| match &part.expression {
+                    Expression::String(text) => escape_braces(&escape(text)),
+                    _ => format!("{{{}}}", escape(&part.string())),
+                })
+                .collect::<String>()),
+            Expression::Array(nodes) => format!("[{}]", join(nodes.iter())),
+            Expression::Index(index, operand) => format!("(@ {} {})", index.string(), operand.string()),
+            Expression::Operator(operator, operands) if operands.is_empty() => format!("({operator})"),
+            Expression::Operator(operator, operands) => format!("({operator} {})", join(operands.iter())),
+            Expression::If(condition, consequence, alternative) => format!("(if {})",
+                join([condition, consequence].into_iter().chain(alternative.as_ref()).map(AsRef::as_ref))),
+            Expression::When(branches) => format!("(when {})", join(branches.iter()
+                .flat_map(|(condition, consequence)| [condition.as_ref(), consequence.as_ref()]))),
+            Expression::Match(value, branches) => format!("(match {} {})", value.string(), branches.iter()
+                .map(|(pattern, guard, consequence)| match guard {
+                    Some(guard) => format!("{pattern} if {} {}", guard.string(), consequence.string()),
+                    None => format!("{pattern} {}", consequence.string()),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")),
+            Expression::For(binding, iterable, body) => format!("(for {} {} {})", binding.string(), iterable.string(), body.string()),
+            Expression::While(condition, loop_body) => format!("(while {})",
+                join([condition].into_iter().chain(loop_body.as_ref()).map(AsRef::as_ref))),
+            Expression::Function(params, vararg, body) => format!("(fn {} {})", parameters(params, vararg), body.string()),
+            Expression::Section(body) => format!("(§ {})", body.string()),
+            Expression::Include(target) => format!("(include {})", target.string()),
+            Expression::Test(name, body) => format!("(test {} {})", name.string(), body.string()),
+            Expression::Spread(operand) => format!("..{}", operand.string()),
+            Expression::Record(name, fields) => format!("(record {})", join(std::iter::once(name.as_ref()).chain(fields.iter()))),
+            Expression::Enum(name, variants) => format!("(enum {} {})", name.string(), variants.iter()
+                .map(|variant| match &variant.expression {
+                    Expression::Record(name, fields) => format!("({})", join(std::iter::once(name.as_ref()).chain(fields.iter()))),
+                    _ => variant.string().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")),
+            Expression::Macro(name, params, vararg, body) => format!("(macro {} {} {})", name.string(), parameters(params, vararg), body.string()),
+            Expression::Quote(node) => format!("(quote {})", node.string()),
+            Expression::Quasiquote(node) => format!("(quasiquote {})", node.string()),
+            Expression::Unquote(node) => format!("(unquote {})", node.string()),
+        }.into_boxed_str()
+    }
+}
+
+// Source of a string literal of the text, which reads back as the same text
+pub fn string_literal(text: &str) -> String {
+    format!("\"{}\"", escape_braces(&escape(text)))
+}
+
+// Source of a float literal, which always has a decimal point and never an exponent
+pub fn float_literal(value: f64) -> String {
+    let literal = value.to_string();
+    match value.is_finite() && !literal.contains('.') {
+        true => literal + ".0",
+        false => literal,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            character => character.to_string(),
+        })
+        .collect()
+}
+
+fn escape_braces(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+fn join<'a>(nodes: impl Iterator<Item = &'a Node>) -> String {
+    nodes.map(|node| node.string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parameters(params: &[Node], vararg: &Option<Node>) -> String {
+    let vararg = vararg.as_ref().map(|vararg| format!("...{}", vararg.string()));
+    format!("|{}|", params.iter()
+        .map(|param| param.string().to_string())
+        .chain(vararg)
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+impl Node {
+    pub fn children(&self) -> Vec<&Node> {
+        let mut children = Vec::new();
+        match &self.expression {
+            Expression::ExpressionLiteral(nodes)
+            | Expression::Array(nodes)
+            | Expression::Interpolation(nodes)
+            | Expression::Operator(_, nodes) => children.extend(nodes.iter()),
+            Expression::Set(variables) => variables.iter()
+                .for_each(|(identifier, value)| children.extend([identifier, value])),
+            Expression::Index(index, operand) => children.extend([index.as_ref(), operand.as_ref()]),
+            Expression::For(binding, iterable, body) => children.extend([binding.as_ref(), iterable.as_ref(), body.as_ref()]),
+            Expression::If(condition, consequence, alternative) => {
+                children.extend([condition.as_ref(), consequence.as_ref()]);
+                children.extend(alternative.as_deref());
+            }
+            Expression::When(branches) => branches.iter()
+                .for_each(|(condition, consequence)| children.extend([condition.as_ref(), consequence.as_ref()])),
+            Expression::Match(value, branches) => {
+                children.push(value);
+                for (pattern, guard, consequence) in branches.iter() {
+                    pattern.collect_nodes(&mut children);
+                    children.extend(guard.as_deref());
+                    children.push(consequence);
+                }
+            }
+            Expression::While(condition, loop_body) => {
+                children.push(condition);
+                children.extend(loop_body.as_deref());
+            }
+            Expression::Function(params, vararg, body) => {
+                children.extend(params.iter());
+                children.extend(vararg.as_ref().as_ref());
+                children.push(body);
+            }
+            Expression::Test(name, body) => children.extend([name.as_ref(), body.as_ref()]),
+            Expression::Record(name, fields) => {
+                children.push(name);
+                children.extend(fields.iter());
+            }
+            Expression::Enum(name, variants) => {
+                children.push(name);
+                children.extend(variants.iter());
+            }
+            Expression::Macro(name, params, vararg, body) => {
+                children.push(name);
+                children.extend(params.iter());
+                children.extend(vararg.as_ref().as_ref());
+                children.push(body);
+            }
+            Expression::Section(node)
+            | Expression::Include(node)
+            | Expression::Spread(node)
+            | Expression::Unquote(node) => children.push(node),
+            Expression::Quote(node)
+            | Expression::Quasiquote(node) => children.push(node),
+            Expression::Identifier(_)
+            | Expression::Integer(_)
+            | Expression::Float(_)
+            | Expression::Boolean(_)
+            | Expression::String(_) => {}
+        }
+        children
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        let mut children = Vec::new();
+        match &mut self.expression {
+            Expression::ExpressionLiteral(nodes)
+            | Expression::Array(nodes)
+            | Expression::Interpolation(nodes)
+            | Expression::Operator(_, nodes) => children.extend(nodes.iter_mut()),
+            Expression::Set(variables) => Rc::make_mut(variables).iter_mut()
+                .for_each(|(identifier, value)| children.extend([identifier, value])),
+            Expression::Index(index, operand) => children.extend([index.as_mut(), operand.as_mut()]),
+            Expression::For(binding, iterable, body) => children.extend([binding.as_mut(), iterable.as_mut(), body.as_mut()]),
+            Expression::If(condition, consequence, alternative) => {
+                children.extend([condition.as_mut(), consequence.as_mut()]);
+                children.extend(alternative.as_deref_mut());
+            }
+            Expression::When(branches) => branches.iter_mut()
+                .for_each(|(condition, consequence)| children.extend([condition.as_mut(), consequence.as_mut()])),
+            Expression::Match(value, branches) => {
+                children.push(value);
+                for (pattern, guard, consequence) in branches.iter_mut() {
+                    pattern.collect_nodes_mut(&mut children);
+                    children.extend(guard.as_deref_mut());
+                    children.push(consequence);
+                }
+            }
+            Expression::While(condition, loop_body) => {
+                children.push(condition);
+                children.extend(loop_body.as_deref_mut());
+            }
+            Expression::Function(params, vararg, body) => {
+                children.extend(Rc::make_mut(params).iter_mut());
+                children.extend(Rc::make_mut(vararg).as_mut());
+                children.push(Rc::make_mut(body));
+            }
+            Expression::Test(name, body) => children.extend([name.as_mut(), body.as_mut()]),
+            Expression::Record(name, fields) => {
+                children.push(name);
+                children.extend(Rc::make_mut(fields).iter_mut());
+            }
+            Expression::Enum(name, variants) => {
+                children.push(name);
+                children.extend(variants.iter_mut());
+            }
+            Expression::Macro(name, params, vararg, body) => {
+                children.push(name);
+                children.extend(Rc::make_mut(params).iter_mut());
+                children.extend(Rc::make_mut(vararg).as_mut());
+                children.push(Rc::make_mut(body));
+            }
+            Expression::Section(node)
+            | Expression::Include(node)
+            | Expression::Spread(node)
+            | Expression::Unquote(node) => children.push(node),
+            Expression::Quote(node)
+            | Expression::Quasiquote(node) => children.push(Rc::make_mut(node)),
+            Expression::Identifier(_)
+            | Expression::Integer(_)
+            | Expression::Float(_)
+            | Expression::Boolean(_)
+            | Expression::String(_) => {}
+        }
+        children
     }
 }
@@ -1,8 +1,12 @@
 use std::rc::Rc;
 
 use crate::ast::ast::Node;
+use crate::ast::pattern::Pattern;
 
-#[derive(Debug, PartialEq)]
+// Pattern of a match branch, its optional guard and the body evaluated when both match
+pub type MatchBranch = (Pattern, Option<Box<Node>>, Box<Node>);
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     ExpressionLiteral(Box<[Node]>),
     // Set(Box<Node>, Box<Node>),
@@ -12,26 +16,31 @@ pub enum Expression {
     Float(f64),
     Boolean(bool),
     String(Rc<str>),
+    // Parts of an interpolated string, Strings for the text and the expressions in between
+    Interpolation(Box<[Node]>),
     Array(Box<[Node]>),
     Index(Box<Node>, Box<Node>),
     Operator(Rc<str>, Box<[Node]>),
     If(Box<Node>, Box<Node>, Option<Box<Node>>),
     When(Box<[(Box<Node>, Box<Node>)]>),
+    Match(Box<Node>, Box<[MatchBranch]>),
     While(Box<Node>, Option<Box<Node>>),
+    // Binding, the array, string or sequence to iterate and the body evaluated for each value
+    For(Box<Node>, Box<Node>, Box<Node>),
     Function(Rc<[Node]>, Rc<Option<Node>>, Rc<Node>),
     Section(Box<Node>),
     Include(Box<Node>),
+    Test(Box<Node>, Box<Node>),
     Spread(Box<Node>),
+    // Name and fields, binds the name to the constructor of the record type
+    Record(Box<Node>, Rc<[Node]>),
+    // Name and variants, identifiers of variants without fields and records of those with fields.
+    // Binds the names of the variants to their values or constructors.
+    Enum(Box<Node>, Box<[Node]>),
+    // Name, parameters, vararg and body, expanded away before evaluation
+    Macro(Box<Node>, Rc<[Node]>, Rc<Option<Node>>, Rc<Node>),
+    Quote(Rc<Node>),
+    Quasiquote(Rc<Node>),
+    // Only allowed inside a quasiquote, splices the array when its operand is a spread
+    Unquote(Box<Node>),
 }
-
-impl Expression {
-
-    pub fn string(&self, _literal: &str) -> Box<str> {
-        match self {
-            // Expression::Set(name, value) => format!("{literal} {} = {}", name.string(), value.string()),
-            _ => String::new(), //FIXME Remove
-        }.into_boxed_str()
-    }
-}
-
-// struct Identifier(str);
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod ast;
-pub mod expression;
\ No newline at end of file
+pub mod expression;
+pub mod pattern;
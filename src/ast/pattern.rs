@@ -0,0 +1,63 @@
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use crate::ast::ast::{AST, Node};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    // Matches anything, written as `_`
+    Wildcard,
+    // Matches anything and binds it to the identifier of the node
+    Binding(Node),
+    // Integer, float, string, boolean or unit literal compared by value
+    Literal(Node),
+    // Matches arrays element wise, the optional rest pattern captures the remaining tail
+    Array(Box<[Pattern]>, Option<Box<Pattern>>),
+    // Matches when the type name of the value is equal, e.g. `n::Integer`
+    Type(Box<Pattern>, Rc<str>),
+}
+
+impl Pattern {
+    pub fn collect_nodes<'a>(&'a self, nodes: &mut Vec<&'a Node>) {
+        match self {
+            Pattern::Binding(node) | Pattern::Literal(node) => nodes.push(node),
+            Pattern::Array(elements, rest) => {
+                elements.iter().for_each(|element| element.collect_nodes(nodes));
+                if let Some(rest) = rest {
+                    rest.collect_nodes(nodes);
+                }
+            }
+            Pattern::Type(inner, _) => inner.collect_nodes(nodes),
+            Pattern::Wildcard => {}
+        }
+    }
+
+    pub fn collect_nodes_mut<'a>(&'a mut self, nodes: &mut Vec<&'a mut Node>) {
+        match self {
+            Pattern::Binding(node) | Pattern::Literal(node) => nodes.push(node),
+            Pattern::Array(elements, rest) => {
+                elements.iter_mut().for_each(|element| element.collect_nodes_mut(nodes));
+                if let Some(rest) = rest {
+                    rest.collect_nodes_mut(nodes);
+                }
+            }
+            Pattern::Type(inner, _) => inner.collect_nodes_mut(nodes),
+            Pattern::Wildcard => {}
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(node) | Pattern::Literal(node) => write!(f, "{}", node.string()),
+            Pattern::Array(elements, rest) => {
+                let rest = rest.iter().map(|rest| format!("..{rest}"));
+                let elements = elements.iter().map(|element| element.to_string()).chain(rest);
+                write!(f, "[{}]", elements.collect::<Vec<_>>().join(" "))
+            }
+            Pattern::Type(inner, type_name) => write!(f, "{inner}::{type_name}"),
+        }
+    }
+}
@@ -0,0 +1,682 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::rc::Rc;
+
+use crate::ast::ast::{Node, Program};
+use crate::ast::expression::Expression;
+use crate::ast::pattern::Pattern;
+use crate::evaluator::builtin::builtins;
+use crate::expander::expander::Expander;
+use crate::lexer::lexer::Lexer;
+use crate::parser::parser::Parser;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub col: u32,
+    pub row: u32,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        };
+        write!(f, "Row {}, Col: {}: {}: {}", self.row, self.col, severity, self.message)
+    }
+}
+
+// Row and column of a token
+pub type Position = (u32, u32);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum DefinitionKind {
+    Variable,
+    // Signature of the function literal bound by set, e.g. `|a b ...c|`
+    Function(Rc<str>),
+    Macro(Rc<str>),
+    Parameter,
+}
+
+#[derive(Debug)]
+pub struct Definition {
+    pub name: Rc<str>,
+    pub col: u32,
+    pub row: u32,
+    pub kind: DefinitionKind,
+    // Start and end of the scope the binding is visible in, None for the top-level scope
+    pub scope: Option<(Position, Position)>,
+}
+
+#[derive(Debug)]
+pub struct Reference {
+    pub name: Rc<str>,
+    pub col: u32,
+    pub row: u32,
+    // Position of the binding the identifier resolved to, None for builtins and unbound names
+    pub definition: Option<Position>,
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+    Unknown,
+}
+
+struct Binding {
+    col: u32,
+    row: u32,
+    used: bool,
+    arity: Arity,
+}
+
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<Rc<str>, Binding>,
+    range: Option<(Position, Position)>,
+}
+
+pub(crate) fn start(files: &[String]) {
+    let mut failed = false;
+
+    for file in files {
+        let Ok(content) = read_to_string(file) else {
+            eprintln!("Could not open: {file}");
+            failed = true;
+            continue;
+        };
+
+        let lexer = Lexer::from(content.as_str());
+        let parser = Parser::from(lexer);
+
+        let program = match parser.parse_program() {
+            Ok(program) => program,
+            Err(errors) => {
+                errors.iter()
+                    .for_each(|error| eprintln!("{file}: ERROR: {error}"));
+                failed = true;
+                continue;
+            }
+        };
+
+        for diagnostic in check(&program) {
+            failed |= diagnostic.severity == Severity::Error;
+            eprintln!("{file}: {diagnostic}");
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+pub fn check(program: &Program) -> Vec<Diagnostic> {
+    analyze(program).diagnostics
+}
+
+// Macro calls are checked as the code they expand to
+pub fn analyze(program: &Program) -> Analysis {
+    let program = match Expander::new().expand(program.nodes.to_vec().into()) {
+        Ok(program) => program,
+        Err(errors) => return Analysis {
+            diagnostics: errors.into_iter()
+                .map(|error| Diagnostic { col: error.col, row: error.row, severity: Severity::Error, message: error.message })
+                .collect(),
+            ..Analysis::default()
+        },
+    };
+
+    let mut checker = Checker {
+        scopes: Vec::new(),
+        analysis: Analysis::default(),
+        included: HashSet::new(),
+        variants: HashMap::new(),
+        evaluates: program.nodes.iter().any(contains_eval),
+        deferred: 0,
+    };
+
+    checker.scopes.push(Scope::default());
+    checker.declare_nodes(&program.nodes);
+    checker.check_nodes(&program.nodes);
+    checker.pop_scope();
+
+    checker.analysis.diagnostics.sort_by_key(|diagnostic| (diagnostic.row, diagnostic.col));
+    checker.analysis
+}
+
+struct Checker {
+    scopes: Vec<Scope>,
+    analysis: Analysis,
+    included: HashSet<String>,
+    // Name of every declared variant and of the enum it belongs to
+    variants: HashMap<Rc<str>, Rc<str>>,
+    // With eval anywhere in the program any name might be bound at runtime
+    evaluates: bool,
+    // Depth of function, macro and test bodies being checked, which run after the top level
+    deferred: usize,
+}
+
+impl Checker {
+
+    fn report(&mut self, node: &Node, severity: Severity, message: String) {
+        self.analysis.diagnostics.push(Diagnostic { col: node.token.col, row: node.token.row, severity, message });
+    }
+
+    fn push_scope(&mut self, node: &Node) {
+        self.scopes.push(Scope {
+            bindings: HashMap::new(),
+            range: Some(((node.token.row, node.token.col), end_of(node))),
+        });
+    }
+
+    fn current_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("checker scope stack is never empty while checking")
+    }
+
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        let mut unused = scope.bindings.into_iter()
+            .filter(|(name, binding)| !binding.used && !name.starts_with('_') && !is_generated(name))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, binding)| (binding.row, binding.col));
+
+        for (name, binding) in unused {
+            self.analysis.diagnostics.push(Diagnostic {
+                col: binding.col,
+                row: binding.row,
+                severity: Severity::Warning,
+                message: format!("Unused binding '{name}'"),
+            });
+        }
+    }
+
+    fn bind(&mut self, identifier: &Node, arity: Arity, kind: DefinitionKind) {
+        let Expression::Identifier(name) = &identifier.expression else {
+            return;
+        };
+        let scope = self.current_scope();
+        match scope.bindings.get_mut(name) {
+            Some(binding) if binding.arity != arity => binding.arity = Arity::Unknown,
+            Some(_) => {}
+            None => {
+                scope.bindings.insert(name.clone(), Binding {
+                    col: identifier.token.col,
+                    row: identifier.token.row,
+                    used: false,
+                    arity,
+                });
+                let scope = scope.range;
+                if is_generated(name) {
+                    return;
+                }
+                self.analysis.definitions.push(Definition {
+                    name: name.clone(),
+                    col: identifier.token.col,
+                    row: identifier.token.row,
+                    kind,
+                    scope,
+                });
+            }
+        }
+    }
+
+    fn resolve(&mut self, name: &Rc<str>) -> Option<&Binding> {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.bindings.get_mut(name) {
+                binding.used = true;
+                return Some(binding);
+            }
+        }
+        None
+    }
+
+    // Top-level bindings are declared up front so function bodies can refer to them, but the
+    // top level itself runs in source order. Returns the row of the binding if the identifier
+    // is evaluated before it.
+    fn bound_later(&self, name: &Rc<str>, node: &Node) -> Option<u32> {
+        if self.deferred > 0 || self.evaluates {
+            return None;
+        }
+        let (depth, binding) = self.scopes.iter().enumerate().rev()
+            .find_map(|(depth, scope)| scope.bindings.get(name).map(|binding| (depth, binding)))?;
+        let later = depth == 0 && (binding.row, binding.col) > (node.token.row, node.token.col);
+        later.then_some(binding.row)
+    }
+
+    // Collects every name bound by `set` in the current scope, so that function bodies
+    // can refer to bindings made after the function itself was defined.
+    fn declare_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.declare(node);
+        }
+    }
+
+    fn declare(&mut self, node: &Node) {
+        match &node.expression {
+            Expression::Set(variables) => {
+                for (identifier, value) in variables.iter() {
+                    self.declare(value);
+                    self.bind(identifier, arity_of(value), kind_of(value));
+                }
+            }
+            Expression::ExpressionLiteral(nodes)
+            | Expression::Array(nodes)
+            | Expression::Interpolation(nodes)
+            | Expression::Operator(_, nodes) => self.declare_nodes(nodes),
+            Expression::Index(index, operand) => {
+                self.declare(index);
+                self.declare(operand);
+            }
+            Expression::If(condition, consequence, alternative) => {
+                self.declare(condition);
+                self.declare(consequence);
+                if let Some(alternative) = alternative {
+                    self.declare(alternative);
+                }
+            }
+            Expression::When(branches) => {
+                for (condition, consequence) in branches.iter() {
+                    self.declare(condition);
+                    self.declare(consequence);
+                }
+            }
+            Expression::Match(value, _) => self.declare(value),
+            Expression::For(_, iterable, _) => self.declare(iterable),
+            Expression::While(condition, loop_body) => {
+                self.declare(condition);
+                if let Some(loop_body) = loop_body {
+                    self.declare(loop_body);
+                }
+            }
+            Expression::Include(target) => self.declare_include(node, target),
+            Expression::Spread(operand)
+            | Expression::Unquote(operand) => self.declare(operand),
+            Expression::Record(name, fields) => self.bind(name, Arity::Exact(fields.len()), DefinitionKind::Function(signature(fields, &None))),
+            Expression::Enum(name, variants) => self.declare_enum(name, variants),
+            Expression::Macro(name, params, vararg, _) => {
+                let arity = if vararg.is_some() { Arity::AtLeast(params.len()) } else { Arity::Exact(params.len()) };
+                self.bind(name, arity, DefinitionKind::Macro(signature(params, vararg)));
+            }
+            Expression::Quote(_)
+            | Expression::Quasiquote(_)
+            | Expression::Function(_, _, _)
+            | Expression::Section(_)
+            | Expression::Test(_, _)
+            | Expression::Identifier(_)
+            | Expression::Integer(_)
+            | Expression::Float(_)
+            | Expression::Boolean(_)
+            | Expression::String(_) => {}
+        }
+    }
+
+    fn declare_enum(&mut self, name: &Node, variants: &[Node]) {
+        for variant in variants.iter() {
+            let (variant, arity, kind) = match &variant.expression {
+                Expression::Record(variant, fields) if !fields.is_empty() =>
+                    (variant.as_ref(), Arity::Exact(fields.len()), DefinitionKind::Function(signature(fields, &None))),
+                Expression::Record(variant, _) => (variant.as_ref(), Arity::Unknown, DefinitionKind::Variable),
+                _ => (variant, Arity::Unknown, DefinitionKind::Variable),
+            };
+            let Expression::Identifier(variant_name) = &variant.expression else {
+                continue;
+            };
+            self.bind(variant, arity, kind);
+            // Variants are often only named in patterns, which are not references
+            self.resolve(variant_name);
+            self.variants.insert(variant_name.clone(), name.token.literal.clone());
+        }
+    }
+
+    fn declare_include(&mut self, node: &Node, target: &Node) {
+        let Expression::String(path) = &target.expression else {
+            self.report(target, Severity::Error, "Illegal include expression. Expected target to be a string.".to_string());
+            return;
+        };
+        // Guards against files including each other
+        if !self.included.insert(path.to_string()) {
+            return;
+        }
+
+        let Ok(content) = read_to_string(path.as_ref()) else {
+            self.included.remove(path.as_ref());
+            self.report(node, Severity::Error, format!("Include error. Could not open: {path}"));
+            return;
+        };
+        let lexer = Lexer::from(content.as_str());
+        let parser = Parser::from(lexer);
+        let Ok(program) = parser.parse_program() else {
+            self.included.remove(path.as_ref());
+            self.report(node, Severity::Error, format!("Could not include file '{path}'. Parse error."));
+            return;
+        };
+
+        // Positions inside the included file are meaningless to the including one
+        let definitions = self.analysis.definitions.len();
+        let diagnostics = self.analysis.diagnostics.len();
+        self.scopes.push(Scope::default());
+        self.declare_nodes(&program.nodes);
+        self.included.remove(path.as_ref());
+        self.analysis.definitions.truncate(definitions);
+        self.analysis.diagnostics.truncate(diagnostics);
+        let Some(included) = self.scopes.pop() else {
+            return;
+        };
+
+        // Bindings made by the included file are used by whoever includes it,
+        // so they are declared as used to not report the file's whole interface.
+        for (name, mut binding) in included.bindings {
+            binding.used = true;
+            binding.col = node.token.col;
+            binding.row = node.token.row;
+            self.current_scope().bindings.entry(name).or_insert(binding);
+        }
+    }
+
+    fn check_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.check(node);
+        }
+    }
+
+    fn check(&mut self, node: &Node) {
+        match &node.expression {
+            Expression::ExpressionLiteral(nodes) => self.check_expression_literal(nodes),
+            Expression::Set(variables) => {
+                for (_, value) in variables.iter() {
+                    self.check(value);
+                }
+            }
+            Expression::Identifier(name) => {
+                let definition = self.resolve(name)
+                    .map(|binding| (binding.row, binding.col));
+                self.analysis.references.push(Reference {
+                    name: name.clone(),
+                    col: node.token.col,
+                    row: node.token.row,
+                    definition,
+                });
+                if definition.is_none() && builtins(name).is_none() && !self.evaluates {
+                    self.report(node, Severity::Error, format!("No binding for identifier '{name}'"));
+                }
+                if let Some(row) = self.bound_later(name, node) {
+                    self.report(node, Severity::Error, format!("Identifier '{name}' is used before it's bound on row {row}"));
+                }
+            }
+            Expression::Array(nodes)
+            | Expression::Interpolation(nodes)
+            | Expression::Operator(_, nodes) => self.check_nodes(nodes),
+            Expression::Index(index, operand) => {
+                self.check(index);
+                self.check(operand);
+            }
+            Expression::If(condition, consequence, alternative) => {
+                self.check(condition);
+                self.check(consequence);
+                if let Some(alternative) = alternative {
+                    self.check(alternative);
+                }
+            }
+            Expression::When(branches) => self.check_when(branches),
+            Expression::Match(value, branches) => {
+                self.check(value);
+                // Guarded branches may not be taken
+                let patterns = branches.iter()
+                    .filter(|(_, guard, _)| guard.is_none())
+                    .map(|(pattern, _, _)| pattern);
+                self.check_exhaustive(node, patterns);
+                for (pattern, guard, consequence) in branches.iter() {
+                    self.push_scope(node);
+                    self.bind_pattern(pattern);
+                    if let Some(guard) = guard {
+                        self.declare(guard);
+                    }
+                    self.declare(consequence);
+                    if let Some(guard) = guard {
+                        self.check(guard);
+                    }
+                    self.check(consequence);
+                    self.pop_scope();
+                }
+            }
+            Expression::While(condition, loop_body) => {
+                self.check(condition);
+                if let Some(loop_body) = loop_body {
+                    self.check(loop_body);
+                }
+            }
+            Expression::For(binding, iterable, body) => {
+                self.check(iterable);
+                self.push_scope(node);
+                self.bind(binding, Arity::Unknown, DefinitionKind::Parameter);
+                self.declare(body);
+                self.check(body);
+                self.pop_scope();
+            }
+            Expression::Function(params, vararg, body) => {
+                self.push_scope(node);
+                for param in params.iter() {
+                    self.bind(param, Arity::Unknown, DefinitionKind::Parameter);
+                }
+                if let Some(vararg) = vararg.as_ref() {
+                    self.bind(vararg, Arity::Unknown, DefinitionKind::Parameter);
+                }
+                self.declare(body);
+                self.check_deferred(body);
+                self.pop_scope();
+            }
+            Expression::Section(section) => {
+                self.push_scope(node);
+                self.declare(section);
+                self.check(section);
+                self.pop_scope();
+            }
+            Expression::Test(name, body) => {
+                self.check(name);
+                self.push_scope(node);
+                self.declare(body);
+                self.check_deferred(body);
+                self.pop_scope();
+            }
+            Expression::Spread(operand)
+            | Expression::Unquote(operand) => self.check(operand),
+            Expression::Macro(name, params, vararg, body) => {
+                // Calls are expanded away before checking
+                if let Expression::Identifier(name) = &name.expression {
+                    self.resolve(name);
+                }
+                self.push_scope(node);
+                for param in params.iter().chain(vararg.as_ref().as_ref()) {
+                    self.bind(param, Arity::Unknown, DefinitionKind::Parameter);
+                }
+                self.declare(body);
+                self.check_deferred(body);
+                self.pop_scope();
+            }
+            Expression::Quasiquote(template) => self.check_unquotes(template),
+            Expression::Quote(_)
+            | Expression::Include(_)
+            | Expression::Record(_, _)
+            | Expression::Enum(_, _)
+            | Expression::Integer(_)
+            | Expression::Float(_)
+            | Expression::Boolean(_)
+            | Expression::String(_) => {}
+        }
+    }
+
+    fn check_deferred(&mut self, body: &Node) {
+        self.deferred += 1;
+        self.check(body);
+        self.deferred -= 1;
+    }
+
+    fn check_expression_literal(&mut self, nodes: &[Node]) {
+        self.check_nodes(nodes);
+
+        let Some(Node { expression: Expression::Identifier(name), .. }) = nodes.first() else {
+            return;
+        };
+        if nodes[1..].iter().any(|node| matches!(node.expression, Expression::Spread(_))) {
+            return;
+        }
+
+        let arguments = nodes.len() - 1;
+        let expected = match self.resolve(name).map(|binding| binding.arity) {
+            Some(Arity::Exact(expected)) if expected != arguments => format!("{expected}"),
+            Some(Arity::AtLeast(expected)) if expected > arguments => format!("at least {expected}"),
+            _ => return,
+        };
+        self.report(&nodes[0], Severity::Error,
+                    format!("{name}: wrong number of arguments. got={arguments}, want={expected}"));
+    }
+
+    // Only the unquoted parts of a template are evaluated where it's written
+    fn check_unquotes(&mut self, template: &Node) {
+        match &template.expression {
+            Expression::Unquote(operand) => self.check(operand),
+            Expression::Quote(_) => {}
+            _ => template.children().into_iter().for_each(|child| self.check_unquotes(child)),
+        }
+    }
+
+    fn check_when(&mut self, branches: &[(Box<Node>, Box<Node>)]) {
+        let mut always_taken = false;
+        for (condition, consequence) in branches.iter() {
+            if always_taken {
+                self.report(condition, Severity::Warning, "Unreachable when-branch".to_string());
+            } else if constant_truthiness(condition) == Some(false) {
+                self.report(consequence, Severity::Warning, "Unreachable when-branch, condition is never true".to_string());
+            }
+            always_taken |= constant_truthiness(condition) == Some(true);
+
+            self.check(condition);
+            self.check(consequence);
+        }
+    }
+
+    // Warns when a match on the variants of an enum leaves some of them out
+    fn check_exhaustive<'a>(&mut self, node: &Node, patterns: impl Iterator<Item = &'a Pattern>) {
+        let mut enum_name = None;
+        let mut handled = HashSet::new();
+        for pattern in patterns {
+            let type_name = match pattern {
+                Pattern::Wildcard | Pattern::Binding(_) => return,
+                Pattern::Type(inner, type_name) if matches!(inner.as_ref(), Pattern::Wildcard | Pattern::Binding(_)) => type_name,
+                _ => continue,
+            };
+            if enum_name == Some(type_name) {
+                return;
+            }
+            if let Some(variant_of) = self.variants.get(type_name) {
+                enum_name.get_or_insert(variant_of);
+                handled.insert(type_name);
+            }
+        }
+
+        let Some(enum_name) = enum_name else {
+            return;
+        };
+        let mut missing = self.variants.iter()
+            .filter(|(variant, variant_of)| *variant_of == enum_name && !handled.contains(variant))
+            .map(|(variant, _)| variant.as_ref())
+            .collect::<Vec<&str>>();
+        if missing.is_empty() {
+            return;
+        }
+        missing.sort_unstable();
+        let message = format!("Match on {enum_name} does not handle {}", missing.join(", "));
+        self.report(node, Severity::Warning, message);
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(identifier) => self.bind(identifier, Arity::Unknown, DefinitionKind::Parameter),
+            Pattern::Array(elements, rest) => {
+                for element in elements.iter() {
+                    self.bind_pattern(element);
+                }
+                if let Some(rest) = rest {
+                    self.bind_pattern(rest);
+                }
+            }
+            Pattern::Type(inner, _) => self.bind_pattern(inner),
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
+}
+
+fn contains_eval(node: &Node) -> bool {
+    let calls_eval = matches!(&node.expression, Expression::ExpressionLiteral(nodes)
+        if matches!(nodes.first(), Some(Node { expression: Expression::Identifier(name), .. }) if name.as_ref() == "eval"));
+    calls_eval || node.children().into_iter().any(contains_eval)
+}
+
+fn arity_of(value: &Node) -> Arity {
+    match &value.expression {
+        Expression::Function(params, vararg, _) if vararg.is_some() => Arity::AtLeast(params.len()),
+        Expression::Function(params, _, _) => Arity::Exact(params.len()),
+        _ => Arity::Unknown,
+    }
+}
+
+fn kind_of(value: &Node) -> DefinitionKind {
+    let Expression::Function(params, vararg, _) = &value.expression else {
+        return DefinitionKind::Variable;
+    };
+    DefinitionKind::Function(signature(params, vararg))
+}
+
+fn signature(params: &[Node], vararg: &Option<Node>) -> Rc<str> {
+    let mut names = params.iter()
+        .map(|param| param.token.literal.to_string())
+        .collect::<Vec<String>>();
+    if let Some(vararg) = vararg.as_ref() {
+        names.push(format!("...{}", vararg.token.literal));
+    }
+    format!("|{}|", names.join(" ")).into()
+}
+
+// Position right after the last token of the node and all of its children
+fn end_of(node: &Node) -> Position {
+    let own = (node.token.row, node.token.col + node.token.literal.chars().count() as u32);
+    node.children().into_iter()
+        .map(end_of)
+        .fold(own, |end, child| end.max(child))
+}
+
+// Names bound by macro templates are renamed to ones that can't be written in source
+fn is_generated(name: &str) -> bool {
+    name.contains('%')
+}
+
+fn constant_truthiness(condition: &Node) -> Option<bool> {
+    match &condition.expression {
+        Expression::Boolean(value) => Some(*value),
+        Expression::Integer(value) => Some(*value != 0),
+        Expression::Float(value) => Some(*value != 0.0),
+        Expression::String(_) => Some(true),
+        Expression::ExpressionLiteral(nodes) if nodes.is_empty() => Some(true),
+        Expression::ExpressionLiteral(nodes) if nodes.len() == 1 => constant_truthiness(&nodes[0]),
+        _ => None,
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod test {
+    use crate::checker::checker::{check, Diagnostic, Severity};
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    #[test]
+    fn test_unbound_identifiers() {
+        let tests = [
+            ("(+ 1 x)", vec![(1, 6, "No binding for identifier 'x'")]),
+            ("(println undefined_name)", vec![(1, 10, "No binding for identifier 'undefined_name'")]),
+            ("(set (x 1)) (println x)", vec![]),
+            ("(set (f (fn |a| (+ a b))))(f 1)", vec![(1, 22, "No binding for identifier 'b'")]),
+            ("(set (f (fn |a| (+ a b))) (b 2))(f 1)", vec![]),
+            ("(set (f (fn || (g))) (g (fn || 1)))(f)", vec![]),
+            ("(§(set (x 1))) (println x)", vec![(1, 25, "No binding for identifier 'x'")]),
+            ("(match [1 2] [a ..rest] (+ a (len rest)))", vec![]),
+            ("(match 1 a 1) (println a)", vec![(1, 24, "No binding for identifier 'a'")]),
+            ("(include \"samples/seven.kvist\") (println seven)", vec![]),
+            ("(eval \"(set (x 1))\") (println x)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let diagnostics = apply_check(input);
+            assert_diagnostics(Severity::Error, &expected, &diagnostics, input);
+        }
+    }
+
+    #[test]
+    fn test_use_before_binding() {
+        let tests = [
+            ("(println x)\n(set (x 1))", vec![(1, 10, "Identifier 'x' is used before it's bound on row 2")]),
+            ("(set (y (+ x 1)))\n(set (x 1))", vec![(1, 12, "Identifier 'x' is used before it's bound on row 2")]),
+            ("(set (x 1))\n(println x)\n(set (x 2))", vec![]),
+            ("(set (f (fn || x)))\n(set (x 1))\n(f)", vec![]),
+            ("(test \"x\" x)\n(set (x 1))", vec![]),
+            ("(match 1 n (+ n x))\n(set (x 1))", vec![(1, 17, "Identifier 'x' is used before it's bound on row 2")]),
+        ];
+
+        for (input, expected) in tests {
+            let diagnostics = apply_check(input);
+            assert_diagnostics(Severity::Error, &expected, &diagnostics, input);
+        }
+    }
+
+    #[test]
+    fn test_arity_errors() {
+        let tests = [
+            ("(set (add (fn |a b| (+ a b)))) (add 1 2)", vec![]),
+            ("(set (add (fn |a b| (+ a b)))) (add 1)", vec![(1, 33, "add: wrong number of arguments. got=1, want=2")]),
+            ("(set (add (fn |a b| (+ a b)))) (add 1 2 3)", vec![(1, 33, "add: wrong number of arguments. got=3, want=2")]),
+            ("(set (add (fn |a b| (+ a b)))) (add ..[1 2])", vec![]),
+            ("(set (sum (fn |a ...b| (+ a ..b)))) (sum 1 2 3)", vec![]),
+            ("(set (sum (fn |a ...b| (+ a ..b)))) (sum)", vec![(1, 38, "sum: wrong number of arguments. got=0, want=at least 1")]),
+            ("(set (f (fn |a| a))) (set (f (fn |a b| a))) (f 1)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let diagnostics = apply_check(input);
+            assert_diagnostics(Severity::Error, &expected, &diagnostics, input);
+        }
+    }
+
+    #[test]
+    fn test_warnings() {
+        let tests = [
+            ("(set (x 1))", vec![(1, 7, "Unused binding 'x'")]),
+            ("(set (_x 1))", vec![]),
+            ("(set (f (fn |a b| a))) (f 1 2)", vec![(1, 16, "Unused binding 'b'")]),
+            ("(match 1 n 2)", vec![(1, 10, "Unused binding 'n'")]),
+            ("(when (true) 1 (false) 2)", vec![(1, 16, "Unreachable when-branch")]),
+            ("(when (= 1 1) 1 () 2 (true) 3)", vec![(1, 22, "Unreachable when-branch")]),
+            ("(when false 1 () 2)", vec![(1, 14, "Unreachable when-branch, condition is never true")]),
+            ("(enum S A B C) (match A _::A 1)", vec![(1, 17, "Match on S does not handle B, C")]),
+            ("(enum S (A v) B) (match B a::A a _::B 2)", vec![]),
+            ("(enum S A B) (match A _::A 1 _::B if false 2)", vec![(1, 15, "Match on S does not handle B")]),
+            ("(enum S A B) (match A _::A 1 _::S 2)", vec![]),
+            ("(enum S A B) (match A _::A 1 _ 2)", vec![]),
+            ("(enum S A B) (match 1 n::Integer n)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let diagnostics = apply_check(input);
+            assert_diagnostics(Severity::Warning, &expected, &diagnostics, input);
+        }
+    }
+
+    fn apply_check(input: &str) -> Vec<Diagnostic> {
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+
+        check(&program)
+    }
+
+    fn assert_diagnostics(severity: Severity, expected: &[(u32, u32, &str)], diagnostics: &[Diagnostic], input: &str) {
+        let actual = diagnostics.iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .map(|diagnostic| (diagnostic.row, diagnostic.col, diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual.as_slice(), "Unexpected diagnostics for: {input}");
+    }
+}
@@ -0,0 +1,4 @@
+pub mod checker;
+
+#[cfg(test)]
+mod checker_test;
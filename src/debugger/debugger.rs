@@ -0,0 +1,325 @@
+use std::cell::RefCell;
+use std::fs::read_to_string;
+use std::io::{BufRead, Write};
+
+use crate::ast::ast::Node;
+use crate::ast::expression::Expression;
+use crate::evaluator::evaluator::Eval;
+use crate::expander::expander::Expander;
+use crate::lexer::lexer::Lexer;
+use crate::object::environment::Environment;
+use crate::object::object::Viewable;
+use crate::parser::parser::Parser;
+use crate::optimizer::optimizer;
+use crate::resolver::resolver::resolve;
+
+const HELP: &str = "\
+Commands:
+    c, continue         Continue until the next breakpoint
+    s, step             Step into the next expression
+    n, next             Step over the current expression
+    o, out              Step out of the current expression
+    b, break <file:row> Add a breakpoint, the file defaults to the current one
+    d, delete <file:row> Remove a breakpoint
+    l, list             List breakpoints
+    e, env              Print the environment chain of the current scope
+    bt, stack           Print the call stack
+    p, print <expr>     Evaluate an expression in the current scope
+    q, quit             Exit the program
+    h, help             Print this help";
+
+thread_local! {
+    static DEBUGGER: RefCell<Option<Debugger>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Mode {
+    Continue,
+    StepInto,
+    // Pause at the next expression not nested deeper than the given depth
+    StepOver(usize),
+    // Pause at the next expression less nested than the given depth
+    StepOut(usize),
+}
+
+struct Frame {
+    name: String,
+    file: String,
+    row: u32,
+    col: u32,
+}
+
+pub struct Debugger {
+    breakpoints: Vec<(String, u32)>,
+    mode: Mode,
+    depth: usize,
+    stack: Vec<Frame>,
+    files: Vec<String>,
+    last_location: Option<(String, u32)>,
+    pause_requested: bool,
+    suspended: bool,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+enum Command {
+    Resume,
+    Prompt,
+}
+
+impl Debugger {
+
+    pub fn new(breakpoints: Vec<(String, u32)>, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Debugger {
+        // Without breakpoints there would be no way to ever pause, so start paused instead
+        let mode = if breakpoints.is_empty() { Mode::StepInto } else { Mode::Continue };
+        Debugger {
+            breakpoints,
+            mode,
+            depth: 0,
+            stack: Vec::new(),
+            files: Vec::new(),
+            last_location: None,
+            pause_requested: false,
+            suspended: false,
+            input,
+            output,
+        }
+    }
+
+    fn current_file(&self) -> String {
+        self.files.last().cloned().unwrap_or("<input>".to_string())
+    }
+
+    fn should_pause(&mut self, node: &Node) -> bool {
+        let location = (self.current_file(), node.token.row);
+        let arrived = self.last_location.as_ref() != Some(&location);
+        let breakpoint = arrived && self.breakpoints.contains(&location);
+        self.last_location = Some(location);
+
+        breakpoint || match self.mode {
+            Mode::Continue => false,
+            Mode::StepInto => true,
+            Mode::StepOver(depth) => self.depth <= depth,
+            Mode::StepOut(depth) => self.depth < depth,
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        // The debugger is best effort, a closed output should not stop the program
+        let _ = writeln!(self.output, "{text}");
+    }
+
+    fn read_command(&mut self) -> Option<String> {
+        let _ = write!(self.output, "(debug) ");
+        let _ = self.output.flush();
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    }
+
+    fn location(&self, node: &Node) -> String {
+        let file = self.current_file();
+        let source = read_to_string(&file).ok()
+            .and_then(|content| content.lines()
+                .nth(node.token.row.saturating_sub(1) as usize)
+                .map(|line| line.trim().to_string()))
+            .map(|line| format!("\n    {line}"))
+            .unwrap_or_default();
+        format!("Paused at {file}:{}:{}{source}", node.token.row, node.token.col)
+    }
+
+    fn stack_trace(&self, node: &Node) -> String {
+        let mut trace = format!("#0 at {}:{}:{}", self.current_file(), node.token.row, node.token.col);
+        for (index, frame) in self.stack.iter().rev().enumerate() {
+            trace += format!("\n#{} {} called from {}:{}:{}", index + 1, frame.name, frame.file, frame.row, frame.col).as_str();
+        }
+        trace
+    }
+
+    fn parse_breakpoint(&self, argument: &str) -> Option<(String, u32)> {
+        match argument.rsplit_once(':') {
+            Some((file, row)) => Some((file.to_string(), row.parse().ok()?)),
+            None => Some((self.current_file(), argument.parse().ok()?)),
+        }
+    }
+
+    fn execute(&mut self, command: &str, node: &Node, environment: &Environment) -> Command {
+        let (name, argument) = command.split_once(' ')
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((command, ""));
+        match name {
+            "c" | "continue" => {
+                self.mode = Mode::Continue;
+                return Command::Resume;
+            }
+            "s" | "step" => {
+                self.mode = Mode::StepInto;
+                return Command::Resume;
+            }
+            "n" | "next" => {
+                self.mode = Mode::StepOver(self.depth);
+                return Command::Resume;
+            }
+            "o" | "out" => {
+                self.mode = Mode::StepOut(self.depth);
+                return Command::Resume;
+            }
+            "b" | "break" => match self.parse_breakpoint(argument) {
+                Some(breakpoint) => {
+                    self.write(&format!("Breakpoint set at {}:{}", breakpoint.0, breakpoint.1));
+                    self.breakpoints.push(breakpoint);
+                }
+                None => self.write("Expected breakpoint as <file:row> or <row>"),
+            },
+            "d" | "delete" => match self.parse_breakpoint(argument) {
+                Some(breakpoint) => {
+                    self.breakpoints.retain(|existing| *existing != breakpoint);
+                    self.write(&format!("Breakpoint removed at {}:{}", breakpoint.0, breakpoint.1));
+                }
+                None => self.write("Expected breakpoint as <file:row> or <row>"),
+            },
+            "l" | "list" => {
+                let breakpoints = self.breakpoints.iter()
+                    .map(|(file, row)| format!("{file}:{row}"))
+                    .collect::<Vec<String>>();
+                self.write(&breakpoints.join("\n"));
+            }
+            "e" | "env" => self.write(&environment.view()),
+            "bt" | "stack" => {
+                let trace = self.stack_trace(node);
+                self.write(&trace);
+            }
+            "h" | "help" => self.write(HELP),
+            "q" | "quit" => std::process::exit(0),
+            "" => {}
+            _ => self.write(&format!("Unknown command '{command}', type 'help' for a list of commands")),
+        }
+        Command::Prompt
+    }
+}
+
+pub fn enable(debugger: Debugger) {
+    DEBUGGER.with_borrow_mut(|current| *current = Some(debugger));
+}
+
+fn with_debugger<R>(action: impl FnOnce(&mut Debugger) -> R) -> Option<R> {
+    DEBUGGER.with_borrow_mut(|debugger| debugger.as_mut()
+        .filter(|debugger| !debugger.suspended)
+        .map(action))
+}
+
+pub fn enter_node(node: &Node, environment: &mut Environment) {
+    let pause = with_debugger(|debugger| {
+        debugger.depth += 1;
+        debugger.should_pause(node)
+    });
+    if pause == Some(true) {
+        pause_at(node, environment);
+    }
+}
+
+pub fn exit_node(node: &Node, environment: &mut Environment) {
+    let pause = with_debugger(|debugger| {
+        debugger.depth = debugger.depth.saturating_sub(1);
+        std::mem::take(&mut debugger.pause_requested)
+    });
+    if pause == Some(true) {
+        pause_at(node, environment);
+    }
+}
+
+pub fn enter_function(node: &Node) {
+    with_debugger(|debugger| {
+        let name = match &node.expression {
+            Expression::Identifier(name) => name.to_string(),
+            _ => "(fn)".to_string(),
+        };
+        let file = debugger.current_file();
+        debugger.stack.push(Frame { name, file, row: node.token.row, col: node.token.col });
+    });
+}
+
+pub fn exit_function() {
+    with_debugger(|debugger| debugger.stack.pop());
+}
+
+pub fn enter_file(file: &str) {
+    with_debugger(|debugger| debugger.files.push(file.to_string()));
+}
+
+pub fn exit_file() {
+    with_debugger(|debugger| debugger.files.pop());
+}
+
+pub fn request_pause() {
+    with_debugger(|debugger| debugger.pause_requested = true);
+}
+
+fn pause_at(node: &Node, environment: &mut Environment) {
+    with_debugger(|debugger| {
+        debugger.mode = Mode::Continue;
+        let location = debugger.location(node);
+        debugger.write(&location);
+    });
+
+    loop {
+        let Some(Some(command)) = with_debugger(|debugger| debugger.read_command()) else {
+            // Input is closed, so let the program run to completion
+            return;
+        };
+
+        if let Some(expression) = command.strip_prefix("p ").or(command.strip_prefix("print ")) {
+            let result = evaluate(expression, environment);
+            with_debugger(|debugger| debugger.write(&result));
+            continue;
+        }
+
+        match with_debugger(|debugger| debugger.execute(&command, node, environment)) {
+            Some(Command::Prompt) => continue,
+            Some(Command::Resume) | None => return,
+        }
+    }
+}
+
+fn evaluate(input: &str, environment: &mut Environment) -> String {
+    let input = format!("({input})");
+    let lexer = Lexer::from(input.as_str());
+    let parser = Parser::from(lexer);
+    let program = match parser.parse_program() {
+        Ok(program) => program,
+        Err(errors) => return errors.iter()
+            .map(|error| format!("ERROR: {error}"))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+
+    let program = match Expander::new().expand(program) {
+        Ok(program) => program,
+        Err(errors) => return errors.iter()
+            .map(|error| format!("ERROR: {error}"))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+
+    let program = optimizer::apply(program);
+    let errors = resolve(&program, environment);
+    if !errors.is_empty() {
+        return errors.iter()
+            .map(|error| format!("ERROR: {error}"))
+            .collect::<Vec<String>>()
+            .join("\n");
+    }
+
+    // Evaluating in the paused scope should not trigger breakpoints itself
+    with_debugger(|debugger| debugger.suspended = true);
+    let result = program.eval(environment);
+    DEBUGGER.with_borrow_mut(|debugger| debugger.as_mut()
+        .map(|debugger| debugger.suspended = false));
+
+    match result {
+        Ok(object) => object.view(),
+        Err(error) => format!("ERROR: {error}"),
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::io::{Cursor, Write};
+    use std::rc::Rc;
+
+    use crate::debugger::debugger::{enable, Debugger};
+    use crate::evaluator::evaluator::Eval;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::environment::Environment;
+    use crate::object::object::Object;
+    use crate::parser::parser::Parser;
+
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_breakpoint_builtin() {
+        let input = "(set (add (fn |a b| ((breakpoint) (+ a b))))) (set (x 5)) (add x 2)";
+        let (result, output) = apply_debug(input, vec![("<input>".to_string(), 2)], "p (+ a b)\nbt\nc\n");
+
+        assert_eq!(Object::Integer(7), result);
+        assert!(output.contains("(debug) 7\n"), "Expected evaluated expression in: {output}");
+        assert!(output.contains("#1 add called from <input>:1:60"), "Expected call stack in: {output}");
+        assert_eq!(1, output.matches("Paused at").count(), "Expected exactly one pause in: {output}");
+    }
+
+    #[test]
+    fn test_breakpoint_by_row() {
+        let input = "(set (x 1))\n(set (x (+ x 1)))\n(set (x (* x 10)))\nx";
+        let (result, output) = apply_debug(input, vec![("<input>".to_string(), 3)], "p x\ne\nc\n");
+
+        assert_eq!(Object::Integer(20), result);
+        assert!(output.contains("Paused at <input>:3:2"), "Expected pause on row 3 in: {output}");
+        assert!(output.contains("(debug) 2\n"), "Expected value before row 3 in: {output}");
+        assert!(output.contains("(x -> 2)"), "Expected environment in: {output}");
+    }
+
+    #[test]
+    fn test_stepping() {
+        let input = "(set (x 1))\n(set (y (+ x 1)))\n(set (z 3))";
+
+        let (_, output) = apply_debug(input, Vec::new(), "n\nn\nc\n");
+        assert!(output.contains("Paused at <input>:1:2"), "Expected pause at start in: {output}");
+        assert!(output.contains("Paused at <input>:2:2"), "Expected step over to row 2 in: {output}");
+        assert!(output.contains("Paused at <input>:3:2"), "Expected step over to row 3 in: {output}");
+        assert_eq!(3, output.matches("Paused at").count(), "Unexpected pauses in: {output}");
+
+        let (_, output) = apply_debug(input, Vec::new(), "n\ns\ns\nc\n");
+        assert!(output.contains("Paused at <input>:2:10\n"), "Expected step into row 2 in: {output}");
+        assert!(output.contains("Paused at <input>:2:12\n"), "Expected step into operand in: {output}");
+    }
+
+    fn apply_debug(input: &str, breakpoints: Vec<(String, u32)>, commands: &str) -> (Object, String) {
+        let output = Output::default();
+        enable(Debugger::new(breakpoints, Box::new(Cursor::new(commands.to_string())), Box::new(output.clone())));
+
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+        let result = program.eval(&mut Environment::new()).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input));
+
+        let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+        (result, output)
+    }
+}
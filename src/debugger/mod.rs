@@ -0,0 +1,4 @@
+pub mod debugger;
+
+#[cfg(test)]
+mod debugger_test;
@@ -1,18 +1,52 @@
+// Builtins take their arguments as Box<[Object]> because that's the type Object::Builtin points to
+#![allow(clippy::boxed_local)]
+
+use crate::ast::ast::{AST, Node};
+use crate::ast::expression::Expression;
+use crate::debugger::debugger;
+use crate::evaluator::evaluator::{apply_function, collect as collect_sequence, length, slice as slice_of};
+use crate::evaluator::limits;
+use crate::evaluator::permissions::{self, Capability};
+use crate::evaluator::time;
+use crate::lexer::lexer::Lexer;
+use crate::parser::parser::Parser;
+use crate::profiler::profiler;
+use crate::object::cell::Cell;
+use crate::object::comparison::{equals, order, Key};
+use crate::object::environment::Environment;
 use crate::object::object::{Object, Viewable};
+use crate::object::sequence::{Sequence, Step};
+use crate::object::vector::Vector;
+use std::collections::HashSet;
 use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
 use std::rc::Rc;
 use std::{env, io};
 use std::env::VarError;
 
+// Builtin functions, then the constants bound to values after the semicolon
 macro_rules! builtins {
-   ($($name:ident),*$(,)?) => {
+   ($($name:ident),*$(,)?; $($constant:ident = $value:expr),*$(,)?) => {
        pub fn builtins(name: &str) -> Option<Object> {
            match name {
                $(stringify!($name) => Some(Object::Builtin($name)),)*
+               $(stringify!($constant) => Some($value),)*
                &_ => None
            }
        }
+
+       pub fn builtin_names() -> &'static [&'static str] {
+           &[$(stringify!($name),)* $(stringify!($constant),)*]
+       }
+
+       // Builtins by their position in builtin_names, as addressed by the resolver
+       pub fn builtin_at(index: usize) -> Option<Object> {
+           const BUILTINS: &[fn(Box<[Object]>) -> Result<Object, String>] = &[$($name,)*];
+           match BUILTINS.get(index) {
+               Some(builtin) => Some(Object::Builtin(*builtin)),
+               None => [$($value,)*].into_iter().nth(index - BUILTINS.len()),
+           }
+       }
    };
 }
 
@@ -25,24 +59,99 @@ builtins! {
     last,
     rest,
     push,
+    put,
+    slice,
     parse_int,
+    parse_float,
+    type_of,
+    is_unit,
+    is_int,
+    is_float,
+    is_number,
+    is_bool,
+    is_string,
+    is_array,
+    is_fn,
+    is_sequence,
+    is_code,
+    is_record,
+    is_atom,
+    to_string,
+    repr,
+    read,
+    eval,
+    to_int,
+    to_float,
+    to_bool,
+    format,
     os_execute,
     get_env,
     exit,
+    breakpoint,
+    assert,
+    assert_eq,
+    range,
+    iterate,
+    generate,
+    map,
+    filter,
+    take,
+    collect,
+    sort,
+    unique,
+    lines,
+    os_lines,
+    abs,
+    min,
+    max,
+    clamp,
+    pow,
+    sqrt,
+    exp,
+    log,
+    sin,
+    cos,
+    tan,
+    asin,
+    acos,
+    atan,
+    floor,
+    ceil,
+    round,
+    trunc,
+    now,
+    monotonic,
+    sleep,
+    format_time,
+    parse_time,
+    local_offset,
+    duration,
+    format_duration,
+    atom,
+    deref,
+    reset,
+    swap;
+    pi = Object::Float(std::f64::consts::PI),
+    e = Object::Float(std::f64::consts::E),
 }
 
 fn args(args: Box<[Object]>) -> Result<Object, String> {
     if args.len() != 0 {
         return Err(format!("args: wrong number of arguments. got={}, want=0", args.len()));
     }
-    let mut args: Vec<String> = env::args().collect();
-    args.remove(0);
+    // Options to the interpreter itself come before the script and are not part of its arguments
+    let args: Vec<String> = env::args()
+        .skip(1)
+        .skip_while(|arg| arg.starts_with("--"))
+        .collect();
 
+    limits::allocate(args.iter().map(|arg| arg.len()).sum())?;
+    limits::allocate_array(args.len())?;
     let args = args.into_iter()
         .map(|arg| Object::String(arg.into()))
         .collect::<Vec<Object>>();
 
-    return Ok(Object::Array(Rc::from(args)));
+    Ok(Object::Array(args.into()))
 }
 
 fn println(args: Box<[Object]>) -> Result<Object, String> {
@@ -60,12 +169,14 @@ fn readln(args: Box<[Object]>) -> Result<Object, String> {
     if args.len() != 0 {
         return Err(format!("readln: wrong number of arguments. got={}, want=0", args.len()));
     }
+    permissions::require(Capability::Stdin, "readln")?;
     let mut stdin = BufReader::new(io::stdin());
     let mut line: String = String::new();
     let Ok(_) = stdin.read_line(&mut line) else {
         return Err("readln: Failed to read line from standard input.".to_string());
     };
 
+    limits::allocate(line.len())?;
     Ok(Object::String(line.trim().into()))
 }
 
@@ -76,6 +187,7 @@ fn len(args: Box<[Object]>) -> Result<Object, String> {
     match &args[0] {
         Object::String(string) => Ok(Object::Integer(string.len() as i32)),
         Object::Array(array) => Ok(Object::Integer(array.len() as i32)),
+        Object::Sequence(sequence) => Ok(Object::Integer(collect_sequence(sequence.clone())?.len() as i32)),
         _ => Err(format!("len: argument to `len` not supported, got {}", &args[0])),
     }
 }
@@ -87,7 +199,8 @@ fn first(args: Box<[Object]>) -> Result<Object, String> {
 
     match &args[0] {
         Object::Array(array) => Ok(array.first().map(|object| object.clone()).unwrap_or(Object::Unit)),
-        _ => Err(format!("first: argument to `first` must be Array, got {}", &args[0])),
+        Object::Sequence(sequence) => Ok(sequence.force()?.map(|(first, _)| first).unwrap_or(Object::Unit)),
+        _ => Err(format!("first: argument to `first` must be Array or Sequence, got {}", &args[0])),
     }
 }
 
@@ -98,7 +211,8 @@ fn last(args: Box<[Object]>) -> Result<Object, String> {
 
     match &args[0] {
         Object::Array(array) => Ok(array.last().map(|object| object.clone()).unwrap_or(Object::Unit)),
-        _ => Err(format!("last: argument to `last` must be Array, got {}", &args[0])),
+        Object::Sequence(sequence) => Ok(collect_sequence(sequence.clone())?.last().cloned().unwrap_or(Object::Unit)),
+        _ => Err(format!("last: argument to `last` must be Array or Sequence, got {}", &args[0])),
     }
 }
 
@@ -108,12 +222,12 @@ fn rest(args: Box<[Object]>) -> Result<Object, String> {
     }
 
     match &args[0] {
-        Object::Array(array) => Ok(
-            array.get(1..)
-                .map(|slice| Object::Array(Rc::from(slice)))
-                .unwrap_or(Object::Array([].into()))
-        ),
-        _ => Err(format!("rest: argument to `rest` must be Array, got {}", &args[0])),
+        // The rest shares the values instead of copying them
+        Object::Array(array) => Ok(Object::Array(array.slice(array.len().min(1), array.len()))),
+        Object::Sequence(sequence) => Ok(sequence.force()?
+            .map(|(_, rest)| Object::Sequence(rest))
+            .unwrap_or(Object::Sequence(Sequence::from_values([].into())))),
+        _ => Err(format!("rest: argument to `rest` must be Array or Sequence, got {}", &args[0])),
     }
 }
 
@@ -124,45 +238,378 @@ fn push(args: Box<[Object]>) -> Result<Object, String> {
 
     match &args[0] {
         Object::Array(array) => {
-            let mut new = array.to_vec();
+            limits::allocate_array(1)?;
+            let mut new = array.clone();
             new.push(args[1].clone());
-            Ok(Object::Array(Rc::from(new)))
+            Ok(Object::Array(new))
         },
         _ => Err(format!("push: argument to `push` must be Array, got {}", &args[0])),
     }
 }
 
+fn put(args: Box<[Object]>) -> Result<Object, String> {
+    let [array, index, value] = &*args else {
+        return Err(format!("put: wrong number of arguments. got={}, want=3", args.len()));
+    };
+
+    match (array, index) {
+        (Object::Array(array), Object::Integer(index)) => {
+            let mut new = array.clone();
+            if *index < 0 || !new.set(*index as usize, value.clone()) {
+                return Err(format!("put: index '{index}' is out of bounds of length '{}'", array.len()));
+            }
+            Ok(Object::Array(new))
+        },
+        (Object::Array(_), index) => Err(format!("put: index must be Integer, got {index}")),
+        // Records are updated by the name of the field
+        (Object::Record(record_type, values), Object::String(field)) => {
+            let Some(index) = record_type.field(field) else {
+                return Err(format!("put: {} has no field '{field}'", record_type.name));
+            };
+            let mut new = values.to_vec();
+            new[index] = value.clone();
+            limits::allocate_array(new.len())?;
+            Ok(Object::Record(record_type.clone(), new.into()))
+        }
+        (Object::Record(..), field) => Err(format!("put: field must be String, got {field}")),
+        (array, _) => Err(format!("put: argument to `put` must be Array or Record, got {array}")),
+    }
+}
+
+fn slice(args: Box<[Object]>) -> Result<Object, String> {
+    let (operand, start, end) = match &*args {
+        [operand, Object::Integer(start)] => (operand, *start, None),
+        [operand, Object::Integer(start), Object::Integer(end)] => (operand, *start, Some(*end)),
+        [_, _] | [_, _, _] => return Err("slice: start and end must be Integer".to_string()),
+        _ => return Err(format!("slice: wrong number of arguments. got={}, want=2 or 3", args.len())),
+    };
+
+    match operand {
+        Object::Array(_) | Object::String(_) => slice_of(operand, start, end)
+            .ok_or(format!("slice: {start}..{} is out of bounds of length '{}'", end.map(|end| end.to_string()).unwrap_or_default(), length(operand))),
+        _ => Err(format!("slice: argument to `slice` must be Array or String, got {operand}")),
+    }
+}
+
+// Parses a string of digits in base 10, or in the given base from 2 to 36
 fn parse_int(args: Box<[Object]>) -> Result<Object, String> {
-    if args.len() != 1 {
-        return Err(format!("parse_int: wrong number of arguments. got={}, want=1", args.len()));
+    let (string, radix) = match &*args {
+        [Object::String(string)] => (string, 10),
+        [Object::String(string), Object::Integer(radix @ 2..=36)] => (string, *radix as u32),
+        [Object::String(_), Object::Integer(radix)] => return Err(format!("parse_int: radix must be from 2 to 36, got {radix}")),
+        [Object::String(_), radix] => return Err(format!("parse_int: radix must be Integer, got {radix}")),
+        [object] | [object, _] => return Err(format!("parse_int: argument to `parse_int` must be String, got {object}")),
+        _ => return Err(format!("parse_int: wrong number of arguments. got={}, want=1 or 2", args.len())),
+    };
+
+    i32::from_str_radix(string, radix)
+        .map(Object::Integer)
+        .map_err(|_| format!("parse_int: Number format error. Cannot convert \"{string}\" to Integer."))
+}
+
+fn parse_float(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::String(string)] => parse_float_string("parse_float", string),
+        [object] => Err(format!("parse_float: argument to `parse_float` must be String, got {object}")),
+        _ => Err(format!("parse_float: wrong number of arguments. got={}, want=1", args.len())),
     }
+}
 
-    match &args[0] {
-        Object::String(string) => {
-            let Ok(int) = string.parse::<i32>() else {
-                return Err(format!("parse_int: Number format error. Cannot convert \"{string}\" to int."));
+fn parse_float_string(name: &str, string: &str) -> Result<Object, String> {
+    string.trim().parse::<f64>()
+        .map(Object::Float)
+        .map_err(|_| format!("{name}: Number format error. Cannot convert \"{string}\" to Float."))
+}
+
+fn type_of(args: Box<[Object]>) -> Result<Object, String> {
+    let [object] = &*args else {
+        return Err(format!("type_of: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    Ok(Object::String(object.to_string().into()))
+}
+
+// Defines builtins testing whether their argument is of any of the types
+macro_rules! type_predicates {
+    ($($name:ident: $($pattern:pat_param)|+),*$(,)?) => {
+        $(fn $name(args: Box<[Object]>) -> Result<Object, String> {
+            let [object] = &*args else {
+                return Err(format!("{}: wrong number of arguments. got={}, want=1", stringify!($name), args.len()));
             };
-            Ok(Object::Integer(int))
+            Ok(Object::Boolean(matches!(object, $($pattern)|+)))
+        })*
+    };
+}
+
+type_predicates! {
+    is_unit: Object::Unit,
+    is_int: Object::Integer(_),
+    is_float: Object::Float(_),
+    is_number: Object::Integer(_) | Object::Float(_),
+    is_bool: Object::Boolean(_),
+    is_string: Object::String(_),
+    is_array: Object::Array(_),
+    is_fn: Object::Function(..) | Object::Builtin(_) | Object::Constructor(_),
+    is_record: Object::Record(..),
+    is_sequence: Object::Sequence(_),
+    is_code: Object::Code(_),
+    is_atom: Object::Atom(_),
+}
+
+fn to_string(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [string @ Object::String(_)] => Ok(string.clone()),
+        [object] => {
+            let view = object.view();
+            limits::allocate(view.len())?;
+            Ok(Object::String(view.into()))
+        }
+        _ => Err(format!("to_string: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+// Source code of a value, which read gives back the value of
+fn repr(args: Box<[Object]>) -> Result<Object, String> {
+    let [object] = &*args else {
+        return Err(format!("repr: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    let repr = object.repr().map_err(|message| format!("repr: {message}"))?;
+    limits::allocate(repr.len())?;
+    Ok(Object::String(repr.into()))
+}
+
+// Value of source code written as a literal, like repr writes it. Records are read with the
+// constructors bound where read is called, so only when read is called directly.
+fn read(args: Box<[Object]>) -> Result<Object, String> {
+    read_with(args, &|_| None)
+}
+
+pub(crate) fn read_in(args: Box<[Object]>, environment: &Environment) -> Result<Object, String> {
+    read_with(args, &|name| environment.get(name))
+}
+
+fn read_with(args: Box<[Object]>, lookup: &dyn Fn(&Rc<str>) -> Option<Object>) -> Result<Object, String> {
+    let source = match &*args {
+        [Object::String(source)] => source,
+        [object] => return Err(format!("read: argument to `read` must be String, got {object}")),
+        _ => return Err(format!("read: wrong number of arguments. got={}, want=1", args.len())),
+    };
+    let lexer = Lexer::from(source.as_ref());
+    let parser = Parser::from(lexer);
+    let program = parser.parse_program().map_err(|errors| format!("read: {}", errors.iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<String>>()
+        .join("; ")))?;
+    let [node] = &*program.nodes else {
+        return Err(format!("read: expected one value, got {}", program.nodes.len()));
+    };
+    limits::allocate(source.len())?;
+    read_value(node, lookup)
+}
+
+// Records are calls to their constructors and variants without fields their names
+fn read_value(node: &Node, lookup: &dyn Fn(&Rc<str>) -> Option<Object>) -> Result<Object, String> {
+    let not_a_value = || Err(format!("read: {} is not a value, eval evaluates code", node.string()));
+    match &node.expression {
+        Expression::ExpressionLiteral(nodes) if nodes.is_empty() => Ok(Object::Unit),
+        Expression::Integer(value) => Ok(Object::Integer(*value)),
+        Expression::Float(value) => Ok(Object::Float(*value)),
+        Expression::Boolean(value) => Ok(Object::Boolean(*value)),
+        Expression::String(value) => Ok(Object::String(value.clone())),
+        Expression::Array(nodes) => Ok(Object::Array(nodes.iter().map(|node| read_value(node, lookup)).collect::<Result<Vector, String>>()?)),
+        Expression::Quote(node) => Ok(Object::Code(node.clone())),
+        Expression::ExpressionLiteral(nodes) => {
+            let Expression::Identifier(name) = &nodes[0].expression else {
+                return not_a_value();
+            };
+            let Some(Object::Constructor(record_type)) = lookup(name) else {
+                return not_a_value();
+            };
+            let values = nodes[1..].iter().map(|node| read_value(node, lookup)).collect::<Result<Vec<Object>, String>>()?;
+            record_type.construct(values).map_err(|message| format!("read: {message}"))
+        }
+        Expression::Identifier(name) => match lookup(name) {
+            Some(Object::Record(record_type, values)) if record_type.variant_of.is_some() => Ok(Object::Record(record_type, values)),
+            _ => not_a_value(),
         },
-        object @ _ => Err(format!("parse_int: Cannot convert {} to int", object))
+        _ => not_a_value(),
     }
 }
 
-fn os_execute(args: Box<[Object]>) -> Result<Object, String> {
-    if args.is_empty() {
-        return Err("os_execute: no command to execute".to_string());
+// Evaluating needs the environment of the call, so calls are evaluated by the evaluator
+// and this is only called when eval is passed to other functions
+fn eval(_: Box<[Object]>) -> Result<Object, String> {
+    Err("eval: can only be called directly, not through another function".to_string())
+}
+
+// Floats are truncated towards zero, strings are parsed and booleans are 1 or 0
+fn to_int(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Integer(value)] => Ok(Object::Integer(*value)),
+        [Object::Float(value)] if (f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&value.trunc()) => Ok(Object::Integer(*value as i32)),
+        [Object::Float(value)] => Err(format!("to_int: {value} is out of range of Integer")),
+        [Object::String(string)] => string.trim().parse::<i32>()
+            .map(Object::Integer)
+            .map_err(|_| format!("to_int: Number format error. Cannot convert \"{string}\" to Integer.")),
+        [Object::Boolean(value)] => Ok(Object::Integer(i32::from(*value))),
+        [object] => Err(format!("to_int: Cannot convert {object} to Integer")),
+        _ => Err(format!("to_int: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+fn to_float(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Integer(value)] => Ok(Object::Float(f64::from(*value))),
+        [Object::Float(value)] => Ok(Object::Float(*value)),
+        [Object::String(string)] => parse_float_string("to_float", string),
+        [Object::Boolean(value)] => Ok(Object::Float(f64::from(u8::from(*value)))),
+        [object] => Err(format!("to_float: Cannot convert {object} to Float")),
+        _ => Err(format!("to_float: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+// Whether the value is truthy, the same as when it's used as a condition
+fn to_bool(args: Box<[Object]>) -> Result<Object, String> {
+    let [object] = &*args else {
+        return Err(format!("to_bool: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    Ok(Object::Boolean(object.is_truthy()))
+}
+
+// Replaces the placeholders of the template with the values, {{ and }} are literal braces.
+// Placeholders are {[position][:[[fill]align][width][.precision]]}, e.g. {}, {1}, {:>8}, {:*^9}, {:05} or {0:.2}.
+// Without a position they take the values in order, numbers are aligned right and other values left.
+fn format(args: Box<[Object]>) -> Result<Object, String> {
+    let (template, values) = match &*args {
+        [Object::String(template), values @ ..] => (template, values),
+        [object, ..] => return Err(format!("format: argument to `format` must be String, got {object}")),
+        [] => return Err("format: wrong number of arguments. got=0, want=1 or more".to_string()),
+    };
+
+    let mut result = String::new();
+    let mut next = 0;
+    let mut chars = template.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                result.push('}');
+            }
+            '}' => return Err("format: Unmatched } in template, write }} for a literal }".to_string()),
+            '{' => {
+                let rest = chars.as_str();
+                let Some(end) = rest.find('}') else {
+                    return Err("format: Unclosed { in template, write {{ for a literal {".to_string());
+                };
+                let placeholder = Placeholder::parse(&rest[..end])
+                    .ok_or_else(|| format!("format: Invalid placeholder {{{}}}", &rest[..end]))?;
+                let position = placeholder.position.unwrap_or_else(|| {
+                    next += 1;
+                    next - 1
+                });
+                let Some(value) = values.get(position) else {
+                    return Err(format!("format: No value for placeholder {position}, got {} values", values.len()));
+                };
+                placeholder.write(value, &mut result);
+                chars = rest[end + 1..].chars();
+            }
+            _ => result.push(char),
+        }
     }
 
-    fn valid_command(arg: &Object) -> Result<String, String> {
-        match arg {
-            object @ Object::String(_) => Ok(object.view()),
-            object @ Object::Boolean(_) => Ok(object.view()),
-            object @ Object::Float(_) => Ok(object.view()),
-            object @ Object::Integer(_) => Ok(object.view()),
-            _ => Err(arg.view()),
+    limits::allocate(result.len())?;
+    Ok(Object::String(result.into()))
+}
+
+struct Placeholder {
+    position: Option<usize>,
+    fill: char,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Placeholder {
+
+    fn parse(placeholder: &str) -> Option<Placeholder> {
+        let (position, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let mut result = Placeholder {
+            position: if position.is_empty() { None } else { Some(position.parse().ok()?) },
+            fill: ' ',
+            align: None,
+            zero: false,
+            width: 0,
+            precision: None,
+        };
+
+        let chars = spec.chars().collect::<Vec<_>>();
+        let mut index = match chars.as_slice() {
+            [fill, align @ ('<' | '>' | '^'), ..] => {
+                result.fill = *fill;
+                result.align = Some(*align);
+                2
+            }
+            [align @ ('<' | '>' | '^'), ..] => {
+                result.align = Some(*align);
+                1
+            }
+            _ => 0,
+        };
+        if result.align.is_none() && chars.get(index) == Some(&'0') {
+            result.zero = true;
+            index += 1;
+        }
+        let spec = &spec[chars[..index].iter().map(|char| char.len_utf8()).sum::<usize>()..];
+        let (width, precision) = spec.split_once('.').unwrap_or((spec, ""));
+        if !width.is_empty() {
+            result.width = width.parse().ok()?;
         }
+        if spec.contains('.') {
+            result.precision = Some(precision.parse().ok()?);
+        }
+        Some(result)
     }
 
+    fn write(&self, value: &Object, result: &mut String) {
+        let is_number = matches!(value, Object::Integer(_) | Object::Float(_));
+        let text = match (value, self.precision) {
+            (Object::Integer(value), Some(precision)) => format!("{:.precision$}", f64::from(*value)),
+            (Object::Float(value), Some(precision)) => format!("{value:.precision$}"),
+            (value, Some(precision)) => value.view().chars().take(precision).collect(),
+            (value, None) => value.view(),
+        };
+        let padding = self.width.saturating_sub(text.chars().count());
+
+        if self.zero && is_number {
+            let digits = text.strip_prefix('-').inspect(|_| result.push('-')).unwrap_or(&text);
+            result.extend(std::iter::repeat_n('0', padding));
+            result.push_str(digits);
+            return;
+        }
+        let fill = if self.zero { '0' } else { self.fill };
+        let before = match self.align {
+            Some('<') => 0,
+            Some('^') => padding / 2,
+            Some(_) => padding,
+            None if is_number || self.zero => padding,
+            None => 0,
+        };
+        result.extend(std::iter::repeat_n(fill, before));
+        result.push_str(&text);
+        result.extend(std::iter::repeat_n(fill, padding - before));
+    }
+}
+
+fn os_execute(args: Box<[Object]>) -> Result<Object, String> {
+    if args.is_empty() {
+        return Err("os_execute: no command to execute".to_string());
+    }
+    permissions::require(Capability::Run, "os_execute")?;
+
     let command = valid_command(&args[0])
         .map_err(|err| format!("os_execute: Invalid command '{err}' not allowed."))?;
 
@@ -186,15 +633,29 @@ fn os_execute(args: Box<[Object]>) -> Result<Object, String> {
     Ok(result)
 }
 
+fn valid_command(arg: &Object) -> Result<String, String> {
+    match arg {
+        object @ Object::String(_) => Ok(object.view()),
+        object @ Object::Boolean(_) => Ok(object.view()),
+        object @ Object::Float(_) => Ok(object.view()),
+        object @ Object::Integer(_) => Ok(object.view()),
+        _ => Err(arg.view()),
+    }
+}
+
 fn get_env(args: Box<[Object]>) -> Result<Object, String> {
     if args.len() != 1 {
         return Err(format!("env: wrong number of arguments. got={}, want=1", args.len()));
     }
+    permissions::require(Capability::Env, "get_env")?;
 
     match &args[0] {
         Object::String(string) => {
             match env::var(string.as_ref()) {
-                Ok(result) => Object::String(result.into()).into(),
+                Ok(result) => {
+                    limits::allocate(result.len())?;
+                    Object::String(result.into()).into()
+                },
                 Err(VarError::NotPresent) => Object::Unit.into(),
                 Err(err) => Err(format!("env: Invalid environment variable '{}': {}", string, err)),
             }
@@ -207,11 +668,598 @@ fn exit(args: Box<[Object]>) -> Result<Object, String> {
     if args.len() != 1 {
         return Err(format!("exit: wrong number of arguments. got={}, want=1", args.len()));
     }
+    permissions::require(Capability::Exit, "exit")?;
 
     match &args[0] {
         Object::Integer(integer) => {
+            profiler::finish();
             std::process::exit(*integer);
         },
         object @ _ => Err(format!("exit: Cannot use {} as exit code", object))
     }
 }
+
+fn breakpoint(args: Box<[Object]>) -> Result<Object, String> {
+    if !args.is_empty() {
+        return Err(format!("breakpoint: wrong number of arguments. got={}, want=0", args.len()));
+    }
+    debugger::request_pause();
+    Ok(Object::Unit)
+}
+
+fn assert(args: Box<[Object]>) -> Result<Object, String> {
+    if args.len() != 1 && args.len() != 2 {
+        return Err(format!("assert: wrong number of arguments. got={}, want=1 or 2", args.len()));
+    }
+    if args[0].is_truthy() {
+        return Ok(Object::Unit);
+    }
+    match args.get(1) {
+        Some(message) => Err(format!("assert: assertion failed: {}", message.view())),
+        None => Err(format!("assert: assertion failed, got {}", args[0].view())),
+    }
+}
+
+fn assert_eq(args: Box<[Object]>) -> Result<Object, String> {
+    if args.len() != 2 {
+        return Err(format!("assert_eq: wrong number of arguments. got={}, want=2", args.len()));
+    }
+    if equals(&args[0], &args[1]) {
+        return Ok(Object::Unit);
+    }
+
+    let expected = args[0].view();
+    let actual = args[1].view();
+    // Points out the first character where the two views differ
+    let difference = expected.chars()
+        .zip(actual.chars())
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    Err(format!("assert_eq: values are not equal\n    expected: {expected} ({})\n    actual:   {actual} ({})\n              {}^",
+                args[0], args[1], " ".repeat(difference)))
+}
+
+fn range(args: Box<[Object]>) -> Result<Object, String> {
+    let (start, end, step) = match *args {
+        [Object::Integer(end)] => (0, end, 1),
+        [Object::Integer(start), Object::Integer(end)] => (start, end, 1),
+        [Object::Integer(start), Object::Integer(end), Object::Integer(step)] => (start, end, step),
+        _ if args.is_empty() || args.len() > 3 => return Err(format!("range: wrong number of arguments. got={}, want=1 to 3", args.len())),
+        _ => return Err(format!("range: arguments must be Integer, got {}", args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>()
+            .join(" "))),
+    };
+    if step == 0 {
+        return Err("range: step must not be 0".to_string());
+    }
+
+    let mut next = Some(start);
+    Ok(Object::Sequence(Sequence::new(Box::new(move |_| {
+        let value = next.filter(|value| if step > 0 { *value < end } else { *value > end });
+        next = value.and_then(|value| value.checked_add(step));
+        Ok(value.map(Object::Integer))
+    }))))
+}
+
+// The seed followed by the function applied to the previous value, without end
+fn iterate(args: Box<[Object]>) -> Result<Object, String> {
+    let [function, seed] = &*args else {
+        return Err(format!("iterate: wrong number of arguments. got={}, want=2", args.len()));
+    };
+    let mut started = false;
+    Ok(Object::Sequence(Sequence::with_state(vec![function.clone(), seed.clone()], Box::new(move |state| {
+        let [function, previous] = state.as_mut_slice() else {
+            return Ok(None);
+        };
+        if started {
+            *previous = apply_function(function, vec![previous.clone()]).map_err(|error| error.to_string())?;
+        }
+        started = true;
+        Ok(Some(previous.clone()))
+    }))))
+}
+
+// Calls the function with a state for each value, it returns the value and the state for
+// the next call as `[value state]`, or Unit to end the sequence
+fn generate(args: Box<[Object]>) -> Result<Object, String> {
+    let [function, state] = &*args else {
+        return Err(format!("generate: wrong number of arguments. got={}, want=2", args.len()));
+    };
+    Ok(Object::Sequence(Sequence::with_state(vec![function.clone(), state.clone()], Box::new(|state| {
+        let [function, current] = state.as_slice() else {
+            return Ok(None);
+        };
+        match apply_function(function, vec![current.clone()]).map_err(|error| error.to_string())? {
+            Object::Unit => Ok(None),
+            Object::Array(pair) if pair.len() == 2 => {
+                state[1] = pair[1].clone();
+                Ok(Some(pair[0].clone()))
+            }
+            object => Err(format!("generate: function must return [value state] or Unit, got {}", object.view())),
+        }
+    }))))
+}
+
+fn map(args: Box<[Object]>) -> Result<Object, String> {
+    let [function, values] = &*args else {
+        return Err(format!("map: wrong number of arguments. got={}, want=2", args.len()));
+    };
+    let values = Object::Sequence(to_sequence("map", values)?);
+    Ok(Object::Sequence(Sequence::with_state(vec![function.clone(), values], Box::new(|state| {
+        let [function, values] = state.as_mut_slice() else {
+            return Ok(None);
+        };
+        let Some(value) = advance(values)? else {
+            return Ok(None);
+        };
+        apply_function(function, vec![value])
+            .map(Some)
+            .map_err(|error| error.to_string())
+    }))))
+}
+
+fn filter(args: Box<[Object]>) -> Result<Object, String> {
+    let [predicate, values] = &*args else {
+        return Err(format!("filter: wrong number of arguments. got={}, want=2", args.len()));
+    };
+    let values = Object::Sequence(to_sequence("filter", values)?);
+    Ok(Object::Sequence(Sequence::with_state(vec![predicate.clone(), values], Box::new(|state| {
+        let [predicate, values] = state.as_mut_slice() else {
+            return Ok(None);
+        };
+        while let Some(value) = advance(values)? {
+            let keep = apply_function(predicate, vec![value.clone()]).map_err(|error| error.to_string())?;
+            if keep.is_truthy() {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }))))
+}
+
+fn take(args: Box<[Object]>) -> Result<Object, String> {
+    let [Object::Integer(count), values] = &*args else {
+        return match args.len() {
+            2 => Err(format!("take: first argument to `take` must be Integer, got {}", &args[0])),
+            _ => Err(format!("take: wrong number of arguments. got={}, want=2", args.len())),
+        };
+    };
+    let mut remaining = *count;
+    let values = Object::Sequence(to_sequence("take", values)?);
+    Ok(Object::Sequence(Sequence::with_state(vec![values], Box::new(move |state| {
+        let [values] = state.as_mut_slice() else {
+            return Ok(None);
+        };
+        if remaining <= 0 {
+            return Ok(None);
+        }
+        remaining -= 1;
+        advance(values)
+    }))))
+}
+
+fn collect(args: Box<[Object]>) -> Result<Object, String> {
+    let [values] = &*args else {
+        return Err(format!("collect: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    Ok(Object::Array(collect_sequence(to_sequence("collect", values)?)?))
+}
+
+// Sorts any values, in the order of the keys the function gives when there is one
+fn sort(args: Box<[Object]>) -> Result<Object, String> {
+    let (values, key) = match &*args {
+        [values] => (values, None),
+        [values, key] => (values, Some(key)),
+        _ => return Err(format!("sort: wrong number of arguments. got={}, want=1 or 2", args.len())),
+    };
+    let values = collect_sequence(to_sequence("sort", values)?)?;
+
+    let mut keyed = Vec::with_capacity(values.len());
+    for value in values.iter() {
+        let key = match key {
+            Some(key) => apply_function(key, vec![value.clone()]).map_err(|error| error.to_string())?,
+            None => value.clone(),
+        };
+        keyed.push((key, value.clone()));
+    }
+    keyed.sort_by(|(left, _), (right, _)| order(left, right));
+    Ok(Object::Array(keyed.into_iter().map(|(_, value)| value).collect()))
+}
+
+// The values without those equal to an earlier one
+// Atoms and sequences are hashed by identity, so mutating them can't change their key
+#[allow(clippy::mutable_key_type)]
+fn unique(args: Box<[Object]>) -> Result<Object, String> {
+    let [values] = &*args else {
+        return Err(format!("unique: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    let mut seen = HashSet::new();
+    let mut unique = Vector::new();
+    for value in to_sequence("unique", values)?.iter() {
+        let value = value?;
+        if seen.insert(Key(value.clone())) {
+            limits::allocate_array(1)?;
+            unique.push(value);
+        }
+    }
+    Ok(Object::Array(unique))
+}
+
+// Lines of standard input, read as they are used
+fn lines(args: Box<[Object]>) -> Result<Object, String> {
+    if !args.is_empty() {
+        return Err(format!("lines: wrong number of arguments. got={}, want=0", args.len()));
+    }
+    permissions::require(Capability::Stdin, "lines")?;
+    Ok(Object::Sequence(Sequence::new(read_lines("lines", BufReader::new(io::stdin())))))
+}
+
+// Lines the command writes to stdout, read while it runs
+fn os_lines(args: Box<[Object]>) -> Result<Object, String> {
+    let Some((command, arguments)) = args.split_first() else {
+        return Err("os_lines: no command to execute".to_string());
+    };
+    permissions::require(Capability::Run, "os_lines")?;
+
+    let command = valid_command(command)
+        .map_err(|err| format!("os_lines: Invalid command '{err}' not allowed."))?;
+    let arguments = arguments.iter()
+        .map(|argument| valid_command(argument)
+            .map_err(|err| format!("os_lines: Invalid parameter '{err}' not allowed.")))
+        .collect::<Result<Vec<String>, String>>()?;
+    let mut child = Command::new(command)
+        .args(arguments)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .stdin(Stdio::inherit())
+        .spawn()
+        .map_err(|error| format!("os_lines: Command failed to execute '{error}'"))?;
+    let Some(stdout) = child.stdout.take() else {
+        return Err("os_lines: Could not read the output of the command".to_string());
+    };
+
+    let mut read = read_lines("os_lines", BufReader::new(stdout));
+    Ok(Object::Sequence(Sequence::new(Box::new(move |state| {
+        let line = read(state)?;
+        if line.is_none() {
+            let _ = child.wait();
+        }
+        Ok(line)
+    }))))
+}
+
+fn read_lines(name: &'static str, mut reader: impl BufRead + 'static) -> Step {
+    Box::new(move |_| {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                limits::allocate(line.len())?;
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                Ok(Some(Object::String(line.strip_suffix('\r').unwrap_or(line).into())))
+            }
+            Err(error) => Err(format!("{name}: Failed to read line: {error}")),
+        }
+    })
+}
+
+// Next value of the sequence in a state, which moves on to the rest of the sequence
+fn advance(values: &mut Object) -> Result<Option<Object>, String> {
+    let Object::Sequence(sequence) = values else {
+        return Ok(None);
+    };
+    let Some((value, rest)) = sequence.force()? else {
+        return Ok(None);
+    };
+    *values = Object::Sequence(rest);
+    Ok(Some(value))
+}
+
+fn to_sequence(name: &str, values: &Object) -> Result<Rc<Sequence>, String> {
+    match values {
+        Object::Sequence(sequence) => Ok(sequence.clone()),
+        Object::Array(array) => Ok(Sequence::from_values(array.clone())),
+        object => Err(format!("{name}: argument to `{name}` must be Array or Sequence, got {object}")),
+    }
+}
+
+fn abs(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Integer(value)] => value.checked_abs()
+            .map(Object::Integer)
+            .ok_or(format!("abs: {value} has no absolute value within Integer")),
+        [Object::Float(value)] => Ok(Object::Float(value.abs())),
+        [object] => Err(format!("abs: argument to `abs` must be Integer or Float, got {object}")),
+        _ => Err(format!("abs: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+fn min(args: Box<[Object]>) -> Result<Object, String> {
+    extreme("min", args, |value, extreme| value < extreme)
+}
+
+fn max(args: Box<[Object]>) -> Result<Object, String> {
+    extreme("max", args, |value, extreme| value > extreme)
+}
+
+// The first of the numbers that no other number replaces, keeping whether it's an Integer or Float
+fn extreme(name: &str, args: Box<[Object]>, replaces: fn(f64, f64) -> bool) -> Result<Object, String> {
+    let Some(first) = args.first() else {
+        return Err(format!("{name}: wrong number of arguments. got=0, want=at least 1"));
+    };
+    let mut extreme = (first, number(name, first)?);
+    for arg in args[1..].iter() {
+        let value = number(name, arg)?;
+        if replaces(value, extreme.1) {
+            extreme = (arg, value);
+        }
+    }
+    Ok(extreme.0.clone())
+}
+
+fn clamp(args: Box<[Object]>) -> Result<Object, String> {
+    let [value, low, high] = &*args else {
+        return Err(format!("clamp: wrong number of arguments. got={}, want=3", args.len()));
+    };
+    let bounds = (number("clamp", low)?, number("clamp", high)?);
+    if bounds.0 > bounds.1 {
+        return Err(format!("clamp: lower bound {} is greater than upper bound {}", low.view(), high.view()));
+    }
+    match number("clamp", value)? {
+        value if value < bounds.0 => Ok(low.clone()),
+        value if value > bounds.1 => Ok(high.clone()),
+        _ => Ok(value.clone()),
+    }
+}
+
+// Integers raised to a non-negative Integer stay Integers, everything else becomes a Float
+fn pow(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Integer(base), Object::Integer(exponent @ 0..)] => base.checked_pow(*exponent as u32)
+            .map(Object::Integer)
+            .ok_or(format!("pow: {base} to the power of {exponent} is out of range of Integer")),
+        [base, exponent] => Ok(Object::Float(number("pow", base)?.powf(number("pow", exponent)?))),
+        _ => Err(format!("pow: wrong number of arguments. got={}, want=2", args.len())),
+    }
+}
+
+fn sqrt(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("sqrt", args, |value| match value {
+        ..0. => Err(format!("sqrt: cannot take the square root of negative number {value}")),
+        _ => Ok(value.sqrt()),
+    })
+}
+
+fn exp(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("exp", args, |value| Ok(value.exp()))
+}
+
+// Natural logarithm, or the logarithm in the given base
+fn log(args: Box<[Object]>) -> Result<Object, String> {
+    let (value, base) = match &*args {
+        [value] => (number("log", value)?, std::f64::consts::E),
+        [value, base] => (number("log", value)?, number("log", base)?),
+        _ => return Err(format!("log: wrong number of arguments. got={}, want=1 or 2", args.len())),
+    };
+    if value <= 0. {
+        return Err(format!("log: cannot take the logarithm of non-positive number {value}"));
+    }
+    if base <= 0. || base == 1. {
+        return Err(format!("log: base must be positive and not 1, got {base}"));
+    }
+    Ok(Object::Float(value.log(base)))
+}
+
+fn sin(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("sin", args, |value| Ok(value.sin()))
+}
+
+fn cos(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("cos", args, |value| Ok(value.cos()))
+}
+
+fn tan(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("tan", args, |value| Ok(value.tan()))
+}
+
+fn asin(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("asin", args, |value| match value {
+        -1.0..=1.0 => Ok(value.asin()),
+        _ => Err(format!("asin: argument must be between -1 and 1, got {value}")),
+    })
+}
+
+fn acos(args: Box<[Object]>) -> Result<Object, String> {
+    float_function("acos", args, |value| match value {
+        -1.0..=1.0 => Ok(value.acos()),
+        _ => Err(format!("acos: argument must be between -1 and 1, got {value}")),
+    })
+}
+
+// Arc tangent of a value, or of y / x in the quadrant of the point (x, y)
+fn atan(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [value] => Ok(Object::Float(number("atan", value)?.atan())),
+        [y, x] => Ok(Object::Float(number("atan", y)?.atan2(number("atan", x)?))),
+        _ => Err(format!("atan: wrong number of arguments. got={}, want=1 or 2", args.len())),
+    }
+}
+
+fn floor(args: Box<[Object]>) -> Result<Object, String> {
+    integer_function("floor", args, f64::floor)
+}
+
+fn ceil(args: Box<[Object]>) -> Result<Object, String> {
+    integer_function("ceil", args, f64::ceil)
+}
+
+// Halfway values are rounded away from zero
+fn round(args: Box<[Object]>) -> Result<Object, String> {
+    integer_function("round", args, f64::round)
+}
+
+fn trunc(args: Box<[Object]>) -> Result<Object, String> {
+    integer_function("trunc", args, f64::trunc)
+}
+
+// Times are Floats of seconds since the Unix epoch and durations are Floats of seconds,
+// so they're added and subtracted like any numbers
+fn now(args: Box<[Object]>) -> Result<Object, String> {
+    if !args.is_empty() {
+        return Err(format!("now: wrong number of arguments. got={}, want=0", args.len()));
+    }
+    Ok(Object::Float(time::now()))
+}
+
+fn monotonic(args: Box<[Object]>) -> Result<Object, String> {
+    if !args.is_empty() {
+        return Err(format!("monotonic: wrong number of arguments. got={}, want=0", args.len()));
+    }
+    Ok(Object::Float(time::monotonic()))
+}
+
+fn sleep(args: Box<[Object]>) -> Result<Object, String> {
+    let [seconds] = &*args else {
+        return Err(format!("sleep: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    let seconds = number("sleep", seconds)?;
+    let duration = std::time::Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("sleep: duration must be a positive number of seconds, got {seconds}"))?;
+    std::thread::sleep(duration);
+    Ok(Object::Unit)
+}
+
+// RFC 3339 timestamp of a time, in UTC or at an offset in seconds east of UTC
+fn format_time(args: Box<[Object]>) -> Result<Object, String> {
+    let (time, offset) = match &*args {
+        [time] => (number("format_time", time)?, 0),
+        [time, Object::Integer(offset)] if offset.unsigned_abs() < 86_400 => (number("format_time", time)?, *offset),
+        [_, Object::Integer(offset)] => return Err(format!("format_time: offset must be less than a day, got {offset}")),
+        [_, offset] => return Err(format!("format_time: offset must be Integer, got {offset}")),
+        _ => return Err(format!("format_time: wrong number of arguments. got={}, want=1 or 2", args.len())),
+    };
+    if !time.is_finite() {
+        return Err(format!("format_time: Cannot format {time} as a time"));
+    }
+    Ok(Object::String(time::format_rfc3339(time, offset.into()).into()))
+}
+
+fn parse_time(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::String(timestamp)] => time::parse_rfc3339(timestamp.trim())
+            .map(Object::Float)
+            .ok_or_else(|| format!("parse_time: Invalid timestamp \"{timestamp}\", expected e.g. \"2024-05-01T12:30:00Z\"")),
+        [object] => Err(format!("parse_time: argument to `parse_time` must be String, got {object}")),
+        _ => Err(format!("parse_time: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+// Offset of the local time zone in seconds east of UTC, now or at a time
+fn local_offset(args: Box<[Object]>) -> Result<Object, String> {
+    let time = match &*args {
+        [] => time::now(),
+        [time] => number("local_offset", time)?,
+        _ => return Err(format!("local_offset: wrong number of arguments. got={}, want=0 or 1", args.len())),
+    };
+    // Within about 30 000 years of the epoch
+    if time.is_nan() || time.abs() >= 1e12 {
+        return Err(format!("local_offset: time {time} is out of range"));
+    }
+    Ok(Object::Integer(time::local_offset(time.floor() as i64) as i32))
+}
+
+// Seconds of an ISO 8601 duration such as PT1H30M
+fn duration(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::String(duration)] => time::parse_duration(duration.trim())
+            .map(Object::Float)
+            .ok_or_else(|| format!("duration: Invalid duration \"{duration}\", expected e.g. \"PT1H30M\" or \"P1DT12H\"")),
+        [object] => Err(format!("duration: argument to `duration` must be String, got {object}")),
+        _ => Err(format!("duration: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+fn format_duration(args: Box<[Object]>) -> Result<Object, String> {
+    let [seconds] = &*args else {
+        return Err(format!("format_duration: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    let seconds = number("format_duration", seconds)?;
+    if !seconds.is_finite() {
+        return Err(format!("format_duration: Cannot format {seconds} as a duration"));
+    }
+    Ok(Object::String(time::format_duration(seconds).into()))
+}
+
+fn number(name: &str, object: &Object) -> Result<f64, String> {
+    match object {
+        Object::Integer(value) => Ok(f64::from(*value)),
+        Object::Float(value) => Ok(*value),
+        object => Err(format!("{name}: argument to `{name}` must be Integer or Float, got {object}")),
+    }
+}
+
+fn float_function(name: &str, args: Box<[Object]>, function: impl Fn(f64) -> Result<f64, String>) -> Result<Object, String> {
+    let [value] = &*args else {
+        return Err(format!("{name}: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    Ok(Object::Float(function(number(name, value)?)?))
+}
+
+// Rounds a Float to an Integer, Integers are already whole
+fn integer_function(name: &str, args: Box<[Object]>, function: fn(f64) -> f64) -> Result<Object, String> {
+    let value = match &*args {
+        [Object::Integer(value)] => return Ok(Object::Integer(*value)),
+        [value] => function(number(name, value)?),
+        _ => return Err(format!("{name}: wrong number of arguments. got={}, want=1", args.len())),
+    };
+    if !(f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&value) {
+        return Err(format!("{name}: {value} is out of range of Integer"));
+    }
+    Ok(Object::Integer(value as i32))
+}
+
+fn atom(args: Box<[Object]>) -> Result<Object, String> {
+    let [value] = &*args else {
+        return Err(format!("atom: wrong number of arguments. got={}, want=1", args.len()));
+    };
+    Ok(Object::Atom(Cell::new(value.clone())))
+}
+
+fn deref(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Atom(cell)] => Ok(cell.get()),
+        [object] => Err(format!("deref: argument to `deref` must be Atom, got {object}")),
+        _ => Err(format!("deref: wrong number of arguments. got={}, want=1", args.len())),
+    }
+}
+
+// Replaces the value of the atom and returns the new value
+fn reset(args: Box<[Object]>) -> Result<Object, String> {
+    match &*args {
+        [Object::Atom(cell), value] => {
+            cell.set(value.clone());
+            Ok(value.clone())
+        }
+        [object, _] => Err(format!("reset: first argument to `reset` must be Atom, got {object}")),
+        _ => Err(format!("reset: wrong number of arguments. got={}, want=2", args.len())),
+    }
+}
+
+// Replaces the value of the atom with the function applied to it and any further arguments,
+// and returns the new value
+fn swap(args: Box<[Object]>) -> Result<Object, String> {
+    let [Object::Atom(cell), function, arguments @ ..] = &*args else {
+        return match args.first() {
+            Some(object) if args.len() >= 2 => Err(format!("swap: first argument to `swap` must be Atom, got {object}")),
+            _ => Err(format!("swap: wrong number of arguments. got={}, want=at least 2", args.len())),
+        };
+    };
+    // The function may use the atom too, so it isn't borrowed while the function runs
+    let arguments = std::iter::once(cell.get())
+        .chain(arguments.iter().cloned())
+        .collect();
+    let value = apply_function(function, arguments).map_err(|error| error.to_string())?;
+    cell.set(value.clone());
+    Ok(value)
+}
@@ -1,17 +1,30 @@
 use std::collections::VecDeque;
 use std::rc::Rc;
 
-use crate::ast::ast::{Node, Program};
+use crate::ast::ast::{Address, Node, Program};
 use crate::ast::expression::Expression;
-use crate::evaluator::builtin::builtins;
+use crate::debugger::debugger;
+use crate::profiler::profiler;
+use crate::evaluator::builtin::{self, builtin_at, builtins};
 use crate::evaluator::error::{EvaluationError, ToEvaluationError};
-use crate::evaluator::include::include_script;
+use crate::evaluator::include::{eval_source, include_script};
+use crate::evaluator::limits;
+use crate::evaluator::limits::Limits;
+use crate::evaluator::match_expression::eval_match_expression;
 use crate::evaluator::operator_expression::eval_operator_expression;
+use crate::evaluator::quote::eval_quasiquote;
 use crate::object::environment::Environment;
-use crate::object::object::Object;
+use crate::object::object::{Object, Viewable};
+use crate::object::record::RecordType;
+use crate::object::sequence::Sequence;
+use crate::object::vector::Vector;
 
 pub trait Eval {
     fn eval(&self, environment: &mut Environment) -> Result<Object, EvaluationError>;
+
+    fn eval_with_limits(&self, environment: &mut Environment, limits: Limits) -> Result<Object, EvaluationError> {
+        limits::with_limits(limits, || self.eval(environment))
+    }
 }
 
 impl Eval for Program {
@@ -27,45 +40,121 @@ impl Eval for Program {
 
 impl Eval for Node {
     fn eval(&self, environment: &mut Environment) -> Result<Object, EvaluationError> {
-        match &self.expression {
+        if let Err(message) = limits::step() {
+            return Err(self.to_error(message));
+        }
+        debugger::enter_node(self, environment);
+        let result = match &self.expression {
             Expression::ExpressionLiteral(nodes) => eval_expression_literal(nodes, environment),
             Expression::Set(variables) => eval_set(variables, environment),
-            Expression::Identifier(value) => eval_identifier(value, environment),
+            Expression::Identifier(value) => eval_identifier(self, value, environment),
             Expression::Integer(value) => Object::Integer(*value).into(),
             Expression::Float(value) => Object::Float(*value).into(),
             Expression::Boolean(value) => Object::Boolean(*value).into(),
             Expression::String(value) => Object::String(value.clone()).into(),
             Expression::Array(nodes) => eval_array_expression(nodes, environment),
+            Expression::Interpolation(parts) => eval_interpolation(parts, environment),
             Expression::Index(index, operands) => eval_index_expression(index.eval(environment)?, operands.eval(environment)?),
             Expression::Operator(operator, operands) => eval_operator_expression(&operator, &operands, environment),
             Expression::If(condition, consequence, alternative) => eval_if_expression(condition, consequence, alternative, environment),
             Expression::When(branches) => eval_when_expression(branches, environment),
+            Expression::Match(value, branches) => eval_match_expression(value, branches, environment),
             Expression::While(condition, None) => eval_while_expression(condition, environment),
             Expression::While(condition, Some(loop_body)) => eval_while_body_expression(condition, loop_body, environment),
+            Expression::For(binding, iterable, body) => eval_for_expression(binding, iterable.eval(environment)?, body, environment),
             Expression::Function(params, vararg, body) => Object::Function(params.clone(), vararg.clone(), body.clone(), environment.clone().into()).into(),
             Expression::Section(section) => eval_scope_section(section, environment),
             Expression::Include(target) => eval_include_expression(target, environment),
+            // Tests are only run by the test runner
+            Expression::Test(_, _) => Object::Unit.into(),
             Expression::Spread(operand) => eval_spread_expression(operand.eval(environment)?),
+            Expression::Record(name, fields) => eval_record_definition(name, fields, environment),
+            Expression::Enum(name, variants) => eval_enum_definition(name, variants, environment),
+            // Macros are defined when the program is expanded
+            Expression::Macro(_, _, _, _) => Object::Unit.into(),
+            Expression::Quote(node) => Object::Code(node.clone()).into(),
+            Expression::Quasiquote(template) => eval_quasiquote(template, environment),
+            Expression::Unquote(_) => Err("Unquote is only allowed inside a quasiquote".to_string().into()),
         }.map_err(|err| match err {
             EvaluationError::Simple(message) => self.to_error(message),
             err @ _ => err,
-        })
+        });
+        debugger::exit_node(self, environment);
+        result
     }
 }
 
+// Indices and slices of [start end] or [start] count from the end when they're negative
 fn eval_index_expression(index: Object, operand: Object) -> Result<Object, EvaluationError> {
     match (index, operand) {
-        // (Object::Integer(index), Object::Array(array)) => array.as_ref().get(index).unwrap_or(Err("Array index out of bounds")),
-        (Object::Integer(index), Object::Array(array)) => array.get(index as usize)
+        (Object::Integer(index), Object::Array(array)) => position(index, array.len())
+            .and_then(|position| array.get(position))
             .map(|object| object.clone())
             .ok_or(format!("Array index out of bounds index was '{index}' but length was '{}'.", array.len()).into()),
+        (Object::Integer(index), Object::String(string)) => {
+            let characters = string.chars().count();
+            position(index, characters)
+                .and_then(|position| string.chars().nth(position))
+                .map(|character| Object::String(character.to_string().into()))
+                .ok_or(format!("String index out of bounds index was '{index}' but length was '{characters}'.").into())
+        }
+        (Object::Array(range), operand @ (Object::Array(_) | Object::String(_))) => {
+            let (start, end) = match &*range.iter().collect::<Vec<&Object>>() {
+                [Object::Integer(start)] => (*start, None),
+                [Object::Integer(start), Object::Integer(end)] => (*start, Some(*end)),
+                _ => return Err(format!("Slice must be [start end] or [start] of Integers, got {}", Object::Array(range).view()).into()),
+            };
+            slice(&operand, start, end).ok_or(format!("Slice {} out of bounds of length '{}'.", Object::Array(range).view(), length(&operand)).into())
+        }
+        (Object::String(field), Object::Record(record_type, values)) => record_type.field(&field)
+            .map(|index| values[index].clone())
+            .ok_or(format!("{} has no field '{field}'", record_type.name).into()),
         (index @ _, operand @ _) => Err(format!("Index type '{index}' not allowed on '{operand}'.").into()),
     }
 }
 
-fn eval_spread_expression(operand: Object) -> Result<Object, EvaluationError> {
+// Position of an index that counts from the end when it's negative, None when out of bounds
+pub(crate) fn position(index: i32, length: usize) -> Option<usize> {
+    let position = match index {
+        0.. => index as usize,
+        _ => length.checked_sub(index.unsigned_abs() as usize)?,
+    };
+    (position < length).then_some(position)
+}
+
+// Values of an Array or characters of a String from start up to end, or to the last one without an end.
+// None when start comes after end or either is outside of the operand.
+pub(crate) fn slice(operand: &Object, start: i32, end: Option<i32>) -> Option<Object> {
+    let length = length(operand);
+    let bound = |index: i32| match index {
+        0.. => (index as usize <= length).then_some(index as usize),
+        _ => length.checked_sub(index.unsigned_abs() as usize),
+    };
+    let start = bound(start)?;
+    let end = end.map_or(Some(length), bound)?;
+    if start > end {
+        return None;
+    }
+    match operand {
+        Object::Array(array) => Some(Object::Array(array.slice(start, end))),
+        Object::String(string) => Some(Object::String(string.chars().skip(start).take(end - start).collect::<String>().into())),
+        _ => None,
+    }
+}
+
+// Number of values of an Array or characters of a String
+pub(crate) fn length(operand: &Object) -> usize {
+    match operand {
+        Object::Array(array) => array.len(),
+        Object::String(string) => string.chars().count(),
+        _ => 0,
+    }
+}
+
+pub(crate) fn eval_spread_expression(operand: Object) -> Result<Object, EvaluationError> {
     match operand {
         Object::Array(array) => Object::Spread(array).into(),
+        Object::Sequence(sequence) => Object::Spread(collect(sequence)?).into(),
         operand @ _ => Err(format!("Spread-operator not allowed on '{operand}'.").into()),
     }
 }
@@ -77,10 +166,27 @@ fn eval_expression_literal(nodes: &[Node], environment: &mut Environment) -> Res
 
     match node.eval(environment) {
         Ok(Object::Function(params, vararg, body, env)) => {
-            eval_function_call(node, params, vararg, nodes, body, &mut Environment::from(env), environment)
+            let mut function_environment = Environment::scope(env, &body);
+            eval_function_call(node, params, vararg, nodes, body, &mut function_environment, environment)
+        }
+        Ok(Object::Constructor(record_type)) => {
+            let mut values = Vec::new();
+            for arg in &nodes[1..] {
+                arg.eval(environment)?.expand_spread(|object| values.push(object));
+            }
+            limits::allocate_array(values.len()).map_err(|message| node.to_error(message))?;
+            record_type.construct(values).map_err(|message| node.to_error(message))
         }
         Ok(Object::Builtin(builtin)) => {
-            eval_builtin(builtin, &nodes[1..], environment)
+            let result = if is_call_to(node, "eval", environment) {
+                eval_eval(&nodes[1..], environment)
+            } else if is_call_to(node, "read", environment) {
+                let scope = environment.clone();
+                eval_builtin(|args| builtin::read_in(args, &scope), node, &nodes[1..], environment)
+            } else {
+                eval_builtin(builtin, node, &nodes[1..], environment)
+            };
+            result
                 .map_err(|err| match err {
                     EvaluationError::Simple(message) => node.to_error(message),
                     err @ _ => err,
@@ -114,7 +220,7 @@ fn eval_function_call(node: &Node, params: Rc<[Node]>, vararg: Rc<Option<Node>>,
 
         let value = args_queue.pop_front()
             .ok_or(node.to_error(format!("Missing parameter value for {name}")))?;
-        function_environment.set(name.clone(), value)
+        bind(param, name, value, function_environment)
     }
 
     if let Some(vararg_name) = vararg.as_ref() {
@@ -131,23 +237,63 @@ fn eval_function_call(node: &Node, params: Rc<[Node]>, vararg: Rc<Option<Node>>,
             node.eval(environment)?
                 .expand_spread(|object| args.push(object));
         }
-        function_environment.set(name.clone(), Object::Array(args.into()))
+        limits::allocate_array(args.len()).map_err(|message| node.to_error(message))?;
+        bind(vararg_name, name, Object::Array(args.into()), function_environment)
     }
 
-    body.eval(function_environment)
+    limits::enter_call().map_err(|message| node.to_error(message))?;
+    debugger::enter_function(node);
+    profiler::enter_function(node, &body);
+    let result = body.eval(function_environment);
+    profiler::exit();
+    debugger::exit_function();
+    limits::exit_call();
+    result
 }
 
-fn eval_builtin(builtin: fn(Box<[Object]>) -> Result<Object, String>, args: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
+// Builtins that need the environment of the call are recognized by the identifier of the
+// callee, like the resolver finds calls to eval, as the builtin itself can be passed around
+fn is_call_to(node: &Node, builtin: &str, environment: &Environment) -> bool {
+    let Expression::Identifier(name) = &node.expression else {
+        return false;
+    };
+    if name.as_ref() != builtin {
+        return false;
+    }
+    match node.resolution.address.get() {
+        Address::Builtin(_) => true,
+        Address::Dynamic => !environment.contains(name),
+        Address::Local(_, _) => false,
+    }
+}
+
+fn eval_builtin(builtin: impl FnOnce(Box<[Object]>) -> Result<Object, String>, node: &Node, args: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
     let mut param = Vec::new();
     for arg in args {
         arg.eval(environment)?.expand_spread(|object| param.push(object));
     }
-    match builtin(param.into()) {
+    profiler::enter_builtin(node);
+    let result = builtin(param.into());
+    profiler::exit();
+    match result {
         Ok(result) => result.into(),
         Err(message) => EvaluationError::Simple(message).into(),
     }
 }
 
+// Evaluates the source code the argument gives in the environment of the call
+fn eval_eval(args: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let mut param = Vec::new();
+    for arg in args {
+        arg.eval(environment)?.expand_spread(|object| param.push(object));
+    }
+    match param.as_slice() {
+        [Object::String(source)] => eval_source(source, environment),
+        [object] => Err(format!("eval: argument to `eval` must be String, got {object}").into()),
+        _ => Err(format!("eval: wrong number of arguments. got={}, want=1", param.len()).into()),
+    }
+}
+
 fn eval_expression_nodes(nodes: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
     let mut result = Object::Unit;
     for node in nodes {
@@ -159,7 +305,7 @@ fn eval_expression_nodes(nodes: &[Node], environment: &mut Environment) -> Resul
 }
 
 fn eval_scope_section(node: &Box<Node>, environment: &mut Environment) -> Result<Object, EvaluationError> {
-    node.eval(&mut Environment::from(Rc::from(environment.clone())))
+    node.eval(&mut Environment::scope(Rc::from(environment.clone()), node))
 }
 
 fn eval_include_expression(target: &Box<Node>, environment: &mut Environment) -> Result<Object, EvaluationError> {
@@ -173,7 +319,7 @@ fn eval_include_expression(target: &Box<Node>, environment: &mut Environment) ->
 fn eval_set(variables: &Rc<[(Node, Node)]>, environment: &mut Environment) -> Result<Object, EvaluationError> {
     let mut result = Object::Unit;
     for (identifier, value) in variables.iter() {
-        let Expression::Identifier(identifier) = &identifier.expression  else {
+        let Expression::Identifier(name) = &identifier.expression  else {
             return Err(identifier.to_error("Expected identifier for set-expression".to_string()));
         };
 
@@ -181,12 +327,52 @@ fn eval_set(variables: &Rc<[(Node, Node)]>, environment: &mut Environment) -> Re
             .spread_to_single()
             .unwrap_or(Object::Unit);
 
-        environment.set(identifier.clone(), result.clone());
+        bind(identifier, name, result.clone(), environment);
     }
 
     Ok(result)
 }
 
+fn eval_record_definition(name: &Node, fields: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let Expression::Identifier(type_name) = &name.expression else {
+        return Err(name.to_error("Expected identifier for record name".to_string()));
+    };
+    let record_type = RecordType {
+        name: type_name.clone(),
+        fields: fields.iter().map(|field| field.token.literal.clone()).collect(),
+        variant_of: None,
+    };
+    let constructor = Object::Constructor(record_type.into());
+    bind(name, type_name, constructor.clone(), environment);
+    Ok(constructor)
+}
+
+// Binds variants with fields to constructors and variants without fields to values
+fn eval_enum_definition(name: &Node, variants: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let enum_name = name.token.literal.clone();
+    for (index, variant) in variants.iter().enumerate() {
+        let (name, fields) = match &variant.expression {
+            Expression::Record(name, fields) => (name.as_ref(), fields.as_ref()),
+            _ => (variant, [].as_slice()),
+        };
+        let Expression::Identifier(variant_name) = &name.expression else {
+            return Err(name.to_error("Expected identifier for variant name".to_string()));
+        };
+        let record_type = Rc::new(RecordType {
+            name: variant_name.clone(),
+            fields: fields.iter().map(|field| field.token.literal.clone()).collect(),
+            variant_of: Some((enum_name.clone(), index)),
+        });
+        let value = if fields.is_empty() {
+            Object::Record(record_type, Rc::new([]))
+        } else {
+            Object::Constructor(record_type)
+        };
+        bind(name, variant_name, value, environment);
+    }
+    Ok(Object::Unit)
+}
+
 fn eval_if_expression(condition: &Box<Node>,
                       consequence: &Box<Node>,
                       alternative: &Option<Box<Node>>,
@@ -243,9 +429,90 @@ fn eval_while_body_expression(condition: &Box<Node>, loop_body: &Box<Node>, envi
     }
 }
 
+// Evaluates the body in a new scope for each value, so closures made in the body keep their own value
+fn eval_for_expression(binding: &Node, iterable: Object, body: &Node, environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let Expression::Identifier(name) = &binding.expression else {
+        return Err(binding.to_error("Expected identifier for for-expression".to_string()));
+    };
+    let values: Box<dyn Iterator<Item = Result<Object, String>>> = match iterable {
+        Object::Array(array) => Box::new((0..array.len()).map(move |index| Ok(array[index].clone()))),
+        Object::String(string) => Box::new(string.chars()
+            .map(|character| Ok(Object::String(character.to_string().into())))
+            .collect::<Vec<_>>()
+            .into_iter()),
+        Object::Sequence(sequence) => Box::new(sequence.iter()),
+        object => return Err(format!("Cannot iterate over {object}").into()),
+    };
+
+    let mut result = Object::Unit;
+    for value in values {
+        let mut scope = Environment::scope(Rc::from(environment.clone()), body);
+        bind(binding, name, value?, &mut scope);
+        result = body.eval(&mut scope)?;
+    }
+    Ok(result)
+}
+
+// Computes every value of a finite sequence
+pub(crate) fn collect(sequence: Rc<Sequence>) -> Result<Vector, String> {
+    let mut values = Vector::new();
+    for value in sequence.iter() {
+        limits::allocate_array(1)?;
+        values.push(value?);
+    }
+    Ok(values)
+}
 
-fn eval_identifier(identifier: &Rc<str>, environment: &mut Environment) -> Result<Object, EvaluationError> {
-    if let Some(value) = environment.get(identifier) {
+// Calls a function with arguments that are already evaluated, e.g. by the builtins taking functions
+pub(crate) fn apply_function(function: &Object, arguments: Vec<Object>) -> Result<Object, EvaluationError> {
+    let (params, vararg, body, env) = match function {
+        Object::Function(params, vararg, body, env) => (params, vararg, body, env),
+        Object::Builtin(builtin) => return builtin(arguments.into()).map_err(EvaluationError::from),
+        Object::Constructor(record_type) => {
+            limits::allocate_array(arguments.len())?;
+            return record_type.construct(arguments).map_err(EvaluationError::from);
+        }
+        object => return Err(format!("{object} is not a function").into()),
+    };
+    let mut function_environment = Environment::scope(env.clone(), body);
+    let mut arguments = VecDeque::from(arguments);
+    for param in params.iter() {
+        let Expression::Identifier(ref name) = param.expression else {
+            return Err(format!("Illegal function parameter type {param:?}").into());
+        };
+        let value = arguments.pop_front()
+            .ok_or(format!("Missing parameter value for {name}"))?;
+        bind(param, name, value, &mut function_environment);
+    }
+    if let Some(vararg_name @ Node { expression: Expression::Identifier(name), .. }) = vararg.as_ref() {
+        bind(vararg_name, name, Object::Array(Vec::from(arguments).into()), &mut function_environment);
+    }
+
+    limits::enter_call()?;
+    debugger::enter_function(body);
+    profiler::enter_function(body, body);
+    let result = body.eval(&mut function_environment);
+    profiler::exit();
+    debugger::exit_function();
+    limits::exit_call();
+    result
+}
+
+// Binds a value to the identifier in the current scope, in the slot the resolver gave it if any
+pub(crate) fn bind(identifier: &Node, name: &Rc<str>, value: Object, environment: &mut Environment) {
+    match identifier.resolution.address.get() {
+        Address::Local(_, slot) => environment.set_local(slot, name.clone(), value),
+        _ => environment.set(name.clone(), value),
+    }
+}
+
+fn eval_identifier(node: &Node, identifier: &Rc<str>, environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let value = match node.resolution.address.get() {
+        Address::Local(depth, slot) => environment.get_local(depth, slot, identifier),
+        Address::Builtin(index) => builtin_at(index),
+        Address::Dynamic => environment.get(identifier),
+    };
+    if let Some(value) = value {
         return Ok(value);
     }
     if let Some(value) = builtins(identifier.as_ref()) {
@@ -254,11 +521,25 @@ fn eval_identifier(identifier: &Rc<str>, environment: &mut Environment) -> Resul
     return Err(format!("No binding for identifier '{}'", identifier).into());
 }
 
+fn eval_interpolation(parts: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let mut string = String::new();
+    for part in parts {
+        match part.eval(environment)? {
+            Object::String(text) => string.push_str(&text),
+            object => string.push_str(&object.view()),
+        }
+    }
+    limits::allocate(string.len())?;
+
+    Object::String(string.into()).into()
+}
+
 fn eval_array_expression(nodes: &Box<[Node]>, environment: &mut Environment) -> Result<Object, EvaluationError> {
     let mut objects = Vec::new();
     for node in nodes.iter() {
         node.eval(environment)?.expand_spread(|object| objects.push(object));
     }
+    limits::allocate_array(objects.len())?;
 
-    return Object::Array(Rc::from(objects)).into();
+    Object::Array(objects.into()).into()
 }
\ No newline at end of file
@@ -1,15 +1,18 @@
-use std::rc::Rc;
 use crate::object::object::Object;
 
 #[cfg(test)]
 mod test {
     use std::rc::Rc;
+    use std::time::Duration;
     use crate::evaluator::error::EvaluationError;
     use crate::evaluator::evaluator::Eval;
+    use crate::evaluator::limits::Limits;
+    use crate::evaluator::permissions::{self, Permissions};
     use crate::lexer::lexer::Lexer;
     use crate::object::environment::Environment;
-    use crate::object::object::Object;
+    use crate::object::object::{Object, Viewable};
     use crate::parser::parser::Parser;
+    use crate::resolver::resolver::resolve;
 
     #[test]
     fn test_eval_integer_expressions() {
@@ -26,6 +29,13 @@ mod test {
             ("(- 6 3 1)", 2),
             ("(* 2 2 3)", 12),
             ("(/ 6 3 1)", 2),
+            ("(% 7 3)", 1),
+            ("(% -7 3)", 2),
+            ("(% 7 -3)", -2),
+            ("(% 20 7 4)", 2),
+            ("(// 7 2)", 3),
+            ("(// -7 2)", -4),
+            ("(// 100 5 3)", 6),
         ];
 
         for (input, expected) in tests {
@@ -53,6 +63,9 @@ mod test {
             ("(/ 1. 2)", 0.5),
             ("(/ 2)", 0.5),
             ("(/ 5 2)", 2.5),
+            ("(% 7.5 2)", 1.5),
+            ("(% -1 0.75)", 0.5),
+            ("(// 7.5 2)", 3.),
         ];
 
         for (input, expected) in tests {
@@ -92,6 +105,38 @@ mod test {
             ("(> 2 1)", true),
             ("(> 1 2 3)", false),
             ("(> 3 2 1)", true),
+            ("(<= 1 1 2)", true),
+            ("(<= 1 2 1)", false),
+            ("(<= 1.5 2)", true),
+            ("(>= 2 2 1)", true),
+            ("(>= 1 2)", false),
+            ("(!= 1 2 1)", true),
+            ("(!= 1 1 2)", false),
+            ("(!= 1 1.0)", false),
+            ("(= 1 1.0)", true),
+            ("(= true true)", true),
+            ("(= true false)", false),
+            ("(= 1 \"1\")", false),
+            ("(= () false)", false),
+            ("(= [1 [2 \"a\"]] [1 [2 \"a\"]])", true),
+            ("(= [1 2] [1 2 3])", false),
+            ("(set (f (fn || 1))) (= f f)", true),
+            ("(= (fn || 1) (fn || 1))", false),
+            ("(= len len)", true),
+            ("(= len first)", false),
+            ("(= (quote (+ a 1)) (quote (+ a  1)))", true),
+            ("(< \"apple\" \"banana\" \"cherry\")", true),
+            ("(< \"b\" \"a\")", false),
+            ("(< [1 2] [1 2 0] [1 3])", true),
+            ("(> [2] [1 \"a\"])", true),
+            ("(< false true)", true),
+            ("(<= [1 2] [1 2])", true),
+            ("(and)", true),
+            ("(and true true)", true),
+            ("(and true false)", false),
+            ("(or)", false),
+            ("(or false true)", true),
+            ("(or false false)", false),
         ];
 
         for (input, expected) in tests {
@@ -142,10 +187,48 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_logic_operators() {
+        let tests = [
+            ("(and 1 \"a\" 2)", Object::Integer(2)),
+            ("(and 1 0 2)", Object::Integer(0)),
+            ("(or 0 \"a\" 2)", Object::String(Rc::from("a"))),
+            ("(or false 0)", Object::Integer(0)),
+            ("(and false (@ 5 []))", Object::Boolean(false)),
+            ("(or true (@ 5 []))", Object::Boolean(true)),
+            ("(set (x 1)) (or false (set (x 2))) (and false (set (x 3))) x", Object::Integer(2)),
+            ("(and ..[1 2 3])", Object::Integer(3)),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_sort_and_unique() {
+        let tests = [
+            ("(sort [3 1 2])", "[1 2 3]"),
+            ("(sort [\"b\" \"c\" \"a\"])", "[a b c]"),
+            ("(sort [\"a\" 2 () [1] 1.5 true])", "[() true 1.5 2 a [1]]"),
+            ("(sort [[1 2] [1] [0 5]])", "[[0 5] [1] [1 2]]"),
+            ("(sort (range 3 0 -1))", "[1 2 3]"),
+            ("(sort [\"ccc\" \"a\" \"bb\"] len)", "[a bb ccc]"),
+            ("(sort [[2 \"x\"] [1 \"y\"] [2 \"a\"]] first)", "[[1 y] [2 x] [2 a]]"),
+            ("(unique [1 2 1 1.0 \"1\" [1 2] [1 2]])", "[1 2 1 [1 2]]"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
+        }
+    }
+
     #[test]
     fn test_array_literal() {
         let tests = [
-            ("[(/ 2 2) (+ 1 1) 3]", Object::Array(Rc::from([Object::Integer(1), Object::Integer(2), Object::Integer(3)]))),
+            ("[(/ 2 2) (+ 1 1) 3]", Object::Array([Object::Integer(1), Object::Integer(2), Object::Integer(3)].into())),
             ("[1 2 3]", vec![Object::Integer(1), Object::Integer(2), Object::Integer(3)].into()),
             ("[1 7.4 3]", vec![Object::Integer(1), Object::Float(7.4), Object::Integer(3)].into()),
             ("[1 7.4 true]", vec![Object::Integer(1), Object::Float(7.4), Object::Boolean(true)].into()),
@@ -166,11 +249,63 @@ mod test {
         let tests = [
             ("(@ 1 [\"one\" \"two\" \"three\"])", Object::String(Rc::from("two"))),
             ("(set (foo [5 6 7]))(@ 1 foo)", Object::Integer(6)),
+            ("(@ -1 [5 6 7])", Object::Integer(7)),
+            ("(@ -3 [5 6 7])", Object::Integer(5)),
+            ("(@ [1 3] [5 6 7 8])", vec![Object::Integer(6), Object::Integer(7)].into()),
+            ("(@ [1] [5 6 7])", vec![Object::Integer(6), Object::Integer(7)].into()),
+            ("(@ [-2 -1] [5 6 7])", vec![Object::Integer(6)].into()),
+            ("(@ [3] [5 6 7])", vec![].into()),
+            ("(@ 1 0 [[1 2] [3 4]])", Object::Integer(3)),
+            ("(@ 0 -1 0 [[1 [2 3]]])", Object::Integer(2)),
+            ("(@ 1 \"héllo\")", Object::String(Rc::from("é"))),
+            ("(@ [1 -1] \"héllo\")", Object::String(Rc::from("éll"))),
+            ("(slice [5 6 7] 1)", vec![Object::Integer(6), Object::Integer(7)].into()),
+            ("(slice \"hello\" 0 -2)", Object::String(Rc::from("hel"))),
         ];
 
         for (input, expected) in tests {
             let evaluated = apply_eval(input).unwrap();
-            assert_eq!(expected, evaluated);
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_array_index_errors() {
+        let tests = [
+            ("(@ 3 [5 6 7])", "Row 1, Col: 2: Array index out of bounds index was '3' but length was '3'."),
+            ("(@ -4 [5 6 7])", "Row 1, Col: 2: Array index out of bounds index was '-4' but length was '3'."),
+            ("(@ 0 2 [[5 6]])", "Row 1, Col: 2: Array index out of bounds index was '2' but length was '2'."),
+            ("(@ [2 1] [5 6 7])", "Row 1, Col: 2: Slice [2 1] out of bounds of length '3'."),
+            ("(@ [0 4] [5 6 7])", "Row 1, Col: 2: Slice [0 4] out of bounds of length '3'."),
+            ("(@ [\"a\"] [5 6 7])", "Row 1, Col: 2: Slice must be [start end] or [start] of Integers, got [a]"),
+            ("(@ 5 \"abc\")", "Row 1, Col: 2: String index out of bounds index was '5' but length was '3'."),
+            ("(slice [5 6 7] -4)", "Row 1, Col: 2: slice: -4.. is out of bounds of length '3'"),
+            ("(slice 1 0)", "Row 1, Col: 2: slice: argument to `slice` must be Array or String, got Integer"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_array_builtins() {
+        let tests = [
+            ("(push [1 2] 3)", "[1 2 3]"),
+            ("(set (a [1 2])) (push a 3) a", "[1 2]"),
+            ("(rest [1 2 3])", "[2 3]"),
+            ("(rest [])", "[]"),
+            ("(push (rest [1 2 3]) 4)", "[2 3 4]"),
+            ("(put [1 2 3] 1 \"two\")", "[1 two 3]"),
+            ("(set (a [1 2 3])) (put a 0 0) a", "[1 2 3]"),
+            ("(set (fill (fn |n acc| (if (= n 0) acc (fill (- n 1) (push acc n)))))) (len (fill 40 []))", "40"),
+            ("(set (sum (fn |xs acc| (if (= (len xs) 0) acc (sum (rest xs) (+ acc (first xs))))))) (sum [..(range 40)] 0)", "780"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
         }
     }
 
@@ -218,11 +353,401 @@ mod test {
         ];
 
         for (input, expected) in tests {
-            let evaluated = apply_eval(input).expect(format!("Coult not evaulate {input} Expected {expected}").as_str());
+            let evaluated = apply_eval(input).unwrap_or_else(|_| panic!("Coult not evaulate {input} Expected {expected}"));
             assert_eq!(expected, evaluated, "Failed at {}", input);
         }
     }
 
+    #[test]
+    fn test_math_builtins() {
+        let tests = [
+            ("(abs -3)", "3"),
+            ("(abs -3.5)", "3.5"),
+            ("(min 3 1.5 2)", "1.5"),
+            ("(max 3 1.5 2)", "3"),
+            ("(clamp 5 0 3)", "3"),
+            ("(clamp -1.5 0 3)", "0"),
+            ("(clamp 2 0 3)", "2"),
+            ("(pow 2 10)", "1024"),
+            ("(pow 4 -1)", "0.25"),
+            ("(pow 4 0.5)", "2"),
+            ("(sqrt 2.25)", "1.5"),
+            ("(exp 0)", "1"),
+            ("(log 1)", "0"),
+            ("(log 8 2)", "3"),
+            ("(sin 0)", "0"),
+            ("(cos 0)", "1"),
+            ("(atan 1 -1)", "2.356194490192345"),
+            ("(floor 2.7)", "2"),
+            ("(floor -2.5)", "-3"),
+            ("(ceil 2.1)", "3"),
+            ("(round 2.5)", "3"),
+            ("(trunc -2.7)", "-2"),
+            ("(floor 4)", "4"),
+            ("(* 2 pi)", "6.283185307179586"),
+            ("(round (* 100 e))", "272"),
+            ("(/ (log (* e e)) 2)", "1"),
+            ("(set (f (fn |r| (* pi r r)))) (round (f 10))", "314"),
+            ("(set (e 2)) (+ e 1)", "3"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_math_errors() {
+        let tests = [
+            ("(% 1)", "Row 1, Col: 2: Operator % expects at least 2 operands found 1"),
+            ("(// \"a\" 2)", "Row 1, Col: 2: Type mismatch (// String Integer)"),
+            ("(abs \"a\")", "Row 1, Col: 2: abs: argument to `abs` must be Integer or Float, got String"),
+            ("(min)", "Row 1, Col: 2: min: wrong number of arguments. got=0, want=at least 1"),
+            ("(clamp 1 3 0)", "Row 1, Col: 2: clamp: lower bound 3 is greater than upper bound 0"),
+            ("(pow 2 31)", "Row 1, Col: 2: pow: 2 to the power of 31 is out of range of Integer"),
+            ("(sqrt -1)", "Row 1, Col: 2: sqrt: cannot take the square root of negative number -1"),
+            ("(log 0)", "Row 1, Col: 2: log: cannot take the logarithm of non-positive number 0"),
+            ("(asin 2)", "Row 1, Col: 2: asin: argument must be between -1 and 1, got 2"),
+            ("(floor 10000000000.0)", "Row 1, Col: 2: floor: 10000000000 is out of range of Integer"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_type_builtins() {
+        let tests = [
+            ("(type_of 1)", "Integer"),
+            ("(type_of 1.5)", "Float"),
+            ("(type_of \"a\")", "String"),
+            ("(type_of [])", "Array"),
+            ("(type_of ())", "Unit"),
+            ("(type_of (fn || 1))", "Function"),
+            ("(type_of len)", "Builtin"),
+            ("(type_of (range 1))", "Sequence"),
+            ("[(is_int 1) (is_int 1.0) (is_float 1.0) (is_number 1) (is_number \"1\")]", "[true false true true false]"),
+            ("[(is_string \"a\") (is_bool false) (is_array [1]) (is_unit ())]", "[true true true true]"),
+            ("[(is_fn len) (is_fn (fn || 1)) (is_fn [])]", "[true true false]"),
+            ("[(is_sequence (range 1)) (is_code (quote a))]", "[true true]"),
+            ("(to_string [1 \"a\"])", "[1 a]"),
+            ("(+ (to_string 12) 3)", "123"),
+            ("(to_int 2.9)", "2"),
+            ("(to_int -2.9)", "-2"),
+            ("(to_int \" 42 \")", "42"),
+            ("(to_int true)", "1"),
+            ("(to_float 2)", "2"),
+            ("(type_of (to_float 2))", "Float"),
+            ("(to_float \"2.5\")", "2.5"),
+            ("(to_float false)", "0"),
+            ("(parse_float \"-1.25\")", "-1.25"),
+            ("(parse_int \"-42\")", "-42"),
+            ("(parse_int \"ff\" 16)", "255"),
+            ("(parse_int \"101\" 2)", "5"),
+            ("[(to_bool 0) (to_bool 1) (to_bool \"\") (to_bool 0.0)]", "[false true true false]"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let tests = [
+            ("(type_of)", "Row 1, Col: 2: type_of: wrong number of arguments. got=0, want=1"),
+            ("(is_int 1 2)", "Row 1, Col: 2: is_int: wrong number of arguments. got=2, want=1"),
+            ("(to_int \"1.5\")", "Row 1, Col: 2: to_int: Number format error. Cannot convert \"1.5\" to Integer."),
+            ("(to_int 10000000000.0)", "Row 1, Col: 2: to_int: 10000000000 is out of range of Integer"),
+            ("(to_int [])", "Row 1, Col: 2: to_int: Cannot convert Array to Integer"),
+            ("(to_float ())", "Row 1, Col: 2: to_float: Cannot convert Unit to Float"),
+            ("(parse_float \"x\")", "Row 1, Col: 2: parse_float: Number format error. Cannot convert \"x\" to Float."),
+            ("(parse_int \"12\" 1)", "Row 1, Col: 2: parse_int: radix must be from 2 to 36, got 1"),
+            ("(parse_int \"z\" 16)", "Row 1, Col: 2: parse_int: Number format error. Cannot convert \"z\" to Integer."),
+            ("(parse_int 12)", "Row 1, Col: 2: parse_int: argument to `parse_int` must be String, got Integer"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let tests = [
+            ("(set (name \"World\")) \"Hello {name}!\"", "Hello World!"),
+            ("(set (a 1) (b 2)) \"{a} + {b} = {(+ a b)}\"", "1 + 2 = 3"),
+            ("\"{[1 2]} {()} {true}\"", "[1 2] () true"),
+            ("\"{{literal}}\"", "{literal}"),
+            ("\"{(+ 1 (len [1 2]))}\"", "3"),
+            ("(format \"{} and {}\" 1 \"a\")", "1 and a"),
+            ("(format \"{1} {0} {1}\" \"a\" \"b\")", "b a b"),
+            ("(format \"[{:5}] [{:5}]\" \"ab\" 12)", "[ab   ] [   12]"),
+            ("(format \"[{:>5}] [{:<5}] [{:^6}]\" \"ab\" 12 \"ab\")", "[   ab] [12   ] [  ab  ]"),
+            ("(format \"[{:*^7}] [{:-<4}]\" \"ab\" 1)", "[**ab***] [1---]"),
+            ("(format \"{:05} {:05}\" 42 -4.5)", "00042 -04.5"),
+            ("(format \"{:.2} {:.1} {:8.3}\" 3.14159 2 2.5)", "3.14 2.0    2.500"),
+            ("(format \"{:.3}\" \"abcdef\")", "abc"),
+            ("(format \"{:>8}\" [1 2])", "   [1 2]"),
+            ("(format \"{{{{}}}} {}\" 1)", "{} 1"),
+            ("(format (+ \"{{\" \"}}\") 1)", "1"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_format_errors() {
+        let tests = [
+            ("(format)", "Row 1, Col: 2: format: wrong number of arguments. got=0, want=1 or more"),
+            ("(format 1)", "Row 1, Col: 2: format: argument to `format` must be String, got Integer"),
+            ("(format \"{} {}\" 1)", "Row 1, Col: 2: format: No value for placeholder 1, got 1 values"),
+            ("(format \"{:x}\" 1)", "Row 1, Col: 2: format: Invalid placeholder {:x}"),
+            ("(format (+ \"{{\" \"a\"))", "Row 1, Col: 2: format: Unclosed { in template, write {{ for a literal {"),
+            ("(format (+ \"a\" \"}}\"))", "Row 1, Col: 2: format: Unmatched } in template, write }} for a literal }"),
+            ("\"{(+ 1 true)}\"", "Row 1, Col: 4: Type mismatch (+ Integer Boolean)"),
+            ("\"\\\"{{}} {(+ 1 true)}\"", "Row 1, Col: 11: Type mismatch (+ Integer Boolean)"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_time_builtins() {
+        let tests = [
+            ("(format_time 0)", "1970-01-01T00:00:00Z"),
+            ("(format_time 1714566600.25 7200)", "2024-05-01T14:30:00.250+02:00"),
+            ("(parse_time \"2024-05-01T14:30:00+02:00\")", "1714566600"),
+            ("(format_time (+ (parse_time \"2024-02-28T23:00:00Z\") (duration \"PT2H\")))", "2024-02-29T01:00:00Z"),
+            ("(duration \"P1DT12H\")", "129600"),
+            ("(format_duration (- (parse_time \"2024-05-02\") (parse_time \"2024-05-01T10:29:30Z\")))", "PT13H30M30S"),
+            ("(> (now) (parse_time \"2024-01-01\"))", "true"),
+            ("(set (start (monotonic))) (sleep 0.01) (>= (- (monotonic) start) 0.01)", "true"),
+            ("(sleep 0)", "()"),
+            ("(is_int (local_offset))", "true"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_time_errors() {
+        let tests = [
+            ("(now 1)", "Row 1, Col: 2: now: wrong number of arguments. got=1, want=0"),
+            ("(sleep -1)", "Row 1, Col: 2: sleep: duration must be a positive number of seconds, got -1"),
+            ("(sleep \"1\")", "Row 1, Col: 2: sleep: argument to `sleep` must be Integer or Float, got String"),
+            ("(format_time 0 \"local\")", "Row 1, Col: 2: format_time: offset must be Integer, got String"),
+            ("(format_time 0 86400)", "Row 1, Col: 2: format_time: offset must be less than a day, got 86400"),
+            ("(format_time 0 (- -2147483647 1))", "Row 1, Col: 2: format_time: offset must be less than a day, got -2147483648"),
+            ("(parse_time \"May 1\")", "Row 1, Col: 2: parse_time: Invalid timestamp \"May 1\", expected e.g. \"2024-05-01T12:30:00Z\""),
+            ("(duration \"P1M\")", "Row 1, Col: 2: duration: Invalid duration \"P1M\", expected e.g. \"PT1H30M\" or \"P1DT12H\""),
+            ("(local_offset (* 1000000.0 1000000.0))", "Row 1, Col: 2: local_offset: time 1000000000000 is out of range"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let tests = [
+            ("(record Point x y) (Point 1 2)", "Point{x 1 y 2}"),
+            ("(record Point x y) (@ \"y\" (Point 1 2))", "2"),
+            ("(record Point x y) (set (p (Point 1 2))) [(put p \"x\" 5) p]", "[Point{x 5 y 2} Point{x 1 y 2}]"),
+            ("(record Point x y) (type_of (Point 1 2))", "Point"),
+            ("(record Point x y) [Point (type_of Point) (is_fn Point) (is_record (Point 1 2))]", "[(record Point x y) Constructor true true]"),
+            ("(record Empty) (Empty)", "Empty{}"),
+            ("(record Point x y) (Point ..[1 2])", "Point{x 1 y 2}"),
+            ("(record Point x y) (collect (map (fn |x| (Point x 0)) [1 2]))", "[Point{x 1 y 0} Point{x 2 y 0}]"),
+            ("(record Point x y) [(= (Point 1 2) (Point 1 2)) (= (Point 1 2) (Point 1 3)) (< (Point 1 2) (Point 1 3))]", "[true false true]"),
+            ("(record A v) (record B v) (set (a (A 1))) (record A v) [(= a (A 1)) (sort [(B 1) (A 2) (A 1)])]", "[false [A{v 1} A{v 2} B{v 1}]]"),
+            ("(record Point x y) (match (Point 1 2) p::Point (+ (@ \"x\" p) (@ \"y\" p)) _ 0)", "3"),
+            ("(record Line from to) (record Point x y) (@ \"x\" (@ \"to\" (Line (Point 0 0) (Point 3 4))))", "3"),
+            ("(record Point x y) (set (p (Point 1 2))) \"at {p}\"", "at Point{x 1 y 2}"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_record_errors() {
+        let tests = [
+            ("(record Point x y) (Point 1)", "Row 1, Col: 21: Point: wrong number of arguments. got=1, want=2"),
+            ("(record Point x y) (@ \"z\" (Point 1 2))", "Row 1, Col: 21: Point has no field 'z'"),
+            ("(record Point x y) (@ 0 (Point 1 2))", "Row 1, Col: 21: Index type 'Integer' not allowed on 'Point'."),
+            ("(record Point x y) (put (Point 1 2) \"z\" 1)", "Row 1, Col: 21: put: Point has no field 'z'"),
+            ("(record Point x y) (put (Point 1 2) 0 1)", "Row 1, Col: 21: put: field must be String, got Integer"),
+            ("(record Point x y) (< (Point 1 2) 1)", "Row 1, Col: 21: Type mismatch (< Point Integer)"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_enums() {
+        let tests = [
+            ("(enum Shape (Circle radius) Empty) [(Circle 1) Empty]", "[Circle{radius 1} Empty]"),
+            ("(enum Shape (Circle radius) Empty) [Circle (type_of (Circle 1)) (type_of Empty)]", "[(enum Shape (Circle radius)) Shape Shape]"),
+            ("(enum Shape (Circle radius) Empty) (@ \"radius\" (Circle 2))", "2"),
+            ("(enum Shape (Circle radius) Empty) (put (Circle 2) \"radius\" 3)", "Circle{radius 3}"),
+            ("(enum Shape (Circle radius) (Rect w h) Empty) (sort [Empty (Rect 1 2) (Circle 5) (Circle 1)])", "[Circle{radius 1} Circle{radius 5} Rect{w 1 h 2} Empty]"),
+            ("(enum Shape (Circle radius) Empty) [(= Empty Empty) (= (Circle 1) (Circle 1)) (= (Circle 1) Empty)]", "[true true false]"),
+            ("(enum Shape (Circle radius) Empty) (match (Circle 2) c::Circle (@ \"radius\" c) _::Empty 0)", "2"),
+            ("(enum Shape (Circle radius) Empty) (match Empty c::Circle (@ \"radius\" c) _::Empty 0)", "0"),
+            ("(enum Shape (Circle radius) Empty) (match Empty s::Shape \"shape\" _ \"other\")", "shape"),
+            ("(enum Shape (Circle radius) Empty) (record Circle radius) (match (Circle 1) _::Shape 1 _ 2)", "2"),
+            ("(enum Shape (Circle radius) Empty) [(is_record Empty) (is_fn Circle)]", "[true true]"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_enum_errors() {
+        let tests = [
+            ("(enum Shape (Circle radius) Empty) (Circle)", "Row 1, Col: 37: Circle: wrong number of arguments. got=0, want=1"),
+            ("(enum Shape (Circle radius) Empty) (< (Circle 1) 1)", "Row 1, Col: 37: Type mismatch (< Shape Integer)"),
+            ("(enum A X) (enum B Y) (< X Y)", "Row 1, Col: 24: Type mismatch (< A B)"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_atoms() {
+        let tests = [
+            ("(atom 1)", "(atom 1)"),
+            ("(set (a (atom [1 2]))) [(deref a) (type_of a) (is_atom a) (is_atom 1)]", "[[1 2] Atom true false]"),
+            ("(set (a (atom 1))) [(reset a 2) (deref a)]", "[2 2]"),
+            ("(set (a (atom 1)) (b a)) (reset a 2) (deref b)", "2"),
+            ("(set (a (atom 1))) [(swap a (fn |n| (+ n 1))) (swap a (fn |n x y| (+ n x y)) 2 3) a]", "[2 7 (atom 7)]"),
+            ("(set (count (atom 0)) (inc (fn || (swap count (fn |n| (+ n 1)))))) (inc) (inc) (deref count)", "2"),
+            ("(set (make (fn |n| [(fn || (swap n (fn |x| (+ x 1)))) (fn || (deref n))]))) (set (pair (make (atom 0)))) ((@ 0 pair)) ((@ 0 pair)) ((@ 1 pair))", "2"),
+            ("(set (a (atom 0))) (swap a (fn |n| (+ (deref a) 10)))", "10"),
+            ("(set (a (atom 0))) (reset a [a]) a", "(atom [(atom ...)])"),
+            ("(set (a (atom 1))) [(= a a) (= a (atom 1)) (len (unique [a a (atom 1)]))]", "[true false 2]"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_atom_errors() {
+        let tests = [
+            ("(deref 1)", "Row 1, Col: 2: deref: argument to `deref` must be Atom, got Integer"),
+            ("(reset 1 2)", "Row 1, Col: 2: reset: first argument to `reset` must be Atom, got Integer"),
+            ("(swap (atom 1))", "Row 1, Col: 2: swap: wrong number of arguments. got=1, want=at least 2"),
+            ("(swap 1 (fn |n| n))", "Row 1, Col: 2: swap: first argument to `swap` must be Atom, got Integer"),
+            ("(swap (atom 1) 2)", "Row 1, Col: 2: Integer is not a function"),
+            ("(< (atom 1) (atom 2))", "Row 1, Col: 2: Type mismatch (< Atom Atom)"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_repr_and_read() {
+        let tests = [
+            (r#"(repr ["a b" "c"])"#, r#"["a b" "c"]"#),
+            (r#"(repr "say \"hi\" \\ {{x}}\n")"#, r#""say \"hi\" \\ {{x}}\n""#),
+            ("(repr [1 -2 2.0 -0.5 () true])", "[1 -2 2.0 -0.5 () true]"),
+            ("(repr (* 1000000.0 1000000.0))", "1000000000000.0"),
+            ("(record Point x y) (record Nothing) (repr [(Point 1 \"a\") (Nothing)])", "[(Point 1 \"a\") (Nothing)]"),
+            ("(enum Shape (Circle r) Empty) (repr [(Circle 1) Empty])", "[(Circle 1) Empty]"),
+            ("(repr (quote (+ a 1)))", "(quote (+ a 1))"),
+            (r#"(read "[1 [2.5 \"x y\"] () false -3]")"#, r#"[1 [2.5 x y] () false -3]"#),
+            (r#"(type_of (read "(quote (+ a 1))"))"#, "Code"),
+            (r#"(set (v ["a b" "c\n{{}}" 1.5 [()] (quote x)])) (= v (read (repr v)))"#, "true"),
+            ("(record Point x y) (set (p (Point 1 [2]))) (= p (eval (repr p)))", "true"),
+            ("(record Point x y) (record Nothing) (set (v [(Point 1 [2.5 \"a\"]) (Nothing)])) (= v (read (repr v)))", "true"),
+            ("(enum Shape (Circle r) Empty) (set (v [(Circle 1) Empty])) (= v (read (repr v)))", "true"),
+            ("(set (f (fn |x| ((set (y 2)) (record Point x y) (read \"(Point 1 2)\"))))) (f 1)", "Point{x 1 y 2}"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_eval() {
+        let tests = [
+            (r#"(eval "(+ 1 2)")"#, "3"),
+            (r#"(eval "(set (x 42))") x"#, "42"),
+            (r#"(set (x 1)) (eval "(set (x (+ x 1)))") x"#, "2"),
+            (r#"(set (f (fn |a| (eval "(* a 2)")))) (f 4)"#, "8"),
+            (r#"(set (f (fn |a| ((eval "(set (b (+ a 1)))") b)))) (f 4)"#, "5"),
+            (r#"(eval "(macro twice |x| (quasiquote [(unquote x) (unquote x)])) (twice 1)")"#, "[1 1]"),
+            (r#"(set (eval (fn |source| source))) (eval "(+ 1")"#, "(+ 1"),
+        ];
+
+        for (input, expected) in tests {
+            let result = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, result.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_repr_read_and_eval_errors() {
+        let tests = [
+            ("(repr (fn |x| x))", "Row 1, Col: 2: repr: Function has no representation"),
+            ("(repr [1 (atom 2)])", "Row 1, Col: 2: repr: Atom has no representation"),
+            ("(repr (to_float \"NaN\"))", "Row 1, Col: 2: repr: NaN has no representation"),
+            ("(record Point x y) (repr Point)", "Row 1, Col: 21: repr: Constructor has no representation"),
+            ("(read 1)", "Row 1, Col: 2: read: argument to `read` must be String, got Integer"),
+            (r#"(read "1 2")"#, "Row 1, Col: 2: read: expected one value, got 2"),
+            (r#"(read "(+ 1 2)")"#, "Row 1, Col: 2: read: (+ 1 2) is not a value, eval evaluates code"),
+            (r#"(read "[1")"#, "Row 1, Col: 2: read: Row 1, Col: 2: Unexpected end of file"),
+            (r#"(read "(Point 1 2)")"#, "Row 1, Col: 2: read: (Point 1 2) is not a value, eval evaluates code"),
+            (r#"(record Point x y) (read "(Point 1)")"#, "Row 1, Col: 21: read: Point: wrong number of arguments. got=1, want=2"),
+            (r#"(record Point x y) (collect (map read ["(Point 1 2)"]))"#, "Row 1, Col: 21: read: (Point 1 2) is not a value, eval evaluates code"),
+            ("(eval 1)", "Row 1, Col: 2: eval: argument to `eval` must be String, got Integer"),
+            (r#"(eval "(+ 1")"#, "Row 1, Col: 2: eval: Parse error: Row 1, Col: 4: Unexpected end of file"),
+            (r#"(eval "y")"#, "Row 1, Col: 2: eval: Resolve error: Row 1, Col: 1: No binding for identifier 'y'"),
+            (r#"(collect (map eval ["1"]))"#, "Row 1, Col: 2: eval: can only be called directly, not through another function"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
     #[test]
     fn test_if_expression() {
         let tests = [
@@ -267,6 +792,54 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_match_expression() {
+        let tests = [
+            ("(match 1 1 \"one\" 2 \"two\")", Object::String(Rc::from("one"))),
+            ("(match 2 1 \"one\" 2 \"two\")", Object::String(Rc::from("two"))),
+            ("(match 2.0 1 \"one\" 2 \"two\")", Object::String(Rc::from("two"))),
+            ("(match \"b\" \"a\" 1 \"b\" 2)", Object::Integer(2)),
+            ("(match true false 0 true 1)", Object::Integer(1)),
+            ("(match () () \"unit\" _ \"other\")", Object::String(Rc::from("unit"))),
+            ("(match 7 _ \"anything\")", Object::String(Rc::from("anything"))),
+            ("(match 7 x (+ x 1))", Object::Integer(8)),
+            ("(match [] [] \"empty\" _ \"other\")", Object::String(Rc::from("empty"))),
+            ("(match [1 2] [a] a [a b] (+ a b))", Object::Integer(3)),
+            ("(match [1 2 3] [first ..rest] rest)", vec![Object::Integer(2), Object::Integer(3)].into()),
+            ("(match [1] [first ..rest] rest)", vec![].into()),
+            ("(match [1 [2 3]] [a [b c]] (+ a b c))", Object::Integer(6)),
+            ("(match [1 2] [1 x] x _ 0)", Object::Integer(2)),
+            ("(match [3 2] [1 x] x _ 0)", Object::Integer(0)),
+            ("(match \"text\" n::Integer n s::String (+ s \"!\"))", Object::String(Rc::from("text!"))),
+            ("(match 5 _::String 0 _::Integer 1)", Object::Integer(1)),
+            ("(match [1 \"a\"] [_::Integer _::Integer] 0 [_ _::String] 1)", Object::Integer(1)),
+            ("(match -3 n if (< n 0) \"negative\" n \"positive\")", Object::String(Rc::from("negative"))),
+            ("(match 3 n if (< n 0) \"negative\" n \"positive\")", Object::String(Rc::from("positive"))),
+            ("(set (x 1)) (match 2 x x) x", Object::Integer(1)),
+            ("(set (y 4)) (match 2 x (+ x y))", Object::Integer(6)),
+            ("(match ..[1 2] 2 \"last\")", Object::String(Rc::from("last"))),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|_| panic!("Could not evaluate {input}"));
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_match_expression_without_match() {
+        let tests = [
+            "(match 3 1 \"one\" 2 \"two\")",
+            "(match [1 2 3] [a b] a)",
+            "(match 1 n if (> n 1) n)",
+        ];
+
+        for input in tests {
+            let evaluated = apply_eval(input);
+            assert!(evaluated.is_err(), "Expected error for: {input}");
+        }
+    }
+
     #[test]
     fn test_while_expression() {
         let tests = [
@@ -324,6 +897,20 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_locals_bound_to_undefined() {
+        let tests = [
+            ("(set (a 5)) (set (f (fn |a| a))) (f (/ 1 0))", Object::Undefined),
+            ("(set (f (fn |a| a))) (f (/ 1 0))", Object::Undefined),
+            ("(set (a 5)) (set (f (fn || ((set (a (/ 1 0))) a)))) (f)", Object::Undefined),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|_| panic!("Failed to evaluate: {input}"));
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
     #[test]
     fn test_vararg_function() {
         let tests = [
@@ -339,13 +926,169 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_assert_builtins() {
+        let tests = [
+            ("(assert true)", Ok(Object::Unit)),
+            ("(assert 1 \"message\")", Ok(Object::Unit)),
+            ("(assert_eq [1 2] [1 2])", Ok(Object::Unit)),
+            ("(test \"not run\" (assert false))", Ok(Object::Unit)),
+            ("(assert false)", Err("Row 1, Col: 2: assert: assertion failed, got false")),
+            ("(assert (= 1 2) \"one is not two\")", Err("Row 1, Col: 2: assert: assertion failed: one is not two")),
+            ("(assert_eq \"abc\" \"abd\")", Err("Row 1, Col: 2: assert_eq: values are not equal\n    expected: abc (String)\n    actual:   abd (String)\n                ^")),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).map_err(|error| error.to_string());
+            assert_eq!(expected.map_err(|error| error.to_string()), evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_eval_with_limits() {
+        let tests = [
+            ("(set (a 1)) (+ a 2)", Limits { max_steps: Some(10), ..Limits::default() }, Ok(Object::Integer(3))),
+            ("(while 1)", Limits { max_steps: Some(100), ..Limits::default() }, Err("Row 1, Col: 9: Evaluation step limit of 100 exceeded")),
+            ("(while 1)", Limits { timeout: Some(Duration::from_millis(10)), ..Limits::default() }, Err("Row 1, Col: 9: Evaluation timed out after 10 ms")),
+            ("(set (f (fn |n| (f (+ n 1))))) (f 0)", Limits { max_depth: Some(50), ..Limits::default() }, Err("Row 1, Col: 18: Maximum call depth of 50 exceeded")),
+            ("(set (s \"abc\")) (+ s s s)", Limits { max_allocation: Some(4), ..Limits::default() }, Err("Row 1, Col: 18: Allocation limit of 4 bytes exceeded")),
+            ("[1 2 3 4 5 6 7 8]", Limits { max_allocation: Some(64), ..Limits::default() }, Err("Row 1, Col: 1: Allocation limit of 64 bytes exceeded")),
+        ];
+
+        for (input, limits, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            let evaluated = program.eval_with_limits(&mut Environment::new(), limits).map_err(|error| error.to_string());
+            assert_eq!(expected.map_err(|error| error.to_string()), evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_eval_with_permissions() {
+        let mut permissions = Permissions { env: true, ..Permissions::default() };
+        permissions.allow_read("samples");
+        permissions::enable(permissions);
+
+        let tests = [
+            ("(include \"samples/seven.kvist\") seven", Ok(Object::Integer(7))),
+            ("(get_env \"KVIST_UNDEFINED_VARIABLE\")", Ok(Object::Unit)),
+            ("(os_execute \"true\")", Err("Row 1, Col: 2: Permission denied: os_execute requires the 'run' capability, allow it with --allow-run")),
+            ("(readln)", Err("Row 1, Col: 2: Permission denied: readln requires the 'stdin' capability, allow it with --allow-stdin")),
+            ("(exit 0)", Err("Row 1, Col: 2: Permission denied: exit requires the 'exit' capability, allow it with --allow-exit")),
+            ("(include \"README.md\")", Err("Row 1, Col: 2: Permission denied: reading 'README.md' requires the 'read' capability, allow it with --allow-read=<dir>")),
+            ("(include \"samples/../README.md\")", Err("Row 1, Col: 2: Permission denied: reading 'samples/../README.md' requires the 'read' capability, allow it with --allow-read=<dir>")),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).map_err(|error| error.to_string());
+            assert_eq!(expected.map_err(|error| error.to_string()), evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_for_expression() {
+        let tests = [
+            ("(for x [1 2 3] x)", Object::Integer(3)),
+            ("(for x [] x)", Object::Unit),
+            ("(set (sum 0)) (for x [1 2 3] (set (sum (+ sum x)))) sum", Object::Integer(0)),
+            ("(for c \"ab\" (+ c c))", Object::String("bb".into())),
+            ("(for x (range 4) (* x x))", Object::Integer(9)),
+            ("(set (fs (collect (map (fn |x| (fn || x)) [1 2])))) ((@ 0 fs))", Object::Integer(1)),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_sequences() {
+        let tests = [
+            ("(collect (range 4))", "[0 1 2 3]"),
+            ("(collect (range 2 5))", "[2 3 4]"),
+            ("(collect (range 5 0 -2))", "[5 3 1]"),
+            ("(collect (take 3 (iterate (fn |n| (* n 2)) 1)))", "[1 2 4]"),
+            ("(collect (generate (fn |n| (if (< n 3) [n (+ n 1)] ())) 0))", "[0 1 2]"),
+            ("(collect (map (fn |n| (+ n 1)) [1 2]))", "[2 3]"),
+            ("(collect (take 2 (filter (fn |n| (> n 10)) (range 1000000000))))", "[11 12]"),
+            ("(set (s (range 3))) (first s) (first s)", "0"),
+            ("(first (rest (rest (range 3))))", "2"),
+            ("(collect (rest (range 0)))", "[]"),
+            ("[(len (range 5)) (last (range 5))]", "[5 4]"),
+            ("(+ ..(range 5))", "10"),
+            ("[..(range 3) 3]", "[0 1 2 3]"),
+            ("(> ..(iterate (fn |n| (+ n 1)) 0))", "false"),
+            ("(range 3)", "(sequence)"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_sequence_errors() {
+        let tests = [
+            ("(range 1 2 0)", "Row 1, Col: 2: range: step must not be 0"),
+            ("(collect (map (fn |n| (+ n true)) [1]))", "Row 1, Col: 2: Row 1, Col: 24: Type mismatch (+ Integer Boolean)"),
+            ("(collect (generate (fn |n| n) 0))", "Row 1, Col: 2: generate: function must return [value state] or Unit, got 0"),
+            ("(for x 1 x)", "Row 1, Col: 2: Cannot iterate over Integer"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_quote_expressions() {
+        let tests = [
+            ("(quote (+ a 1))", "(+ a 1)"),
+            ("(quote x)", "x"),
+            ("(set (x 2)) (quasiquote (* (unquote x) y))", "(* 2 y)"),
+            ("(set (xs [1 \"a\"])) (quasiquote (f (unquote ..xs) 3))", "(f 1 \"a\" 3)"),
+            ("(set (body (quote (g)))) (quasiquote (if c (unquote body)))", "(if c (g))"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap();
+            let Object::Code(_) = evaluated else {
+                panic!("object is not Code. got={:?}", evaluated)
+            };
+            assert_eq!(expected, evaluated.view(), "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_unquote_errors() {
+        let tests = [
+            ("(unquote 1)", "Row 1, Col: 2: Unquote is only allowed inside a quasiquote"),
+            ("(quasiquote (unquote ..[1]))", "Row 1, Col: 14: Unquote splicing is only allowed inside a list"),
+            ("(quasiquote (f (unquote ..1)))", "Row 1, Col: 28: Unquote splicing expects an Array, got Integer"),
+            ("(quasiquote (f (unquote println)))", "Row 1, Col: 17: Cannot unquote Builtin into code"),
+        ];
+
+        for (input, expected) in tests {
+            let error = apply_eval(input).expect_err(&format!("Expected error for: {input}"));
+            assert_eq!(expected, error.to_string(), "Failed to evaluate: {input}");
+        }
+    }
+
     fn apply_eval(input: &str) -> Result<Object, EvaluationError> {
         // let program = Parser::new(Lexer::new(input)).parse_program();
         let lexer = Lexer::from(input);
         let parser = Parser::from(lexer);
-        let program = parser.parse_program().expect(format!("Failed to parse program: {}", input).as_str());
+        let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
 
-        program.eval(&mut Environment::new())
+        let mut environment = Environment::new();
+        if let Some(error) = resolve(&program, &environment).first() {
+            return Err(error.to_string().into());
+        }
+        program.eval(&mut environment)
     }
 
     // fn test_integer_object(object: Object, expected: i64, input: &str) {
@@ -373,6 +1116,6 @@ mod test {
 
 impl From<Vec<Object>> for Object {
     fn from(value: Vec<Object>) -> Self {
-        Object::Array(Rc::from(value))
+        Object::Array(value.into())
     }
 }
\ No newline at end of file
@@ -1,13 +1,21 @@
+use std::fmt::Display;
 use std::fs::read_to_string;
+use crate::debugger::debugger;
 use crate::evaluator::error::EvaluationError;
 use crate::evaluator::evaluator::Eval;
+use crate::evaluator::permissions;
+use crate::expander::expander::Expander;
 use crate::lexer::lexer::Lexer;
 use crate::object::environment::Environment;
 use crate::object::object::Object;
 use crate::parser::parser::Parser;
+use crate::optimizer::optimizer;
+use crate::profiler::profiler;
+use crate::resolver::resolver::resolve;
 
 
 pub fn include_script(target: &str, environment: &mut Environment) -> Result<Object, EvaluationError> {
+    permissions::require_read(target)?;
 
     let Ok(content) = read_to_string(target) else {
         return EvaluationError::Simple(format!("Include error. Could not open: {target}")).into();
@@ -17,7 +25,30 @@ pub fn include_script(target: &str, environment: &mut Environment) -> Result<Obj
     let parser = Parser::from(lexer);
 
      let evaluation = match parser.parse_program() {
-        Ok(result) => result.eval(environment),
+        Ok(result) => {
+            let result = match Expander::new().expand(result) {
+                Ok(result) => result,
+                Err(errors) => {
+                    eprintln!("Could not include file '{target}'. Expansion error:");
+                    errors.iter()
+                        .for_each(|error| eprintln!("\tERROR: {error}"));
+                    return EvaluationError::Simple(format!("Could not include file '{target}'")).into()
+                }
+            };
+            let result = optimizer::apply(result);
+            let errors = resolve(&result, environment);
+            if !errors.is_empty() {
+                eprintln!("Could not include file '{target}'. Resolve error:");
+                errors.iter()
+                    .for_each(|error| eprintln!("\tERROR: {error}"));
+                return EvaluationError::Simple(format!("Could not include file '{target}'")).into()
+            }
+            debugger::enter_file(target);
+            profiler::add_file(&result, target);
+            let evaluation = result.eval(environment);
+            debugger::exit_file();
+            evaluation
+        }
         Err(errors) => {
             eprintln!("Could not include file '{target}'. Parse error:");
             errors.iter()
@@ -33,4 +64,28 @@ pub fn include_script(target: &str, environment: &mut Environment) -> Result<Obj
             EvaluationError::Simple(format!("Could not include file '{target}'")).into()
         }
     }
-}
\ No newline at end of file
+}
+
+// Evaluates source code in the environment, as the eval builtin does
+pub fn eval_source(source: &str, environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let lexer = Lexer::from(source);
+    let parser = Parser::from(lexer);
+    let program = parser.parse_program()
+        .map_err(|errors| error_list("Parse error", &errors))?;
+    let program = Expander::new().expand(program)
+        .map_err(|errors| error_list("Expansion error", &errors))?;
+    let program = optimizer::apply(program);
+    let errors = resolve(&program, environment);
+    if !errors.is_empty() {
+        return Err(error_list("Resolve error", &errors));
+    }
+    program.eval(environment)
+}
+
+fn error_list(kind: &str, errors: &[impl Display]) -> EvaluationError {
+    let errors = errors.iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<String>>()
+        .join("; ");
+    EvaluationError::Simple(format!("eval: {kind}: {errors}"))
+}
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use crate::object::object::Object;
+
+// Deep enough for recursive scripts while staying within the stack the interpreter runs on
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+// How many steps are evaluated between checks of the clock
+const TIMEOUT_CHECK_INTERVAL: u64 = 256;
+
+thread_local! {
+    static USAGE: RefCell<Option<Usage>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub max_depth: Option<usize>,
+    pub timeout: Option<Duration>,
+    // Total number of bytes allowed to be allocated for arrays and strings
+    pub max_allocation: Option<usize>,
+}
+
+struct Usage {
+    limits: Limits,
+    steps: u64,
+    depth: usize,
+    allocated: usize,
+    deadline: Option<Instant>,
+}
+
+impl From<Limits> for Usage {
+    fn from(limits: Limits) -> Self {
+        Usage {
+            limits,
+            steps: 0,
+            depth: 0,
+            allocated: 0,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+}
+
+// Enforces the limits on every evaluation on this thread from now on
+pub fn enable(limits: Limits) {
+    USAGE.with_borrow_mut(|usage| *usage = Some(limits.into()));
+}
+
+// Enforces the limits only while running the given evaluation
+pub fn with_limits<R>(limits: Limits, evaluation: impl FnOnce() -> R) -> R {
+    let previous = USAGE.with_borrow_mut(|usage| usage.replace(limits.into()));
+    let result = evaluation();
+    USAGE.with_borrow_mut(|usage| *usage = previous);
+    result
+}
+
+pub fn step() -> Result<(), String> {
+    USAGE.with_borrow_mut(|usage| {
+        let Some(usage) = usage else {
+            return Ok(());
+        };
+        usage.steps += 1;
+        if let Some(max_steps) = usage.limits.max_steps {
+            if usage.steps > max_steps {
+                return Err(format!("Evaluation step limit of {max_steps} exceeded"));
+            }
+        }
+        if let (Some(deadline), Some(timeout)) = (usage.deadline, usage.limits.timeout) {
+            if usage.steps % TIMEOUT_CHECK_INTERVAL == 0 && Instant::now() > deadline {
+                return Err(format!("Evaluation timed out after {} ms", timeout.as_millis()));
+            }
+        }
+        Ok(())
+    })
+}
+
+pub fn enter_call() -> Result<(), String> {
+    USAGE.with_borrow_mut(|usage| {
+        let Some(usage) = usage else {
+            return Ok(());
+        };
+        if let Some(max_depth) = usage.limits.max_depth {
+            if usage.depth >= max_depth {
+                return Err(format!("Maximum call depth of {max_depth} exceeded"));
+            }
+        }
+        usage.depth += 1;
+        Ok(())
+    })
+}
+
+pub fn exit_call() {
+    USAGE.with_borrow_mut(|usage| if let Some(usage) = usage {
+        usage.depth = usage.depth.saturating_sub(1);
+    });
+}
+
+pub fn allocate(bytes: usize) -> Result<(), String> {
+    USAGE.with_borrow_mut(|usage| {
+        let Some(usage) = usage else {
+            return Ok(());
+        };
+        usage.allocated = usage.allocated.saturating_add(bytes);
+        match usage.limits.max_allocation {
+            Some(max_allocation) if usage.allocated > max_allocation =>
+                Err(format!("Allocation limit of {max_allocation} bytes exceeded")),
+            _ => Ok(()),
+        }
+    })
+}
+
+pub fn allocate_array(length: usize) -> Result<(), String> {
+    allocate(length.saturating_mul(size_of::<Object>()))
+}
@@ -0,0 +1,81 @@
+use std::rc::Rc;
+
+use crate::ast::ast::Node;
+use crate::ast::expression::{Expression, MatchBranch};
+use crate::ast::pattern::Pattern;
+use crate::evaluator::error::{EvaluationError, ToEvaluationError};
+use crate::evaluator::evaluator::{bind, Eval};
+use crate::object::environment::Environment;
+use crate::object::comparison::equals;
+use crate::object::object::{Object, Viewable};
+
+pub(crate) fn eval_match_expression(value: &Node,
+                                    branches: &[MatchBranch],
+                                    environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let value = value.eval(environment)?
+        .spread_to_single()
+        .unwrap_or(Object::Unit);
+
+    for (pattern, guard, consequence) in branches.iter() {
+        let mut branch_environment = Environment::scope(Rc::from(environment.clone()), consequence);
+
+        if !match_pattern(pattern, &value, &mut branch_environment)? {
+            continue;
+        }
+
+        if let Some(guard) = guard {
+            let guard = guard.eval(&mut branch_environment)?
+                .spread_to_single()
+                .unwrap_or(Object::Unit);
+            if !guard.is_truthy() {
+                continue;
+            }
+        }
+
+        return consequence.eval(&mut branch_environment);
+    }
+
+    Err(format!("No pattern matched value '{}'", value.view()).into())
+}
+
+fn match_pattern(pattern: &Pattern, value: &Object, environment: &mut Environment) -> Result<bool, EvaluationError> {
+    match pattern {
+        Pattern::Wildcard => Ok(true),
+        Pattern::Binding(identifier) => {
+            let Expression::Identifier(name) = &identifier.expression else {
+                return Err(identifier.to_error("Expected identifier for pattern binding".to_string()));
+            };
+            bind(identifier, name, value.clone(), environment);
+            Ok(true)
+        }
+        Pattern::Literal(literal) => Ok(equals(&literal.eval(environment)?, value)),
+        Pattern::Array(elements, rest) => {
+            let Object::Array(array) = value else {
+                return Ok(false);
+            };
+            match rest {
+                None if array.len() != elements.len() => return Ok(false),
+                Some(_) if array.len() < elements.len() => return Ok(false),
+                _ => {}
+            }
+            for (element, object) in elements.iter().zip(array.iter()) {
+                if !match_pattern(element, object, environment)? {
+                    return Ok(false);
+                }
+            }
+            match rest {
+                Some(rest) => match_pattern(rest, &Object::Array(array.slice(elements.len(), array.len())), environment),
+                None => Ok(true),
+            }
+        }
+        Pattern::Type(inner, type_name) => {
+            // Variants of enums are matched by the name of the enum or of the variant
+            let is_variant = matches!(value, Object::Record(record_type, _)
+                if record_type.variant_of.is_some() && record_type.name == *type_name);
+            if !is_variant && value.to_string() != type_name.as_ref() {
+                return Ok(false);
+            }
+            match_pattern(inner, value, environment)
+        }
+    }
+}
@@ -1,7 +1,13 @@
 pub mod evaluator;
 mod evaluator_test;
-mod builtin;
+pub(crate) mod builtin;
 mod error;
 mod include;
 mod queued_evaluator;
-mod operator_expression;
\ No newline at end of file
+pub(crate) mod operator_expression;
+mod match_expression;
+pub(crate) mod quote;
+pub mod limits;
+pub mod permissions;
+pub mod time;
+mod time_test;
\ No newline at end of file
@@ -1,21 +1,39 @@
 use crate::ast::ast::Node;
 use crate::evaluator::error::EvaluationError;
+use crate::evaluator::limits;
 use crate::evaluator::queued_evaluator::QueuedEvaluator;
 use crate::object::environment::Environment;
+use crate::object::comparison::{comparable, equals, order};
 use crate::object::object::{Object, Viewable};
+use std::cmp::Ordering;
 use std::rc::Rc;
 
 pub(crate) fn eval_operator_expression(operator: &Rc<str>, operands: &[Node], environment: &mut Environment) -> Result<Object, EvaluationError> {
-    let queued_evaluator = QueuedEvaluator::new(operands, environment);
+    apply_operator(operator, QueuedEvaluator::new(operands, environment))
+}
+
+// Applies an operator to operands that are already known, e.g. literals when optimizing
+pub(crate) fn apply_operator_to_objects(operator: &Rc<str>, operands: Vec<Object>) -> Result<Object, EvaluationError> {
+    apply_operator(operator, QueuedEvaluator::from_objects(operands, &mut Environment::new()))
+}
+
+fn apply_operator(operator: &Rc<str>, queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
     match operator.as_ref() {
         "+" => plus_operator(queued_evaluator),
         "-" => minus_operator(queued_evaluator),
         "*" => multiply_operator(queued_evaluator),
         "/" => divide_operator(queued_evaluator),
-        "<" => lesser_than_operator(queued_evaluator),
-        ">" => greater_than_operator(queued_evaluator),
-        "=" => equals_operator(queued_evaluator),
+        "//" => integer_divide_operator(queued_evaluator),
+        "%" => modulo_operator(queued_evaluator),
+        "<" => ordering_operator("<", queued_evaluator, Ordering::is_lt),
+        ">" => ordering_operator(">", queued_evaluator, Ordering::is_gt),
+        "<=" => ordering_operator("<=", queued_evaluator, Ordering::is_le),
+        ">=" => ordering_operator(">=", queued_evaluator, Ordering::is_ge),
+        "=" => comparison_operator(queued_evaluator, |left, right| Ok(equals(left, right))),
+        "!=" => comparison_operator(queued_evaluator, |left, right| Ok(!equals(left, right))),
         "!" => not_operator(queued_evaluator),
+        "and" => and_operator(queued_evaluator),
+        "or" => or_operator(queued_evaluator),
         _ => Err(format!("unknown operator '{operator}'").into()),
     }
 }
@@ -38,14 +56,19 @@ fn plus_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, Evalua
             (Object::Integer(left), Object::Float(right)) => Object::Float(f64::from(left) + right).into(),
             (Object::Float(left), Object::Float(right)) => Object::Float(left + right).into(),
 
-            (Object::String(left), right @ _) => Object::String(format!("{left}{}", right.view()).into()).into(),
-            (left @ _, Object::String(right)) => Object::String(format!("{}{right}", left.view()).into()).into(),
+            (Object::String(left), right @ _) => concatenate(format!("{left}{}", right.view()))?,
+            (left @ _, Object::String(right)) => concatenate(format!("{}{right}", left.view()))?,
             (left @ _, right @ _) => return EvaluationError::from(format!("Type mismatch (+ {left} {right})").to_string()).into(),
         }
     }
     Ok(left)
 }
 
+fn concatenate(string: String) -> Result<Object, EvaluationError> {
+    limits::allocate(string.len())?;
+    Ok(Object::String(string.into()))
+}
+
 fn minus_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
     let Some(first) = queued_evaluator.next() else {
         return Object::Integer(0).into();
@@ -94,7 +117,7 @@ fn divide_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, Eval
     if !queued_evaluator.has_next() {
         return match first? {
             Object::Integer(0)   => Object::Undefined.into(),
-            Object::Float(value) if value == 0. => Object::Undefined.into(),
+            Object::Float(0.) => Object::Undefined.into(),
             Object::Integer(value) => Object::Float(1. / f64::from(value)).into(),
             Object::Float(value) => Object::Float(1. / value).into(),
             object @ _ => EvaluationError::from(format!("Type mismatch (/ {object})").to_string()).into(),
@@ -106,7 +129,7 @@ fn divide_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, Eval
     while let Some(operand) = queued_evaluator.next() {
         result = match (result, operand?) {
             (_, Object::Integer(0)) => Object::Undefined.into(),
-            (_, Object::Float(value)) if value == 0. => Object::Undefined.into(),
+            (_, Object::Float(0.)) => Object::Undefined.into(),
             (Object::Integer(left), Object::Integer(right)) => no_truncating_division(left, right),
             (Object::Float(left), Object::Integer(right)) => Object::Float(left / f64::from(right)),
             (Object::Integer(left), Object::Float(right)) => Object::Float(f64::from(left) / right),
@@ -126,62 +149,70 @@ fn no_truncating_division(left: i32, right: i32) -> Object {
     }
 }
 
-fn lesser_than_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
+// Division rounding down, so it goes together with the modulo operator
+fn integer_divide_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
     let Some(first) = queued_evaluator.next() else {
-        return Object::Boolean(false).into();
+        return Err("Operator // expects at least 2 operands found 0".to_string().into());
     };
     if !queued_evaluator.has_next() {
-        return Object::Boolean(true).into();
+        return Err("Operator // expects at least 2 operands found 1".to_string().into());
     }
-    let mut left = first?;
-    let mut result = true;
+    let mut result = first?;
 
     while let Some(operand) = queued_evaluator.next() {
-        let right = operand?;
-        result = match (&left, &right) {
-            (Object::Integer(left), Object::Integer(right)) => left < right,
-            (Object::Float(left), Object::Integer(right)) => *left < f64::from(*right),
-            (Object::Integer(left), Object::Float(right)) => f64::from(*left) < *right,
-            (Object::Float(left), Object::Float(right)) => left < right,
-            (left @ _, right @ _) => return Err(format!("Type mismatch (< {left} {right})").into()),
-        };
-        if !result {
-            return Object::Boolean(false).into();
+        result = match (result, operand?) {
+            (_, Object::Integer(0)) => Object::Undefined,
+            (_, Object::Float(0.)) => Object::Undefined,
+            (Object::Integer(left), Object::Integer(right)) => match left.checked_div(right) {
+                Some(quotient) if left % right != 0 && (left < 0) != (right < 0) => Object::Integer(quotient - 1),
+                Some(quotient) => Object::Integer(quotient),
+                None => return Err(format!("Integer overflow (// {left} {right})").into()),
+            },
+            (Object::Float(left), Object::Integer(right)) => Object::Float((left / f64::from(right)).floor()),
+            (Object::Integer(left), Object::Float(right)) => Object::Float((f64::from(left) / right).floor()),
+            (Object::Float(left), Object::Float(right)) => Object::Float((left / right).floor()),
+            (left, right) => return Err(format!("Type mismatch (// {left} {right})").into()),
         }
-        left = right;
     }
-    Object::Boolean(result).into()
+
+    result.into()
 }
 
-fn greater_than_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
+// The remainder has the sign of the divisor, e.g. (% -1 3) is 2
+fn modulo_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
     let Some(first) = queued_evaluator.next() else {
-        return Object::Boolean(false).into();
+        return Err("Operator % expects at least 2 operands found 0".to_string().into());
     };
     if !queued_evaluator.has_next() {
-        return Object::Boolean(true).into();
+        return Err("Operator % expects at least 2 operands found 1".to_string().into());
     }
-    let mut left = first?;
-    let mut result = true;
+    let mut result = first?;
 
     while let Some(operand) = queued_evaluator.next() {
-        let right = operand?;
-        result = match (&left, &right) {
-            (Object::Integer(left), Object::Integer(right)) => left > right,
-            (Object::Float(left), Object::Integer(right)) => *left > f64::from(*right),
-            (Object::Integer(left), Object::Float(right)) => f64::from(*left) > *right,
-            (Object::Float(left), Object::Float(right)) => left > right,
-            (left @ _, right @ _) => return Err(format!("Type mismatch (> {left} {right})").into()),
-        };
-        if !result {
-            return Object::Boolean(false).into();
+        result = match (result, operand?) {
+            (_, Object::Integer(0)) => Object::Undefined,
+            (_, Object::Float(0.)) => Object::Undefined,
+            (Object::Integer(left), Object::Integer(right)) => match left.checked_rem(right) {
+                Some(remainder) if remainder != 0 && (remainder < 0) != (right < 0) => Object::Integer(remainder + right),
+                Some(remainder) => Object::Integer(remainder),
+                None => Object::Integer(0),
+            },
+            (Object::Float(left), Object::Integer(right)) => float_modulo(left, f64::from(right)),
+            (Object::Integer(left), Object::Float(right)) => float_modulo(f64::from(left), right),
+            (Object::Float(left), Object::Float(right)) => float_modulo(left, right),
+            (left, right) => return Err(format!("Type mismatch (% {left} {right})").into()),
         }
-        left = right;
     }
-    Object::Boolean(result).into()
+
+    result.into()
 }
 
-fn equals_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
+fn float_modulo(left: f64, right: f64) -> Object {
+    Object::Float(left - right * (left / right).floor())
+}
 
+// Chained comparisons hold when they hold for every pair of neighbouring operands, e.g. (< 1 x 10)
+fn comparison_operator(mut queued_evaluator: QueuedEvaluator, holds: impl Fn(&Object, &Object) -> Result<bool, EvaluationError>) -> Result<Object, EvaluationError> {
     let Some(first) = queued_evaluator.next() else {
         return Object::Boolean(false).into();
     };
@@ -189,27 +220,23 @@ fn equals_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, Eval
         return Object::Boolean(true).into();
     }
     let mut left = first?;
-    let mut result = true;
 
-    // for operand in operands {
     while let Some(operand) = queued_evaluator.next() {
         let right = operand?;
-        result = match (&left, &right) {
-            (Object::Integer(left), Object::Integer(right)) => left == right,
-            (Object::Float(left), Object::Integer(right)) => *left == f64::from(*right),
-            (Object::Integer(left), Object::Float(right)) => f64::from(*left) == *right,
-            (Object::Float(left), Object::Float(right)) => left == right,
-            (Object::String(left), Object::String(right)) => left == right,
-            (Object::Unit, Object::Unit) => true,
-            (Object::Unit, _) | (_, Object::Unit) => false,
-            (left @ _, right @ _) => return Err(format!("Type mismatch (= {left} {right})").into()),
-        };
-        if !result {
+        if !holds(&left, &right)? {
             return Object::Boolean(false).into();
         }
         left = right;
     }
-    Object::Boolean(result).into()
+    Object::Boolean(true).into()
+}
+
+// Numbers, strings, arrays and booleans are ordered, see comparison::order
+fn ordering_operator(operator: &str, queued_evaluator: QueuedEvaluator, accepts: fn(Ordering) -> bool) -> Result<Object, EvaluationError> {
+    comparison_operator(queued_evaluator, |left, right| match comparable(left, right) {
+        true => Ok(accepts(order(left, right))),
+        false => Err(format!("Type mismatch ({operator} {left} {right})").into()),
+    })
 }
 
 fn not_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
@@ -220,3 +247,27 @@ fn not_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, Evaluat
         Object::Boolean(!&object?.is_truthy())
     } else { Object::Boolean(true) }.into()
 }
+
+// The first operand that is not truthy, or the last one. Operands after it aren't evaluated.
+fn and_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
+    let mut result = Object::Boolean(true);
+    while let Some(operand) = queued_evaluator.next() {
+        result = operand?;
+        if !result.is_truthy() {
+            break;
+        }
+    }
+    result.into()
+}
+
+// The first operand that is truthy, or the last one. Operands after it aren't evaluated.
+fn or_operator(mut queued_evaluator: QueuedEvaluator) -> Result<Object, EvaluationError> {
+    let mut result = Object::Boolean(false);
+    while let Some(operand) = queued_evaluator.next() {
+        result = operand?;
+        if result.is_truthy() {
+            break;
+        }
+    }
+    result.into()
+}
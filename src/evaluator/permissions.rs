@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static PERMISSIONS: RefCell<Option<Permissions>> = const { RefCell::new(None) };
+}
+
+// Reading files depends on the path, so it's required with require_read instead
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Capability {
+    Run,
+    Env,
+    Stdin,
+    Exit,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::Run => "run",
+            Capability::Env => "env",
+            Capability::Stdin => "stdin",
+            Capability::Exit => "exit",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum ReadAccess {
+    #[default]
+    Nowhere,
+    Anywhere,
+    // Files can only be read from within these directories
+    Directories(Vec<PathBuf>),
+}
+
+// Capabilities granted to a sandboxed script, everything not granted is denied
+#[derive(Debug, Default, Clone)]
+pub struct Permissions {
+    pub run: bool,
+    pub env: bool,
+    pub read: ReadAccess,
+    pub stdin: bool,
+    pub exit: bool,
+}
+
+impl Permissions {
+
+    pub fn allow_read(&mut self, directory: &str) {
+        let directory = absolute(Path::new(directory));
+        match &mut self.read {
+            ReadAccess::Anywhere => {}
+            ReadAccess::Directories(directories) => directories.push(directory),
+            ReadAccess::Nowhere => self.read = ReadAccess::Directories(vec![directory]),
+        }
+    }
+
+    fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Run => self.run,
+            Capability::Env => self.env,
+            Capability::Stdin => self.stdin,
+            Capability::Exit => self.exit,
+        }
+    }
+
+    fn allows_reading(&self, path: &Path) -> bool {
+        match &self.read {
+            ReadAccess::Nowhere => false,
+            ReadAccess::Anywhere => true,
+            ReadAccess::Directories(directories) => {
+                let path = absolute(path);
+                directories.iter().any(|directory| path.starts_with(directory))
+            }
+        }
+    }
+}
+
+// Resolves links and `..` so a path can't escape an allowed directory,
+// falls back to the plain absolute path for files that don't exist
+fn absolute(path: &Path) -> PathBuf {
+    canonicalize(path).unwrap_or_else(|_| env::current_dir()
+        .map(|directory| directory.join(path))
+        .unwrap_or(path.to_path_buf()))
+}
+
+// Runs every script on this thread sandboxed with only the given capabilities
+pub fn enable(permissions: Permissions) {
+    PERMISSIONS.with_borrow_mut(|current| *current = Some(permissions));
+}
+
+pub fn require(capability: Capability, name: &str) -> Result<(), String> {
+    PERMISSIONS.with_borrow(|permissions| match permissions {
+        Some(permissions) if !permissions.allows(capability) =>
+            Err(format!("Permission denied: {name} requires the '{capability}' capability, allow it with --allow-{capability}")),
+        _ => Ok(()),
+    })
+}
+
+pub fn require_read(path: &str) -> Result<(), String> {
+    PERMISSIONS.with_borrow(|permissions| match permissions {
+        Some(permissions) if !permissions.allows_reading(Path::new(path)) =>
+            Err(format!("Permission denied: reading '{path}' requires the 'read' capability, allow it with --allow-read=<dir>")),
+        _ => Ok(()),
+    })
+}
@@ -1,15 +1,20 @@
 use std::collections::VecDeque;
+use std::rc::Rc;
 use crate::ast::ast::Node;
+use crate::ast::expression::Expression;
 use crate::evaluator::error::{EvaluationError, ToEvaluationError};
-use crate::evaluator::evaluator::Eval;
+use crate::evaluator::evaluator::{eval_spread_expression, Eval};
 use crate::object::environment::Environment;
 use crate::object::object::Object;
+use crate::object::sequence::Sequence;
 
 pub struct QueuedEvaluator<'a> {
     buf: VecDeque<Object>,
     index: usize,
     nodes: &'a[Node],
     environment: &'a mut Environment,
+    // Spread sequences are computed one value at a time, as the operands are asked for
+    sequence: Option<Rc<Sequence>>,
 }
 
 impl QueuedEvaluator<'_> {
@@ -20,6 +25,18 @@ impl QueuedEvaluator<'_> {
             index: 0,
             nodes,
             environment,
+            sequence: None,
+        }
+    }
+
+    // Operands that are already evaluated
+    pub fn from_objects(objects: Vec<Object>, environment: &mut Environment) -> QueuedEvaluator<'_> {
+        QueuedEvaluator {
+            buf: objects.into(),
+            index: 0,
+            nodes: &[],
+            environment,
+            sequence: None,
         }
     }
 
@@ -27,15 +44,40 @@ impl QueuedEvaluator<'_> {
         if let Some(object) = self.buf.pop_front() {
             return Result::from(object).into();
         }
+        if let Some(sequence) = self.sequence.take() {
+            match sequence.force() {
+                Ok(Some((value, rest))) => {
+                    self.sequence = Some(rest);
+                    return Some(Ok(value));
+                }
+                Ok(None) => {}
+                Err(message) => return Some(Err(message.into())),
+            }
+        }
         let Some(node) = self.nodes.get(self.index) else {
             return None;
         };
-        let result = match node.eval(self.environment) {
+        let evaluated = match &node.expression {
+            Expression::Spread(operand) => match operand.eval(self.environment) {
+                Ok(Object::Sequence(sequence)) => {
+                    self.index += 1;
+                    self.sequence = Some(sequence);
+                    return self.next();
+                }
+                Ok(object) => eval_spread_expression(object).map_err(|error| match error {
+                    EvaluationError::Simple(message) => node.to_error(message),
+                    error => error,
+                }),
+                error @ Err(_) => error,
+            },
+            _ => node.eval(self.environment),
+        };
+        let result = match evaluated {
             Ok(Object::Spread(objects)) => {
-                for object in &objects[1..] {
+                for object in objects.iter().skip(1) {
                     self.buf.push_back(object.clone());
                 }
-                objects.get(0).map(|o| o.clone())
+                objects.first().map(|o| o.clone())
                     .ok_or(node.to_error("Missing argument for spread operator".to_string()))
             }
             result @ _ => result,
@@ -45,10 +87,12 @@ impl QueuedEvaluator<'_> {
     }
 
     pub fn has_next(&self) -> bool {
-        !self.buf.is_empty() || self.index < self.nodes.len()
+        let sequence_has_next = self.sequence.as_ref()
+            .is_some_and(|sequence| !matches!(sequence.force(), Ok(None)));
+        !self.buf.is_empty() || sequence_has_next || self.index < self.nodes.len()
     }
 
     pub fn queue_len(&self) -> usize {
-        self.buf.len() + self.nodes.len() - self.index
+        self.buf.len() + usize::from(self.sequence.is_some()) + self.nodes.len() - self.index
     }
 }
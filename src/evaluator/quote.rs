@@ -0,0 +1,157 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::ast::{Node, Resolution};
+use crate::ast::expression::Expression;
+use crate::ast::pattern::Pattern;
+use crate::evaluator::error::{EvaluationError, ToEvaluationError};
+use crate::evaluator::evaluator::Eval;
+use crate::object::environment::Environment;
+use crate::object::object::Object;
+use crate::token::token::Token;
+
+thread_local! {
+    static CALL_SITE: RefCell<Option<Token>> = const { RefCell::new(None) };
+    static GENSYM: Cell<usize> = const { Cell::new(0) };
+}
+
+// Quasiquotes evaluated while expanding a macro call get the position of the call,
+// so errors in the expanded code point at the code the user wrote
+pub(crate) fn at_call_site<T>(token: &Token, expand: impl FnOnce() -> T) -> T {
+    let outer = CALL_SITE.replace(Some(token.clone()));
+    let result = expand();
+    CALL_SITE.set(outer);
+    result
+}
+
+// Copies the template with every unquote replaced by the code of its value. Names bound
+// by the template itself are renamed to fresh ones, so they can't capture or shadow
+// names in the code that is unquoted into it.
+pub(crate) fn eval_quasiquote(template: &Node, environment: &mut Environment) -> Result<Object, EvaluationError> {
+    let mut renames = HashMap::new();
+    collect_binders(template, &mut renames);
+
+    let mut code = template.clone();
+    let call_site = CALL_SITE.with_borrow(|call_site| call_site.clone());
+    prepare(&mut code, &renames, call_site.as_ref());
+    substitute(&mut code, environment)?;
+    Ok(Object::Code(code.into()))
+}
+
+// Turns a value into code that evaluates to it
+pub(crate) fn to_node(object: Object, token: &Token) -> Result<Node, EvaluationError> {
+    let expression = match object {
+        Object::Code(node) => return Ok(Rc::unwrap_or_clone(node)),
+        Object::Unit => Expression::ExpressionLiteral(Box::default()),
+        Object::Integer(value) => Expression::Integer(value),
+        Object::Float(value) => Expression::Float(value),
+        Object::Boolean(value) => Expression::Boolean(value),
+        Object::String(value) => Expression::String(value),
+        Object::Array(objects) => Expression::Array(objects.iter()
+            .map(|object| to_node(object.clone(), token))
+            .collect::<Result<_, _>>()?),
+        object => return Err(format!("Cannot unquote {object} into code").into()),
+    };
+    Ok(Node { expression, token: token.clone(), resolution: Resolution::default() })
+}
+
+fn collect_binders(node: &Node, renames: &mut HashMap<Rc<str>, Rc<str>>) {
+    let mut binders = Vec::new();
+    match &node.expression {
+        Expression::Unquote(_) | Expression::Quote(_) | Expression::Quasiquote(_) => return,
+        Expression::Set(variables) => binders.extend(variables.iter().map(|(identifier, _)| identifier)),
+        Expression::Function(params, vararg, _) => binders.extend(params.iter().chain(vararg.as_ref().as_ref())),
+        Expression::For(binding, _, _) => binders.push(binding),
+        Expression::Match(_, branches) => branches.iter()
+            .for_each(|(pattern, _, _)| collect_pattern_binders(pattern, &mut binders)),
+        _ => {}
+    }
+    for binder in binders {
+        if let Expression::Identifier(name) = &binder.expression {
+            renames.entry(name.clone()).or_insert_with(|| gensym(name));
+        }
+    }
+    node.children().into_iter().for_each(|child| collect_binders(child, renames));
+}
+
+fn collect_pattern_binders<'a>(pattern: &'a Pattern, binders: &mut Vec<&'a Node>) {
+    match pattern {
+        Pattern::Binding(identifier) => binders.push(identifier),
+        Pattern::Array(elements, rest) => {
+            elements.iter().for_each(|element| collect_pattern_binders(element, binders));
+            rest.iter().for_each(|rest| collect_pattern_binders(rest, binders));
+        }
+        Pattern::Type(inner, _) => collect_pattern_binders(inner, binders),
+        Pattern::Wildcard | Pattern::Literal(_) => {}
+    }
+}
+
+fn gensym(name: &str) -> Rc<str> {
+    let count = GENSYM.get() + 1;
+    GENSYM.set(count);
+    // Names with % can't be written in source code, so they never collide
+    format!("{name}%{count}").into()
+}
+
+// Renames the bound names and moves the template to the call site, leaving unquotes as is
+fn prepare(node: &mut Node, renames: &HashMap<Rc<str>, Rc<str>>, call_site: Option<&Token>) {
+    if let Some(call_site) = call_site {
+        node.token.row = call_site.row;
+        node.token.col = call_site.col;
+    }
+    match &mut node.expression {
+        Expression::Unquote(_) => {}
+        Expression::Identifier(name) => if let Some(renamed) = renames.get(name) {
+            *name = renamed.clone();
+            node.token.literal = renamed.clone();
+        },
+        _ => node.children_mut().into_iter().for_each(|child| prepare(child, renames, call_site)),
+    }
+}
+
+fn substitute(node: &mut Node, environment: &mut Environment) -> Result<(), EvaluationError> {
+    match &mut node.expression {
+        Expression::Unquote(operand) => {
+            if let Expression::Spread(_) = operand.expression {
+                return Err(node.to_error("Unquote splicing is only allowed inside a list".to_string()));
+            }
+            let object = operand.eval(environment)?;
+            *node = to_node(object, &node.token).map_err(|error| node.to_error(error.to_string()))?;
+            Ok(())
+        }
+        Expression::ExpressionLiteral(nodes)
+        | Expression::Array(nodes)
+        | Expression::Operator(_, nodes) => {
+            let mut spliced = Vec::with_capacity(nodes.len());
+            for mut node in std::mem::take(nodes).into_vec() {
+                match &node.expression {
+                    Expression::Unquote(operand) => match &operand.expression {
+                        Expression::Spread(spread) => spliced.extend(splice(spread, &node.token, environment)?),
+                        _ => {
+                            substitute(&mut node, environment)?;
+                            spliced.push(node);
+                        }
+                    },
+                    _ => {
+                        substitute(&mut node, environment)?;
+                        spliced.push(node);
+                    }
+                }
+            }
+            *nodes = spliced.into_boxed_slice();
+            Ok(())
+        }
+        Expression::Quote(_) | Expression::Quasiquote(_) => Ok(()),
+        _ => node.children_mut().into_iter().try_for_each(|child| substitute(child, environment)),
+    }
+}
+
+fn splice(operand: &Node, token: &Token, environment: &mut Environment) -> Result<Vec<Node>, EvaluationError> {
+    match operand.eval(environment)? {
+        Object::Array(objects) => objects.iter()
+            .map(|object| to_node(object.clone(), token).map_err(|error| operand.to_error(error.to_string())))
+            .collect(),
+        object => Err(operand.to_error(format!("Unquote splicing expects an Array, got {object}"))),
+    }
+}
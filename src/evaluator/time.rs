@@ -0,0 +1,504 @@
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Times are seconds since the Unix epoch, 1970-01-01T00:00:00Z, and durations are seconds.
+// Offsets of time zones are seconds east of UTC.
+
+const DAY: i64 = 86_400;
+
+// Seconds since the Unix epoch, negative before it
+pub fn now() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(error) => -error.duration().as_secs_f64(),
+    }
+}
+
+// Seconds since an arbitrary point in time that never goes backwards, for measuring durations
+pub fn monotonic() -> f64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+// Days since the epoch of a date in the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Year, month and day of a number of days since the epoch
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// RFC 3339 timestamp of the time at the offset, e.g. 2024-05-01T12:30:00Z or 2024-05-01T14:30:00.250+02:00.
+// Fractions of seconds are only written when there are any, as milliseconds.
+pub fn format_rfc3339(time: f64, offset: i64) -> String {
+    let millis = ((time + offset as f64) * 1000.).round() as i64;
+    let (days, millis) = (millis.div_euclid(DAY * 1000), millis.rem_euclid(DAY * 1000));
+    let (year, month, day) = civil_from_days(days);
+    let seconds = millis / 1000;
+    let mut result = format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if millis % 1000 != 0 {
+        result.push_str(&format!(".{:03}", millis % 1000));
+    }
+    match offset {
+        0 => result.push('Z'),
+        _ => result.push_str(&format!("{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 3600, offset.abs() / 60 % 60)),
+    }
+    result
+}
+
+// Time of an ISO 8601 or RFC 3339 timestamp, a date such as 2024-05-01 optionally followed by a time
+// such as T12:30, T12:30:05 or T12:30:05.25 and an offset such as Z, +02:00 or -0500. Times without
+// an offset are in UTC.
+pub fn parse_rfc3339(timestamp: &str) -> Option<f64> {
+    let mut parser = Scanner { rest: timestamp };
+    let year = parser.number(4, 4)?;
+    parser.expect("-")?;
+    let month = parser.number(2, 2)?;
+    parser.expect("-")?;
+    let day = parser.number(2, 2)?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut seconds = 0.;
+    if parser.expect("T").or_else(|| parser.expect("t")).or_else(|| parser.expect(" ")).is_some() {
+        let hour = parser.number(2, 2)?;
+        parser.expect(":")?;
+        let minute = parser.number(2, 2)?;
+        let second = match parser.expect(":") {
+            Some(_) => parser.number(2, 2)?,
+            None => 0,
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        seconds = (hour * 3600 + minute * 60 + second) as f64;
+        if parser.expect(".").is_some() {
+            let start = parser.rest;
+            parser.number(1, 9)?;
+            seconds += format!("0.{}", &start[..start.len() - parser.rest.len()]).parse::<f64>().ok()?;
+        }
+    }
+
+    let offset = match parser.rest {
+        "" | "Z" | "z" => 0,
+        _ => parser.offset().filter(|_| parser.rest.is_empty())?,
+    };
+    Some((days_from_civil(year, month, day) * DAY - offset) as f64 + seconds)
+}
+
+// ISO 8601 duration, e.g. PT1H30M, P2DT12H, PT0.5S or P1W. Years and months are not a fixed number
+// of seconds and are not allowed. A leading - gives a negative duration.
+pub fn parse_duration(duration: &str) -> Option<f64> {
+    let (sign, duration) = match duration.strip_prefix('-') {
+        Some(duration) => (-1., duration),
+        None => (1., duration),
+    };
+    let duration = duration.strip_prefix('P')?;
+    let (date, time) = duration.split_once('T').unwrap_or((duration, ""));
+    if duration.is_empty() || duration.ends_with('T') {
+        return None;
+    }
+    let seconds = components(date, &[('W', 7. * DAY as f64), ('D', DAY as f64)])?
+        + components(time, &[('H', 3600.), ('M', 60.), ('S', 1.)])?;
+    Some(sign * seconds)
+}
+
+// Sum of components such as 1H30M, in the order of the units
+fn components(mut text: &str, units: &[(char, f64)]) -> Option<f64> {
+    let mut seconds = 0.;
+    let mut units = units.iter();
+    while !text.is_empty() {
+        let end = text.find(|char: char| !char.is_ascii_digit() && char != '.')?;
+        let value = text[..end].parse::<f64>().ok()?;
+        let unit = text[end..].chars().next()?;
+        let (_, size) = units.find(|(name, _)| *name == unit)?;
+        seconds += value * size;
+        text = &text[end + unit.len_utf8()..];
+    }
+    Some(seconds)
+}
+
+// ISO 8601 duration of a number of seconds, e.g. PT1H30M or -P1DT0.5S
+pub fn format_duration(seconds: f64) -> String {
+    let sign = if seconds < 0. { "-" } else { "" };
+    let millis = (seconds.abs() * 1000.).round() as i64;
+    let (days, hours, minutes, millis) = (millis / (DAY * 1000), millis / 3_600_000 % 24, millis / 60_000 % 60, millis % 60_000);
+
+    let mut result = format!("{sign}P");
+    if days != 0 {
+        result.push_str(&format!("{days}D"));
+    }
+    if hours != 0 || minutes != 0 || millis != 0 || days == 0 {
+        result.push('T');
+    }
+    if hours != 0 {
+        result.push_str(&format!("{hours}H"));
+    }
+    if minutes != 0 {
+        result.push_str(&format!("{minutes}M"));
+    }
+    if millis != 0 || result.ends_with('T') {
+        result.push_str(&format!("{}S", millis as f64 / 1000.));
+    }
+    result
+}
+
+struct Scanner<'a> {
+    rest: &'a str,
+}
+
+impl Scanner<'_> {
+
+    fn expect(&mut self, text: &str) -> Option<()> {
+        self.rest = self.rest.strip_prefix(text)?;
+        Some(())
+    }
+
+    // Number of at least min and at most max digits
+    fn number(&mut self, min: usize, max: usize) -> Option<i64> {
+        let digits = self.rest.chars().take(max).take_while(char::is_ascii_digit).count();
+        if digits < min {
+            return None;
+        }
+        let (number, rest) = self.rest.split_at(digits);
+        self.rest = rest;
+        number.parse().ok()
+    }
+
+    // Offset east of UTC such as +02:00, +0200 or +02
+    fn offset(&mut self) -> Option<i64> {
+        let sign = match self.rest.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        self.rest = &self.rest[1..];
+        let hours = self.number(2, 2)?;
+        let minutes = match self.expect(":") {
+            Some(_) => self.number(2, 2)?,
+            None => self.number(2, 2).unwrap_or(0),
+        };
+        (hours <= 23 && minutes <= 59).then_some(sign * (hours * 3600 + minutes * 60))
+    }
+}
+
+// Offset of the local time zone at the time. The zone is given by the TZ variable, either a name such as
+// Europe/Stockholm, a path to a zoneinfo file or a POSIX rule such as CET-1CEST,M3.5.0,M10.5.0/3, and
+// otherwise by /etc/localtime. Unknown zones are UTC.
+pub fn local_offset(time: i64) -> i64 {
+    let zone = match env::var("TZ") {
+        Ok(zone) => {
+            let zone = zone.strip_prefix(':').unwrap_or(&zone);
+            let path = if zone.starts_with('/') { zone.to_string() } else { format!("/usr/share/zoneinfo/{zone}") };
+            match fs::read(path) {
+                Ok(bytes) if !zone.is_empty() => Zone::parse(&bytes),
+                _ => Rule::parse(zone).map(Zone::from),
+            }
+        }
+        Err(_) => fs::read("/etc/localtime").ok().and_then(|bytes| Zone::parse(&bytes)),
+    };
+    zone.map_or(0, |zone| zone.offset(time))
+}
+
+// Time zone of a zoneinfo file, as described by RFC 8536
+#[derive(Debug)]
+pub struct Zone {
+    // Times when the offset changes and the offset from then on
+    transitions: Vec<(i64, i64)>,
+    // Offset before the first transition
+    initial: i64,
+    // Rule for times after the last transition
+    rule: Option<Rule>,
+}
+
+impl Zone {
+
+    pub fn parse(bytes: &[u8]) -> Option<Zone> {
+        if bytes.get(..4)? != b"TZif" {
+            return None;
+        }
+        let (header, block) = (Header::parse(bytes)?, &bytes[44..]);
+        // Version 2 and later repeat the data with 64-bit times after the 32-bit ones
+        let (header, block, time_size) = match bytes[4] {
+            0 => (header, block, 4),
+            _ => {
+                let block = block.get(header.size(4)..)?;
+                (Header::parse(block)?, block.get(44..)?, 8)
+            }
+        };
+
+        let mut reader = Reader { bytes: block };
+        let times = (0..header.times).map(|_| reader.signed(time_size)).collect::<Option<Vec<_>>>()?;
+        let indices = (0..header.times).map(|_| reader.signed(1)).collect::<Option<Vec<_>>>()?;
+        let offsets = (0..header.types)
+            .map(|_| {
+                let offset = reader.signed(4)?;
+                reader.signed(2)?;
+                Some(offset)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let transitions = times.into_iter().zip(indices)
+            .map(|(time, index)| Some((time, *offsets.get(usize::try_from(index).ok()?)?)))
+            .collect::<Option<Vec<_>>>()?;
+        let footer = match time_size {
+            8 => block.get(header.size(8)..)
+                .and_then(|footer| std::str::from_utf8(footer).ok())
+                .and_then(|footer| footer.trim().lines().next())
+                .and_then(Rule::parse),
+            _ => None,
+        };
+        Some(Zone { transitions, initial: *offsets.first()?, rule: footer })
+    }
+
+    pub fn offset(&self, time: i64) -> i64 {
+        let index = self.transitions.partition_point(|(transition, _)| *transition <= time);
+        match (index, &self.rule) {
+            (0, Some(rule)) if self.transitions.is_empty() => rule.offset(time),
+            (0, _) => self.initial,
+            (index, Some(rule)) if index == self.transitions.len() => rule.offset(time),
+            (index, _) => self.transitions[index - 1].1,
+        }
+    }
+}
+
+impl From<Rule> for Zone {
+    fn from(rule: Rule) -> Self {
+        Zone { transitions: Vec::new(), initial: rule.standard, rule: Some(rule) }
+    }
+}
+
+struct Header {
+    leaps: usize,
+    times: usize,
+    types: usize,
+    characters: usize,
+    standard_indicators: usize,
+    universal_indicators: usize,
+}
+
+impl Header {
+
+    fn parse(bytes: &[u8]) -> Option<Header> {
+        let mut reader = Reader { bytes: bytes.get(20..44)? };
+        let mut count = || usize::try_from(reader.signed(4)?).ok();
+        Some(Header {
+            universal_indicators: count()?,
+            standard_indicators: count()?,
+            leaps: count()?,
+            times: count()?,
+            types: count()?,
+            characters: count()?,
+        })
+    }
+
+    // Size of the data block with times of the size
+    fn size(&self, time_size: usize) -> usize {
+        self.times * (time_size + 1) + self.types * 6 + self.characters + self.leaps * (time_size + 4)
+            + self.standard_indicators + self.universal_indicators
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+
+    // Big-endian signed number of the size in bytes
+    fn signed(&mut self, size: usize) -> Option<i64> {
+        let bytes = self.bytes.get(..size)?;
+        self.bytes = &self.bytes[size..];
+        let number = bytes.iter().fold(0i64, |number, byte| number << 8 | i64::from(*byte));
+        let unused = 64 - 8 * size as u32;
+        Some(number << unused >> unused)
+    }
+}
+
+// POSIX time zone rule such as EST5EDT,M3.2.0,M11.1.0, a standard offset and optionally
+// a daylight saving offset with the days and times it starts and ends
+#[derive(Debug)]
+pub struct Rule {
+    standard: i64,
+    daylight: Option<(i64, Transition, Transition)>,
+}
+
+#[derive(Debug)]
+enum Transition {
+    // Day of the year from 1 to 365, February 29 is never counted
+    Julian(i64, i64),
+    // Day of the year from 0 to 365
+    Day(i64, i64),
+    // Month, week from 1 to 5 where 5 is the last and weekday from 0, Sunday, to 6
+    Weekday(i64, i64, i64, i64),
+}
+
+impl Rule {
+
+    pub fn parse(rule: &str) -> Option<Rule> {
+        let mut scanner = Scanner { rest: rule };
+        scanner.zone_name()?;
+        let standard = -scanner.duration()?;
+        if scanner.rest.is_empty() {
+            return Some(Rule { standard, daylight: None });
+        }
+
+        scanner.zone_name()?;
+        let daylight = match scanner.rest.chars().next() {
+            Some(',') | None => standard + 3600,
+            _ => -scanner.duration()?,
+        };
+        let (start, end) = match scanner.rest {
+            // Without days the rule of the United States is used
+            "" => (Transition::Weekday(3, 2, 0, 7200), Transition::Weekday(11, 1, 0, 7200)),
+            _ => {
+                scanner.expect(",")?;
+                let start = scanner.transition()?;
+                scanner.expect(",")?;
+                (start, scanner.transition()?)
+            }
+        };
+        scanner.rest.is_empty().then_some(Rule { standard, daylight: Some((daylight, start, end)) })
+    }
+
+    pub fn offset(&self, time: i64) -> i64 {
+        let Some((daylight, start, end)) = &self.daylight else {
+            return self.standard;
+        };
+        let (year, _, _) = civil_from_days((time + self.standard).div_euclid(DAY));
+        // Daylight saving starts at a time of the standard offset and ends at a time of the daylight saving offset
+        let start = start.local_time(year) - self.standard;
+        let end = end.local_time(year) - daylight;
+        let is_daylight = match start < end {
+            true => start <= time && time < end,
+            // Southern hemisphere, daylight saving spans the new year
+            false => !(end <= time && time < start),
+        };
+        if is_daylight { *daylight } else { self.standard }
+    }
+}
+
+impl Transition {
+
+    // Local time of the transition in the year, as seconds since the epoch
+    fn local_time(&self, year: i64) -> i64 {
+        let (day, time) = match *self {
+            Transition::Julian(day, time) => {
+                let leap_day = i64::from(is_leap_year(year) && day >= 60);
+                (days_from_civil(year, 1, 1) + day - 1 + leap_day, time)
+            }
+            Transition::Day(day, time) => (days_from_civil(year, 1, 1) + day, time),
+            Transition::Weekday(month, week, weekday, time) => {
+                let first = days_from_civil(year, month, 1);
+                // The epoch was a Thursday
+                let first_weekday = (first + 4).rem_euclid(7);
+                let mut day = first + (weekday - first_weekday).rem_euclid(7) + (week - 1) * 7;
+                if day >= first + days_in_month(year, month) {
+                    day -= 7;
+                }
+                (day, time)
+            }
+        };
+        day * DAY + time
+    }
+}
+
+impl Scanner<'_> {
+
+    // Name such as CET or a quoted name such as <+03>
+    fn zone_name(&mut self) -> Option<()> {
+        let length = match self.rest.strip_prefix('<') {
+            Some(rest) => rest.find('>')? + 2,
+            None => self.rest.find(|char: char| !char.is_ascii_alphabetic()).unwrap_or(self.rest.len()),
+        };
+        self.rest = (length >= 3).then(|| &self.rest[length..])?;
+        Some(())
+    }
+
+    // Duration such as 5, -1, 02:30 or +10:30:15 in seconds
+    fn duration(&mut self) -> Option<i64> {
+        let sign = match self.rest.chars().next()? {
+            '-' => -1,
+            '+' => 1,
+            _ => 0,
+        };
+        if sign != 0 {
+            self.rest = &self.rest[1..];
+        }
+        let hours = self.number(1, 3)?;
+        let minutes = match self.expect(":") {
+            Some(_) => self.number(2, 2)?,
+            None => 0,
+        };
+        let seconds = match self.expect(":") {
+            Some(_) => self.number(2, 2)?,
+            None => 0,
+        };
+        Some(if sign < 0 { -1 } else { 1 } * (hours * 3600 + minutes * 60 + seconds))
+    }
+
+    // Day such as M3.5.0, J60 or 59 optionally followed by a time such as /3 or /-1:30
+    fn transition(&mut self) -> Option<Transition> {
+        let day = match self.rest.chars().next()? {
+            'M' => {
+                self.expect("M")?;
+                let month = self.number(1, 2)?;
+                self.expect(".")?;
+                let week = self.number(1, 1)?;
+                self.expect(".")?;
+                let weekday = self.number(1, 1)?;
+                ((1..=12).contains(&month) && (1..=5).contains(&week) && weekday <= 6)
+                    .then_some(Transition::Weekday(month, week, weekday, 0))?
+            }
+            'J' => {
+                self.expect("J")?;
+                let day = self.number(1, 3)?;
+                (1..=365).contains(&day).then_some(Transition::Julian(day, 0))?
+            }
+            _ => {
+                let day = self.number(1, 3)?;
+                (day <= 365).then_some(Transition::Day(day, 0))?
+            }
+        };
+        let time = match self.expect("/") {
+            Some(_) => self.duration()?,
+            None => 7200,
+        };
+        Some(match day {
+            Transition::Julian(day, _) => Transition::Julian(day, time),
+            Transition::Day(day, _) => Transition::Day(day, time),
+            Transition::Weekday(month, week, weekday, _) => Transition::Weekday(month, week, weekday, time),
+        })
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod test {
+    use crate::evaluator::time::{civil_from_days, days_from_civil, format_duration, format_rfc3339, parse_duration, parse_rfc3339, Rule, Zone};
+
+    #[test]
+    fn test_civil_dates() {
+        let tests = [
+            ((1970, 1, 1), 0),
+            ((2000, 3, 1), 11_017),
+            ((2024, 2, 29), 19_782),
+            ((1969, 12, 31), -1),
+            ((1600, 1, 1), -135_140),
+        ];
+
+        for (date, days) in tests {
+            assert_eq!(days, days_from_civil(date.0, date.1, date.2), "Failed days of {date:?}");
+            assert_eq!(date, civil_from_days(days), "Failed date of {days}");
+        }
+        for days in -800_000..800_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days, days_from_civil(year, month, day), "Failed round trip of {days}");
+        }
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let tests = [
+            (0., 0, "1970-01-01T00:00:00Z"),
+            (1_714_566_600., 0, "2024-05-01T12:30:00Z"),
+            (1_714_566_600.25, 7200, "2024-05-01T14:30:00.250+02:00"),
+            (1_714_566_600., -19_800, "2024-05-01T07:00:00-05:30"),
+            (-0.5, 0, "1969-12-31T23:59:59.500Z"),
+            (0.007, 0, "1970-01-01T00:00:00.007Z"),
+        ];
+
+        for (time, offset, expected) in tests {
+            assert_eq!(expected, format_rfc3339(time, offset), "Failed to format {time} at {offset}");
+            assert_eq!(Some(time), parse_rfc3339(expected), "Failed to parse {expected}");
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        let tests = [
+            ("2024-05-01", Some(1_714_521_600.)),
+            ("2024-05-01T12:30", Some(1_714_566_600.)),
+            ("2024-05-01 12:30:00+0200", Some(1_714_559_400.)),
+            ("2024-05-01t12:30:00.125z", Some(1_714_566_600.125)),
+            ("2024-05-01T12:30:00-05", Some(1_714_584_600.)),
+            ("2023-02-29", None),
+            ("2024-13-01", None),
+            ("2024-05-01T24:00", None),
+            ("2024-05-01T12:30:00+2", None),
+            ("2024-05-01T12:30:00Z ", None),
+            ("2024-5-1", None),
+            ("", None),
+        ];
+
+        for (timestamp, expected) in tests {
+            assert_eq!(expected, parse_rfc3339(timestamp), "Failed to parse {timestamp}");
+        }
+    }
+
+    #[test]
+    fn test_durations() {
+        let tests = [
+            ("PT0S", 0.),
+            ("PT1H30M", 5400.),
+            ("P1DT2H30M0.5S", 95_400.5),
+            ("P1D", 86_400.),
+            ("-PT1.5S", -1.5),
+        ];
+
+        for (duration, seconds) in tests {
+            assert_eq!(Some(seconds), parse_duration(duration), "Failed to parse {duration}");
+            assert_eq!(duration, format_duration(seconds), "Failed to format {seconds}");
+        }
+        assert_eq!(Some(7. * 86_400.), parse_duration("P1W"));
+        for invalid in ["", "P", "PT", "P1Y", "P1M", "PT1M1H", "1H", "PT-1S"] {
+            assert_eq!(None, parse_duration(invalid), "Expected {invalid} to be invalid");
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let tests = [
+            ("UTC0", "2024-07-01T00:00:00Z", 0),
+            ("<+0530>-5:30", "2024-07-01T00:00:00Z", 19_800),
+            ("EST5EDT", "2024-07-01T00:00:00Z", -14_400),
+            ("CET-1CEST,M3.5.0,M10.5.0/3", "2024-03-31T00:59:59Z", 3600),
+            ("CET-1CEST,M3.5.0,M10.5.0/3", "2024-03-31T01:00:00Z", 7200),
+            ("CET-1CEST,M3.5.0,M10.5.0/3", "2024-10-27T00:59:59Z", 7200),
+            ("CET-1CEST,M3.5.0,M10.5.0/3", "2024-10-27T01:00:00Z", 3600),
+            ("AEST-10AEDT,M10.1.0,M4.1.0/3", "2024-01-01T00:00:00Z", 39_600),
+            ("AEST-10AEDT,M10.1.0,M4.1.0/3", "2024-07-01T00:00:00Z", 36_000),
+            ("XST3XDT,J60/0,300", "2024-03-01T03:00:00Z", -7200),
+            ("XST3XDT,J60/0,300", "2024-02-29T03:00:00Z", -10_800),
+        ];
+
+        for (rule, timestamp, expected) in tests {
+            let time = parse_rfc3339(timestamp).unwrap() as i64;
+            let zone = Rule::parse(rule).unwrap_or_else(|| panic!("Failed to parse {rule}"));
+            assert_eq!(expected, zone.offset(time), "Failed offset of {rule} at {timestamp}");
+        }
+        for invalid in ["", "5", "CET", "CET-1CEST,M13.1.0,M10.5.0", "CET-1CEST,M3.5.0", "CET-1 "] {
+            assert!(Rule::parse(invalid).is_none(), "Expected {invalid} to be invalid");
+        }
+    }
+
+    #[test]
+    fn test_zoneinfo() {
+        // Version 1 file with a transition at 1000 from the offset 3600 to 7200
+        let mut bytes = b"TZif".to_vec();
+        bytes.extend([0; 16]);
+        [0u32, 0, 0, 1, 2, 4].iter().for_each(|count| bytes.extend(count.to_be_bytes()));
+        bytes.extend(1000i32.to_be_bytes());
+        bytes.push(1);
+        bytes.extend(3600i32.to_be_bytes());
+        bytes.extend([0, 0]);
+        bytes.extend(7200i32.to_be_bytes());
+        bytes.extend([1, 2]);
+        bytes.extend(b"A\0B\0");
+
+        let zone = Zone::parse(&bytes).expect("Failed to parse zoneinfo");
+        assert_eq!(3600, zone.offset(-1_000_000));
+        assert_eq!(3600, zone.offset(999));
+        assert_eq!(7200, zone.offset(1000));
+        assert_eq!(7200, zone.offset(1_000_000));
+        assert!(Zone::parse(&bytes[..50]).is_none());
+        assert!(Zone::parse(b"not a zone").is_none());
+    }
+}
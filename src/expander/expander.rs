@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::rc::Rc;
+
+use crate::ast::ast::{Node, Program};
+use crate::ast::expression::Expression;
+use crate::evaluator::evaluator::Eval;
+use crate::evaluator::permissions;
+use crate::evaluator::quote::{at_call_site, to_node};
+use crate::lexer::lexer::Lexer;
+use crate::object::environment::Environment;
+use crate::object::object::Object;
+use crate::parser::parser::Parser;
+
+// Expansions of expansions deeper than this are most likely a macro expanding to itself
+const MAX_EXPANSION_DEPTH: usize = 256;
+
+#[derive(Debug)]
+pub struct ExpansionError {
+    pub col: u32,
+    pub row: u32,
+    pub message: String,
+}
+
+impl Display for ExpansionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}, Col: {}: {}", self.row, self.col, self.message)
+    }
+}
+
+struct Macro {
+    params: Rc<[Node]>,
+    vararg: Rc<Option<Node>>,
+    body: Rc<Node>,
+}
+
+// Replaces macro calls with the code the macro evaluates to. Macros are defined by
+// the macro-expressions in the order they are written, including those of included files,
+// and stay defined for the programs expanded after, e.g. the following lines of the repl.
+#[derive(Default)]
+pub struct Expander {
+    macros: HashMap<Rc<str>, Rc<Macro>>,
+    // Files whose macros are being defined, guards against files including each other
+    including: Vec<String>,
+}
+
+impl Expander {
+
+    pub fn new() -> Expander {
+        Expander::default()
+    }
+
+    pub fn expand(&mut self, program: Program) -> Result<Program, Vec<ExpansionError>> {
+        let mut nodes = program.nodes;
+        let mut errors = Vec::new();
+        for node in nodes.iter_mut() {
+            self.expand_node(node, 0, &mut errors);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Program { nodes })
+    }
+
+    fn expand_node(&mut self, node: &mut Node, depth: usize, errors: &mut Vec<ExpansionError>) {
+        match &node.expression {
+            Expression::Macro(name, params, vararg, body) => {
+                if let Expression::Identifier(name) = &name.expression {
+                    let definition = Macro { params: params.clone(), vararg: vararg.clone(), body: body.clone() };
+                    self.macros.insert(name.clone(), definition.into());
+                }
+                return;
+            }
+            // Quoted code is data, templates are expanded once the unquotes are filled in
+            Expression::Quote(_) | Expression::Quasiquote(_) => return,
+            Expression::Include(target) => if let Expression::String(path) = &target.expression {
+                self.define_included(path);
+            },
+            // Like function calls, expansions are positioned at the name of the macro
+            Expression::ExpressionLiteral(nodes) => if let Some((name, definition)) = self.called_macro(nodes) {
+                if depth >= MAX_EXPANSION_DEPTH {
+                    errors.push(error(&nodes[0], format!("Expansion of macro '{name}' exceeded the maximum depth of {MAX_EXPANSION_DEPTH}")));
+                    return;
+                }
+                match call(&name, &definition, &nodes[0], &nodes[1..]) {
+                    Ok(expanded) => {
+                        *node = expanded;
+                        self.expand_node(node, depth + 1, errors);
+                    }
+                    Err(message) => errors.push(error(&nodes[0], message)),
+                }
+                return;
+            },
+            _ => {}
+        }
+        for child in node.children_mut() {
+            self.expand_node(child, depth, errors);
+        }
+    }
+
+    fn called_macro(&self, nodes: &[Node]) -> Option<(Rc<str>, Rc<Macro>)> {
+        let Expression::Identifier(name) = &nodes.first()?.expression else {
+            return None;
+        };
+        self.macros.get(name).map(|definition| (name.clone(), definition.clone()))
+    }
+
+    // Included files are expanded when they're evaluated, this only defines their macros.
+    // Files that can't be read are left for the include to report.
+    fn define_included(&mut self, path: &str) {
+        if self.including.iter().any(|including| including == path) || permissions::require_read(path).is_err() {
+            return;
+        }
+        let Ok(content) = read_to_string(path) else {
+            return;
+        };
+        let Ok(program) = Parser::from(Lexer::from(content.as_str())).parse_program() else {
+            return;
+        };
+        self.including.push(path.to_string());
+        let _ = self.expand(program);
+        self.including.pop();
+    }
+}
+
+// Evaluates the body of the macro with its parameters bound to the code of the arguments
+fn call(name: &str, definition: &Macro, node: &Node, arguments: &[Node]) -> Result<Node, String> {
+    let params = definition.params.len();
+    match definition.vararg.as_ref() {
+        None if arguments.len() != params => return Err(format!("{name}: wrong number of arguments. got={}, want={params}", arguments.len())),
+        Some(_) if arguments.len() < params => return Err(format!("{name}: wrong number of arguments. got={}, want=at least {params}", arguments.len())),
+        _ => {}
+    }
+
+    let mut environment = Environment::new();
+    for (param, argument) in definition.params.iter().zip(arguments) {
+        if let Expression::Identifier(param) = &param.expression {
+            environment.set(param.clone(), Object::Code(argument.clone().into()));
+        }
+    }
+    if let Some(Node { expression: Expression::Identifier(vararg), .. }) = definition.vararg.as_ref() {
+        let rest = arguments[params..].iter()
+            .map(|argument| Object::Code(argument.clone().into()))
+            .collect();
+        environment.set(vararg.clone(), Object::Array(rest));
+    }
+
+    let expanded = at_call_site(&node.token, || definition.body.eval(&mut environment))
+        .map_err(|error| format!("Expansion of macro '{name}' failed: {error}"))?;
+    to_node(expanded, &node.token)
+        .map_err(|error| format!("Expansion of macro '{name}' failed: {error}"))
+}
+
+fn error(node: &Node, message: String) -> ExpansionError {
+    ExpansionError { col: node.token.col, row: node.token.row, message }
+}
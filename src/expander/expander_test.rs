@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod test {
+    use crate::ast::ast::{Program, AST};
+    use crate::evaluator::evaluator::Eval;
+    use crate::expander::expander::Expander;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::environment::Environment;
+    use crate::object::object::{Object, Viewable};
+    use crate::parser::parser::Parser;
+    use crate::resolver::resolver::resolve;
+
+    const UNLESS: &str = "(macro unless |condition ...body| (quasiquote (if (unquote condition) () ((unquote ..body)))))";
+
+    #[test]
+    fn test_expand() {
+        let tests = [
+            ("(unless (> a 1) (f a) (g))", "(if (> a 1) () ((f a) (g)))"),
+            ("(fn |a| (unless a (unless b (f))))", "(fn |a| (if a () ((if b () ((f))))))"),
+            ("(quote (unless a (f)))", "(quote (unless a (f)))"),
+            ("(unless_not a)", "(unless_not a)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut expander = Expander::new();
+            expander.expand(parse(UNLESS)).unwrap();
+            let program = expander.expand(parse(input)).unwrap();
+            assert_eq!(expected, program.string().as_ref(), "Failed to expand: {input}");
+        }
+    }
+
+    #[test]
+    fn test_eval_expanded() {
+        let tests = [
+            ("(unless false 1 2)", Object::Integer(2)),
+            ("(unless true 1 2)", Object::Unit),
+            ("(macro twice |x| (quasiquote (+ (unquote x) (unquote x)))) (twice 21)", Object::Integer(42)),
+            ("(macro constant |x| (+ 1 2)) (constant ignored)", Object::Integer(3)),
+            ("(macro literal |...xs| xs) (@ 1 (literal 1 2 3))", Object::Integer(2)),
+            // Bindings made by the template don't capture the arguments
+            ("(macro swap |a b| (quasiquote (set (tmp (unquote a)) ((unquote a) (unquote b)) ((unquote b) tmp))))
+              (set (tmp 1) (other 2))
+              (swap tmp other)
+              [tmp other]", Object::Array([Object::Integer(2), Object::Integer(1)].into())),
+            ("(macro with_x |body| (quasiquote ((set (x 10)) (unquote body))))
+              (set (x 1))
+              (with_x x)", Object::Integer(1)),
+            // Unquoted names are not renamed, so they can be bound on purpose
+            ("(macro with_it |value body| (quasiquote (§ ((set ((unquote (quote it)) (unquote value))) (unquote body)))))
+              (with_it 5 (* it 2))", Object::Integer(10)),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = apply_eval(input).unwrap_or_else(|error| panic!("Failed to evaluate: {input}: {error}"));
+            assert_eq!(expected, evaluated, "Failed to evaluate: {input}");
+        }
+    }
+
+    #[test]
+    fn test_expansion_errors() {
+        let tests = [
+            ("(unless)", "Row 1, Col: 2: unless: wrong number of arguments. got=0, want=at least 1"),
+            ("(macro one |x| x) (one 1 2)", "Row 1, Col: 20: one: wrong number of arguments. got=2, want=1"),
+            ("(macro loop |x| (quasiquote (loop (unquote x)))) (loop 1)",
+             "Row 1, Col: 51: Expansion of macro 'loop' exceeded the maximum depth of 256"),
+            ("(macro broken |x| (+ x 1)) (broken 1)",
+             "Row 1, Col: 29: Expansion of macro 'broken' failed: Row 1, Col: 20: Type mismatch (+ Code Integer)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut expander = Expander::new();
+            expander.expand(parse(UNLESS)).unwrap();
+            let errors = expander.expand(parse(input)).err().unwrap_or_else(|| panic!("Expected error for: {input}"));
+            assert_eq!(expected, errors[0].to_string(), "Failed to expand: {input}");
+        }
+    }
+
+    #[test]
+    fn test_expanded_errors_are_reported_at_call_site() {
+        let input = "(macro add_one |x| (quasiquote (+ (unquote x) 1)))\n(set (a \"a\"))\n  (add_one [])";
+
+        let error = apply_eval(input).expect_err("Expected type mismatch");
+        assert_eq!("Row 3, Col: 4: Type mismatch (+ Array Integer)", error.to_string());
+    }
+
+    fn parse(input: &str) -> Program {
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {input}"))
+    }
+
+    fn apply_eval(input: &str) -> Result<Object, String> {
+        let mut expander = Expander::new();
+        expander.expand(parse(UNLESS)).unwrap();
+        let program = expander.expand(parse(input))
+            .map_err(|errors| errors[0].to_string())?;
+
+        let mut environment = Environment::new();
+        if let Some(error) = resolve(&program, &environment).first() {
+            return Err(error.to_string());
+        }
+        program.eval(&mut environment)
+            .map_err(|error| error.to_string())
+            .map(|object| match object {
+                Object::Code(_) => Object::String(object.view().into()),
+                object => object,
+            })
+    }
+}
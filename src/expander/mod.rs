@@ -0,0 +1,4 @@
+pub mod expander;
+
+#[cfg(test)]
+mod expander_test;
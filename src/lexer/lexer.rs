@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::str::CharIndices;
 
@@ -13,6 +14,9 @@ pub struct Lexer<'a> {
     current: char,
     peek: Option<(usize, char)>,
     eof: bool,
+    // Columns from the opening quote of the characters of string literals with escapes,
+    // by the position of the literal
+    string_offsets: HashMap<(u32, u32), Vec<u32>>,
 }
 
 impl<'a> From<&'a str> for Lexer<'a> {
@@ -26,6 +30,7 @@ impl<'a> From<&'a str> for Lexer<'a> {
             current: '\0',
             peek: Some((0, '\0')),
             eof: false,
+            string_offsets: HashMap::new(),
         };
 
         for i in 0..2 {
@@ -47,7 +52,7 @@ impl Lexer<'_> {
             '.' => self.dot_token(),
             ',' => self.create_token(TokenType::Comma, self.current.literal()),
             '§' => self.create_token(TokenType::Section, self.current.literal()),
-            '!' => self.create_token(TokenType::Bang, self.current.literal()),
+            '!' => self.equals_token(TokenType::Bang, TokenType::BangEquals),
             '|' => self.create_token(TokenType::Pipe, self.current.literal()),
             '(' => self.create_token(TokenType::LParen, self.current.literal()),
             ')' => self.create_token(TokenType::RParen, self.current.literal()),
@@ -55,14 +60,15 @@ impl Lexer<'_> {
             '+' => self.create_token(TokenType::Plus, self.current.literal()),
             '-' => return self.read_minus(),
             '*' => self.create_token(TokenType::Asterisk, self.current.literal()),
-            '/' => self.create_token(TokenType::Slash, self.current.literal()),
+            '/' => self.slash_token(),
+            '%' => self.create_token(TokenType::Percent, self.current.literal()),
             '=' => self.create_token(TokenType::Equals, self.current.literal()),
             '{' => self.create_token(TokenType::LBrace, self.current.literal()),
             '}' => self.create_token(TokenType::RBrace, self.current.literal()),
             '[' => self.create_token(TokenType::LBracket, self.current.literal()),
             ']' => self.create_token(TokenType::RBracket, self.current.literal()),
-            '<' => self.create_token(TokenType::LesserThan, self.current.literal()),
-            '>' => self.create_token(TokenType::GreaterThan, self.current.literal()),
+            '<' => self.equals_token(TokenType::LesserThan, TokenType::LesserEquals),
+            '>' => self.equals_token(TokenType::GreaterThan, TokenType::GreaterEquals),
             '"' => self.read_string(),
             '\0' => {
                 self.eof = true;
@@ -83,6 +89,12 @@ impl Lexer<'_> {
         return token;
     }
 
+    // Columns from the opening quote of the characters of the string literal, None when
+    // they're one after another as without escapes
+    pub(crate) fn take_string_offsets(&mut self, token: &Token) -> Option<Vec<u32>> {
+        self.string_offsets.remove(&(token.row, token.col))
+    }
+
     // fn create_token<'a>(&self, token_type: TokenType, literal: &'a str) -> Token<'a> {
     fn create_token(&self, token_type: TokenType, literal: Rc<str>) -> Token {
         Token { token_type, row: self.row, col: self.col, literal }
@@ -111,8 +123,19 @@ impl Lexer<'_> {
             "false" => TokenType::False,
             "if" => TokenType::If,
             "when" => TokenType::When,
+            "match" => TokenType::Match,
             "while" => TokenType::While,
+            "for" => TokenType::For,
             "include" => TokenType::Include,
+            "test" => TokenType::Test,
+            "macro" => TokenType::Macro,
+            "record" => TokenType::Record,
+            "enum" => TokenType::Enum,
+            "quote" => TokenType::Quote,
+            "quasiquote" => TokenType::Quasiquote,
+            "unquote" => TokenType::Unquote,
+            "and" => TokenType::And,
+            "or" => TokenType::Or,
             _ => TokenType::Ident,
         }
     }
@@ -122,13 +145,43 @@ impl Lexer<'_> {
         let col = self.col;
 
         let mut string = String::new();
+        let mut offsets = Vec::new();
+        let mut escaped = false;
         // self.read_char();
         loop {
             self.read_char();
             if self.current == '"' || self.current == '\0' {
                 break;
             }
+            let offset = self.col - col;
+            // Backslashes escape quotes, backslashes, newlines, tabs and carriage returns,
+            // before other characters they're kept as they are
+            if self.current == '\\' {
+                escaped = true;
+                self.read_char();
+                match self.current {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    '"' | '\\' => string.push(self.current),
+                    '\0' => break,
+                    other => {
+                        string.push('\\');
+                        offsets.push(offset);
+                        string.push(other);
+                        offsets.push(self.col - col);
+                        continue;
+                    }
+                }
+                offsets.push(offset);
+                continue;
+            }
             string.push(self.current);
+            offsets.push(offset);
+        }
+        if escaped {
+            offsets.push(self.col - col);
+            self.string_offsets.insert((row, col), offsets);
         }
         // return self.input[position..self.position].to_string();
         return Token {
@@ -186,6 +239,28 @@ impl Lexer<'_> {
         return self.create_token(TokenType::Illegal, self.current.literal());
     }
 
+    // The operator, or the one with = added when it's followed by =
+    fn equals_token(&mut self, token_type: TokenType, with_equals: TokenType) -> Token {
+        if let Some((_, '=')) = self.peek {
+            let col = self.col;
+            let row = self.row;
+            let literal = Rc::from(format!("{}=", self.current));
+            self.read_char();
+            return Token {token_type: with_equals, col, row, literal};
+        }
+        return self.create_token(token_type, self.current.literal());
+    }
+
+    fn slash_token(&mut self) -> Token {
+        if let Some((_, '/')) = self.peek {
+            let col = self.col;
+            let row = self.row;
+            self.read_char();
+            return Token {token_type: TokenType::DoubleSlash, col, row, literal: Rc::from("//")};
+        }
+        return self.create_token(TokenType::Slash, self.current.literal());
+    }
+
     fn dot_token(&mut self) -> Token {
         if let Some((_, '.')) = self.peek {
             let col = self.col;
@@ -8,7 +8,7 @@ mod test {
     #[test]
     fn test_next_token_simple() {
         // let input = "=+(){},;";
-        let input = "§(+)={}::";
+        let input = "§(+)={}::%///<=>=!=<>!";
 
         let expected = [
             (TokenType::Section, "§"),
@@ -20,6 +20,15 @@ mod test {
             (TokenType::LBrace, "{"),
             (TokenType::RBrace, "}"),
             (TokenType::DoubleColon, "::"),
+            (TokenType::Percent, "%"),
+            (TokenType::DoubleSlash, "//"),
+            (TokenType::Slash, "/"),
+            (TokenType::LesserEquals, "<="),
+            (TokenType::GreaterEquals, ">="),
+            (TokenType::BangEquals, "!="),
+            (TokenType::LesserThan, "<"),
+            (TokenType::GreaterThan, ">"),
+            (TokenType::Bang, "!"),
             // (TokenType::Comma, ","),
             // (TokenType::Semicolon, ";"),
             (TokenType::EOF, ""),
@@ -54,7 +63,8 @@ mod test {
         (while (false) (\"hello\"))\
         (when (false) (\"hello\") (true) (\"world\"))\
         (.. ...)\
-        (include \"file.kvist\")
+        (include \"file.kvist\")\
+        (match x n::Integer)
         ";
 
         let expected = [
@@ -173,6 +183,13 @@ mod test {
             (TokenType::Include, "include"),
             (TokenType::String, "file.kvist"),
             (TokenType::RParen, ")"),
+            (TokenType::LParen, "("),
+            (TokenType::Match, "match"),
+            (TokenType::Ident, "x"),
+            (TokenType::Ident, "n"),
+            (TokenType::DoubleColon, "::"),
+            (TokenType::Ident, "Integer"),
+            (TokenType::RParen, ")"),
             (TokenType::EOF, ""),
         ];
 
@@ -243,4 +260,21 @@ a b c
             assert_eq!(expect, (result.col, result.row))
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_string_escapes() {
+        let tests = [
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#""a\\b""#, "a\\b"),
+            (r#""line\nnext\ttab\r""#, "line\nnext\ttab\r"),
+            (r#""kept \d \{""#, "kept \\d \\{"),
+            (r#""unclosed \""#, "unclosed \""),
+        ];
+
+        for (input, expected) in tests {
+            let token = Lexer::from(input).next_token();
+            assert_eq!(TokenType::String, token.token_type, "Failed to lex: {input}");
+            assert_eq!(expected, token.literal.as_ref(), "Failed to lex: {input}");
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt::{Display, Formatter, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+
+    pub fn get(&self, key: &str) -> &Json {
+        let Json::Object(members) = self else {
+            return &Json::Null;
+        };
+        members.iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+            .unwrap_or(&Json::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Json::Number(value) if *value >= 0. => Some(*value as u32),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Json, String> {
+        let mut chars = input.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(character) => Err(format!("Unexpected trailing character '{character}'")),
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Json::Number(f64::from(value))
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Boolean(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(value: Vec<Json>) -> Self {
+        Json::Array(value)
+    }
+}
+
+impl<const N: usize> From<[(&str, Json); N]> for Json {
+    fn from(members: [(&str, Json); N]) -> Self {
+        Json::Object(members.into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect())
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Boolean(value) => write!(f, "{value}"),
+            Json::Number(value) if value.fract() == 0. && value.abs() < 1e15 => write!(f, "{}", *value as i64),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, value: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            character if (character as u32) < 0x20 => write!(f, "\\u{:04x}", character as u32)?,
+            character => f.write_char(character)?,
+        }
+    }
+    f.write_char('"')
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|character| character.is_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: &str) -> Result<(), String> {
+    for character in expected.chars() {
+        if chars.next() != Some(character) {
+            return Err(format!("Expected '{expected}'"));
+        }
+    }
+    Ok(())
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('n') => expect(chars, "null").map(|_| Json::Null),
+        Some('t') => expect(chars, "true").map(|_| Json::Boolean(true)),
+        Some('f') => expect(chars, "false").map(|_| Json::Boolean(false)),
+        Some('"') => parse_string(chars).map(Json::String),
+        Some('[') => parse_array(chars),
+        Some('{') => parse_object(chars),
+        Some(character) if *character == '-' || character.is_ascii_digit() => parse_number(chars),
+        Some(character) => Err(format!("Unexpected character '{character}'")),
+        None => Err("Unexpected end of input".to_string()),
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    let mut literal = String::new();
+    while let Some(character) = chars.next_if(|character| "+-.eE".contains(*character) || character.is_ascii_digit()) {
+        literal.push(character);
+    }
+    literal.parse::<f64>()
+        .map(Json::Number)
+        .map_err(|_| format!("Could not parse {literal} as number"))
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    chars.next();
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some('n') => string.push('\n'),
+                Some('r') => string.push('\r'),
+                Some('t') => string.push('\t'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('u') => string.push(parse_unicode_escape(chars)?),
+                Some(character) => string.push(character),
+                None => return Err("Unterminated string".to_string()),
+            },
+            Some(character) => string.push(character),
+            None => return Err("Unterminated string".to_string()),
+        }
+    }
+}
+
+fn parse_unicode_escape(chars: &mut Peekable<Chars>) -> Result<char, String> {
+    let code = parse_hex(chars)?;
+    if !(0xD800..0xDC00).contains(&code) {
+        return char::from_u32(code).ok_or(format!("Invalid unicode escape {code:x}"));
+    }
+    // High surrogate, the low surrogate must follow as another escape
+    expect(chars, "\\u")?;
+    let low = parse_hex(chars)?;
+    let code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+    char::from_u32(code).ok_or(format!("Invalid unicode escape {code:x}"))
+}
+
+fn parse_hex(chars: &mut Peekable<Chars>) -> Result<u32, String> {
+    let hex = chars.by_ref().take(4).collect::<String>();
+    u32::from_str_radix(&hex, 16).map_err(|_| format!("Invalid unicode escape '{hex}'"))
+}
+
+fn parse_array(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    chars.next();
+    let mut values = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(Json::Array(values));
+    }
+    loop {
+        values.push(parse_value(chars)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => return Ok(Json::Array(values)),
+            _ => return Err("Expected ',' or ']' in array".to_string()),
+        }
+    }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    chars.next();
+    let mut members = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(Json::Object(members));
+    }
+    loop {
+        skip_whitespace(chars);
+        if chars.peek() != Some(&'"') {
+            return Err("Expected string as object key".to_string());
+        }
+        let name = parse_string(chars)?;
+        skip_whitespace(chars);
+        expect(chars, ":")?;
+        members.push((name, parse_value(chars)?));
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => return Ok(Json::Object(members)),
+            _ => return Err("Expected ',' or '}' in object".to_string()),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod test {
+    use crate::lsp::json::Json;
+    use crate::lsp::server::Server;
+
+    const URI: &str = "file:///test.kvist";
+
+    #[test]
+    fn test_json_round_trip() {
+        let tests = [
+            ("null", "null"),
+            ("true", "true"),
+            ("[1, 2.5, -3]", "[1,2.5,-3]"),
+            ("{\"a\": {\"b\": [\"c\"]}}", "{\"a\":{\"b\":[\"c\"]}}"),
+            ("\"line\\nbreak \\\"quoted\\\" \\u00a7\"", "\"line\\nbreak \\\"quoted\\\" §\""),
+            ("\"\\ud83d\\ude00\"", "\"😀\""),
+        ];
+
+        for (input, expected) in tests {
+            let json = Json::parse(input).unwrap_or_else(|_| panic!("Failed to parse {input}"));
+            assert_eq!(expected, json.to_string(), "Failed round trip of: {input}");
+        }
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let tests = ["", "[1 2]", "{\"a\" 1}", "\"unterminated", "nul", "1 2"];
+
+        for input in tests {
+            assert!(Json::parse(input).is_err(), "Expected error for: {input}");
+        }
+    }
+
+    #[test]
+    fn test_publishes_diagnostics() {
+        let mut server = Server::default();
+
+        let notifications = open(&mut server, "(set (x 1)) (+ x y)");
+        assert_eq!(1, notifications.len());
+        let diagnostics = notifications[0].get("params").get("diagnostics");
+        let diagnostics = diagnostics.as_array().unwrap();
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Some("No binding for identifier 'y'"), diagnostics[0].get("message").as_str());
+        assert_eq!(Some(17), diagnostics[0].get("range").get("start").get("character").as_u32());
+
+        let notifications = open(&mut server, "(set (x 1)");
+        let diagnostics = notifications[0].get("params").get("diagnostics");
+        assert!(!diagnostics.as_array().unwrap().is_empty(), "Expected parse error diagnostic");
+        assert_eq!(Some(1), diagnostics.as_array().unwrap()[0].get("severity").as_u32());
+    }
+
+    #[test]
+    fn test_definition_and_hover() {
+        let mut server = Server::default();
+        open(&mut server, "(set (add (fn |a b| (+ a b))))\n(add 1 2)");
+
+        let response = request(&mut server, "textDocument/definition", 1, 1);
+        let start = response.get("result").get("range").get("start");
+        assert_eq!((Some(0), Some(6)), (start.get("line").as_u32(), start.get("character").as_u32()));
+
+        let response = request(&mut server, "textDocument/definition", 0, 24);
+        let start = response.get("result").get("range").get("start");
+        assert_eq!((Some(0), Some(15)), (start.get("line").as_u32(), start.get("character").as_u32()));
+
+        let response = request(&mut server, "textDocument/hover", 1, 2);
+        assert_eq!(Some("```\n(fn |a b|) add\n```"), response.get("result").get("contents").get("value").as_str());
+
+        let response = request(&mut server, "textDocument/hover", 0, 24);
+        assert_eq!(Some("```\n(parameter) a\n```"), response.get("result").get("contents").get("value").as_str());
+    }
+
+    #[test]
+    fn test_completion_and_symbols() {
+        let mut server = Server::default();
+        open(&mut server, "(set (x 1) (f (fn |param| param)))\n(f x)");
+
+        let response = request(&mut server, "textDocument/completion", 1, 1);
+        let names = labels(response.get("result"));
+        assert!(names.contains(&"println".to_string()));
+        assert!(names.contains(&"x".to_string()));
+        assert!(names.contains(&"f".to_string()));
+        assert!(!names.contains(&"param".to_string()), "Parameter out of scope should not be completed");
+
+        let response = request(&mut server, "textDocument/completion", 0, 28);
+        assert!(labels(response.get("result")).contains(&"param".to_string()));
+
+        let response = request(&mut server, "textDocument/documentSymbol", 0, 0);
+        let symbols = response.get("result").as_array().unwrap().iter()
+            .map(|symbol| (symbol.get("name").as_str().unwrap().to_string(), symbol.get("kind").as_u32().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("x".to_string(), 13), ("f".to_string(), 12)], symbols);
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut server = Server::default();
+
+        let response = server.handle(&Json::parse("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}").unwrap());
+        assert_eq!(Json::Boolean(true), *response[0].get("result").get("capabilities").get("hoverProvider"));
+
+        let response = server.handle(&Json::parse("{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"unknown\"}").unwrap());
+        assert_eq!(Some(-32601.), match response[0].get("error").get("code") { Json::Number(code) => Some(*code), _ => None });
+
+        server.handle(&Json::parse("{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"shutdown\"}").unwrap());
+        server.handle(&Json::parse("{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}").unwrap());
+        assert_eq!(Some(0), server.exit);
+    }
+
+    fn open(server: &mut Server, text: &str) -> Vec<Json> {
+        server.handle(&Json::from([
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/didOpen".into()),
+            ("params", Json::from([
+                ("textDocument", Json::from([
+                    ("uri", URI.into()),
+                    ("text", text.into()),
+                ])),
+            ])),
+        ]))
+    }
+
+    fn request(server: &mut Server, method: &str, line: u32, character: u32) -> Json {
+        let mut responses = server.handle(&Json::from([
+            ("jsonrpc", "2.0".into()),
+            ("id", 1.into()),
+            ("method", method.into()),
+            ("params", Json::from([
+                ("textDocument", Json::from([("uri", URI.into())])),
+                ("position", Json::from([("line", line.into()), ("character", character.into())])),
+            ])),
+        ]));
+        assert_eq!(1, responses.len(), "Expected one response to {method}");
+        responses.remove(0)
+    }
+
+    fn labels(items: &Json) -> Vec<String> {
+        items.as_array().unwrap().iter()
+            .map(|item| item.get("label").as_str().unwrap().to_string())
+            .collect()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod server;
+pub mod json;
+
+#[cfg(test)]
+mod lsp_test;
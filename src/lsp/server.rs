@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, Write};
+
+use crate::checker::checker::{analyze, Analysis, DefinitionKind, Position, Severity};
+use crate::evaluator::builtin::{builtin_names, builtins};
+use crate::lexer::lexer::Lexer;
+use crate::lsp::json::Json;
+use crate::parser::parser::Parser;
+
+const TEXT_DOCUMENT_SYNC_FULL: u32 = 1;
+const DIAGNOSTIC_ERROR: u32 = 1;
+const DIAGNOSTIC_WARNING: u32 = 2;
+const COMPLETION_FUNCTION: u32 = 3;
+const COMPLETION_VARIABLE: u32 = 6;
+const SYMBOL_FUNCTION: u32 = 12;
+const SYMBOL_VARIABLE: u32 = 13;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+pub(crate) fn start() {
+    let mut server = Server::default();
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout();
+
+    while let Some(content) = read_message(&mut stdin) {
+        let message = match Json::parse(&content) {
+            Ok(message) => message,
+            Err(error) => {
+                eprintln!("Could not parse message: {error}");
+                continue;
+            }
+        };
+        for response in server.handle(&message) {
+            let response = response.to_string();
+            let written = write!(stdout, "Content-Length: {}\r\n\r\n{response}", response.len())
+                .and_then(|_| stdout.flush());
+            if written.is_err() {
+                return;
+            }
+        }
+        if let Some(code) = server.exit {
+            std::process::exit(code);
+        }
+    }
+}
+
+fn read_message(input: &mut impl BufRead) -> Option<String> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut content = vec![0; length?];
+    input.read_exact(&mut content).ok()?;
+    String::from_utf8(content).ok()
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    pub exit: Option<i32>,
+}
+
+struct Document {
+    analysis: Analysis,
+    diagnostics: Vec<Json>,
+}
+
+impl Server {
+
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").as_str().unwrap_or("");
+        let params = message.get("params");
+        let id = message.get("id");
+
+        let result = match method {
+            "initialize" => Some(capabilities()),
+            "initialized" => None,
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Json::Null)
+            }
+            "exit" => {
+                self.exit = Some(if self.shutdown { 0 } else { 1 });
+                None
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                return self.update(document.get("uri"), document.get("text"));
+            }
+            "textDocument/didChange" => {
+                let text = params.get("contentChanges").as_array()
+                    .and_then(|changes| changes.last())
+                    .map(|change| change.get("text"))
+                    .unwrap_or(&Json::Null);
+                return self.update(params.get("textDocument").get("uri"), text);
+            }
+            "textDocument/didClose" => {
+                let uri = params.get("textDocument").get("uri");
+                self.documents.remove(uri.as_str().unwrap_or(""));
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/completion" => Some(self.completion(params)),
+            "textDocument/documentSymbol" => Some(self.document_symbols(params)),
+            _ if *id != Json::Null => {
+                return vec![Json::from([
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id.clone()),
+                    ("error", Json::from([
+                        ("code", Json::Number(f64::from(METHOD_NOT_FOUND))),
+                        ("message", format!("Method not found: {method}").into()),
+                    ])),
+                ])];
+            }
+            _ => None,
+        };
+
+        match (result, id) {
+            (Some(result), id) if *id != Json::Null => vec![Json::from([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result),
+            ])],
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(&mut self, uri: &Json, text: &Json) -> Vec<Json> {
+        let (Some(name), Some(text)) = (uri.as_str(), text.as_str()) else {
+            return Vec::new();
+        };
+        let document = analyze_document(text);
+        let diagnostics = document.diagnostics.clone();
+        self.documents.insert(name.to_string(), document);
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    fn document(&self, params: &Json) -> Option<&Document> {
+        let uri = params.get("textDocument").get("uri").as_str()?;
+        self.documents.get(uri)
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let (Some(document), Some(position)) = (self.document(params), position_of(params)) else {
+            return Json::Null;
+        };
+        let analysis = &document.analysis;
+
+        let definition = analysis.definitions.iter()
+            .find(|definition| covers(definition.row, definition.col, &definition.name, position))
+            .map(|definition| (definition.row, definition.col, &definition.name))
+            .or_else(|| analysis.references.iter()
+                .find(|reference| covers(reference.row, reference.col, &reference.name, position))
+                .and_then(|reference| reference.definition.map(|(row, col)| (row, col, &reference.name))));
+
+        match definition {
+            Some((row, col, name)) => Json::from([
+                ("uri", params.get("textDocument").get("uri").clone()),
+                ("range", range(row, col, name.chars().count() as u32)),
+            ]),
+            None => Json::Null,
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let (Some(document), Some(position)) = (self.document(params), position_of(params)) else {
+            return Json::Null;
+        };
+        let analysis = &document.analysis;
+
+        let target = analysis.references.iter()
+            .find(|reference| covers(reference.row, reference.col, &reference.name, position))
+            .map(|reference| (reference.row, reference.col, reference.name.clone(), reference.definition))
+            .or_else(|| analysis.definitions.iter()
+                .find(|definition| covers(definition.row, definition.col, &definition.name, position))
+                .map(|definition| (definition.row, definition.col, definition.name.clone(), Some((definition.row, definition.col)))));
+
+        let Some((row, col, name, definition)) = target else {
+            return Json::Null;
+        };
+
+        let description = match definition {
+            Some((definition_row, definition_col)) => {
+                let kind = analysis.definitions.iter()
+                    .find(|definition| definition.name == name && (definition.row, definition.col) == (definition_row, definition_col))
+                    .map(|definition| definition.kind.clone())
+                    .unwrap_or(DefinitionKind::Variable);
+                match kind {
+                    DefinitionKind::Function(signature) => format!("(fn {signature}) {name}"),
+                    DefinitionKind::Macro(signature) => format!("(macro {signature}) {name}"),
+                    DefinitionKind::Variable => format!("(set) {name}"),
+                    DefinitionKind::Parameter => format!("(parameter) {name}"),
+                }
+            }
+            None if builtins(&name).is_some() => format!("(builtin) {name}"),
+            None => return Json::Null,
+        };
+
+        Json::from([
+            ("contents", Json::from([
+                ("kind", "markdown".into()),
+                ("value", format!("```\n{description}\n```").into()),
+            ])),
+            ("range", range(row, col, name.chars().count() as u32)),
+        ])
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let mut items = builtin_names().iter()
+            .map(|name| completion_item(name, COMPLETION_FUNCTION, "builtin"))
+            .collect::<Vec<Json>>();
+
+        let (Some(document), Some(position)) = (self.document(params), position_of(params)) else {
+            return Json::Array(items);
+        };
+
+        let mut seen = Vec::new();
+        for definition in document.analysis.definitions.iter() {
+            let in_scope = match definition.scope {
+                Some((start, end)) => start <= position && position <= end,
+                None => true,
+            };
+            if !in_scope || seen.contains(&definition.name) {
+                continue;
+            }
+            seen.push(definition.name.clone());
+            items.push(match &definition.kind {
+                DefinitionKind::Function(signature) => completion_item(&definition.name, COMPLETION_FUNCTION, &format!("fn {signature}")),
+                DefinitionKind::Macro(signature) => completion_item(&definition.name, COMPLETION_FUNCTION, &format!("macro {signature}")),
+                DefinitionKind::Variable => completion_item(&definition.name, COMPLETION_VARIABLE, "set"),
+                DefinitionKind::Parameter => completion_item(&definition.name, COMPLETION_VARIABLE, "parameter"),
+            });
+        }
+        Json::Array(items)
+    }
+
+    fn document_symbols(&self, params: &Json) -> Json {
+        let Some(document) = self.document(params) else {
+            return Json::Array(Vec::new());
+        };
+
+        document.analysis.definitions.iter()
+            .filter(|definition| definition.scope.is_none())
+            .map(|definition| {
+                let kind = match definition.kind {
+                    DefinitionKind::Function(_) | DefinitionKind::Macro(_) => SYMBOL_FUNCTION,
+                    _ => SYMBOL_VARIABLE,
+                };
+                let range = range(definition.row, definition.col, definition.name.chars().count() as u32);
+                Json::from([
+                    ("name", definition.name.as_ref().into()),
+                    ("kind", kind.into()),
+                    ("range", range.clone()),
+                    ("selectionRange", range),
+                ])
+            })
+            .collect::<Vec<Json>>()
+            .into()
+    }
+}
+
+fn analyze_document(text: &str) -> Document {
+    let lexer = Lexer::from(text);
+    let parser = Parser::from(lexer);
+
+    match parser.parse_program() {
+        Ok(program) => {
+            let analysis = analyze(&program);
+            let diagnostics = analysis.diagnostics.iter()
+                .map(|diagnostic| {
+                    let severity = match diagnostic.severity {
+                        Severity::Error => DIAGNOSTIC_ERROR,
+                        Severity::Warning => DIAGNOSTIC_WARNING,
+                    };
+                    diagnostic_json(diagnostic.row, diagnostic.col, severity, &diagnostic.message)
+                })
+                .collect();
+            Document { analysis, diagnostics }
+        }
+        Err(errors) => Document {
+            analysis: Analysis::default(),
+            diagnostics: errors.iter()
+                .map(|error| diagnostic_json(error.row, error.col, DIAGNOSTIC_ERROR, &error.message))
+                .collect(),
+        }
+    }
+}
+
+fn capabilities() -> Json {
+    Json::from([
+        ("capabilities", Json::from([
+            ("textDocumentSync", TEXT_DOCUMENT_SYNC_FULL.into()),
+            ("definitionProvider", true.into()),
+            ("hoverProvider", true.into()),
+            ("completionProvider", Json::from([])),
+            ("documentSymbolProvider", true.into()),
+        ])),
+        ("serverInfo", Json::from([
+            ("name", "kvist".into()),
+        ])),
+    ])
+}
+
+fn publish_diagnostics(uri: &Json, diagnostics: Vec<Json>) -> Json {
+    Json::from([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", Json::from([
+            ("uri", uri.clone()),
+            ("diagnostics", diagnostics.into()),
+        ])),
+    ])
+}
+
+fn diagnostic_json(row: u32, col: u32, severity: u32, message: &str) -> Json {
+    Json::from([
+        ("range", range(row, col, 1)),
+        ("severity", severity.into()),
+        ("source", "kvist".into()),
+        ("message", message.into()),
+    ])
+}
+
+fn completion_item(label: &str, kind: u32, detail: &str) -> Json {
+    Json::from([
+        ("label", label.into()),
+        ("kind", kind.into()),
+        ("detail", detail.into()),
+    ])
+}
+
+// Tokens count rows and columns from 1 while the protocol counts lines and characters from 0
+fn range(row: u32, col: u32, length: u32) -> Json {
+    let line = row.saturating_sub(1);
+    let character = col.saturating_sub(1);
+    Json::from([
+        ("start", Json::from([("line", line.into()), ("character", character.into())])),
+        ("end", Json::from([("line", line.into()), ("character", (character + length).into())])),
+    ])
+}
+
+fn position_of(params: &Json) -> Option<Position> {
+    let position = params.get("position");
+    let line = position.get("line").as_u32()?;
+    let character = position.get("character").as_u32()?;
+    Some((line + 1, character + 1))
+}
+
+fn covers(row: u32, col: u32, name: &str, (position_row, position_col): Position) -> bool {
+    row == position_row && col <= position_col && position_col <= col + name.chars().count() as u32
+}
@@ -1,19 +1,128 @@
+// Each module keeps its main code in a file of the same name, next to its tests
+#![allow(clippy::module_inception)]
+
 use std::env;
+use std::io::{self, BufReader};
+use std::thread;
+use std::time::Duration;
+
+use crate::debugger::debugger::Debugger;
+use crate::evaluator::limits::{self, Limits};
+use crate::evaluator::permissions::{self, Permissions, ReadAccess};
+use crate::profiler::profiler::{Output, Profiler};
 
 mod token;
 mod lexer;
 mod parser;
+mod expander;
+mod resolver;
+mod optimizer;
 mod ast;
 mod evaluator;
 mod object;
 mod repl;
 mod script;
+mod checker;
+mod lsp;
+mod debugger;
+mod profiler;
+mod testing;
+
+// Every nested expression and function call recurses in the evaluator, so give it plenty of room
+const STACK_SIZE: usize = 512 * 1024 * 1024;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() <= 1 {
-        repl::repl::start();
-    } else {
-        script::script::start(&args[1]);
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("Failed to start interpreter thread");
+    if interpreter.join().is_err() {
+        std::process::exit(101);
     }
 }
+
+fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Options to the interpreter are given before the script or command, e.g. `kvist --debug script.kvist`
+    let options = args.iter()
+        .take_while(|arg| arg.starts_with("--"))
+        .count();
+    let limits = configure(&args[..options]);
+
+    let args = &args[options..];
+    if args.is_empty() {
+        repl::repl::start(limits);
+        return;
+    }
+    limits::enable(limits);
+    match args[0].as_str() {
+        "check" => checker::checker::start(&args[1..]),
+        "lsp" => lsp::server::start(),
+        "test" => testing::testing::start(&args[1..]),
+        _ => script::script::start(&args[0]),
+    }
+    profiler::profiler::finish();
+}
+
+fn configure(options: &[String]) -> Limits {
+    let mut debug = false;
+    let mut breakpoints = Vec::new();
+    let mut limits = Limits { max_depth: Some(limits::DEFAULT_MAX_DEPTH), ..Limits::default() };
+    // Scripts may do anything unless sandboxed, either explicitly or by granting a capability
+    let mut sandbox = false;
+    let mut permissions = Permissions::default();
+
+    for option in options {
+        let (name, value) = option.split_once('=')
+            .map(|(name, value)| (name, Some(value)))
+            .unwrap_or((option.as_str(), None));
+        match (name, value) {
+            ("--debug", None) => debug = true,
+            ("--optimize", None) => optimizer::optimizer::enable(),
+            ("--profile", None) => profiler::profiler::enable(Profiler::new(Output::Report)),
+            ("--profile", Some(path)) => profiler::profiler::enable(Profiler::new(Output::Folded(path.to_string()))),
+            ("--max-steps", Some(value)) => limits.max_steps = Some(parse_limit(name, value)),
+            ("--max-depth", Some(value)) => limits.max_depth = Some(parse_limit(name, value)),
+            ("--timeout", Some(value)) => limits.timeout = Some(Duration::from_millis(parse_limit(name, value))),
+            ("--max-memory", Some(value)) => limits.max_allocation = Some(parse_limit(name, value)),
+            ("--sandbox", None) => sandbox = true,
+            ("--allow-run", None) => (sandbox, permissions.run) = (true, true),
+            ("--allow-env", None) => (sandbox, permissions.env) = (true, true),
+            ("--allow-stdin", None) => (sandbox, permissions.stdin) = (true, true),
+            ("--allow-exit", None) => (sandbox, permissions.exit) = (true, true),
+            ("--allow-read", None) => (sandbox, permissions.read) = (true, ReadAccess::Anywhere),
+            ("--allow-read", Some(directory)) => {
+                sandbox = true;
+                permissions.allow_read(directory);
+            }
+            ("--break", Some(location)) => {
+                let Some((file, row)) = location.rsplit_once(':')
+                    .and_then(|(file, row)| Some((file.to_string(), row.parse::<u32>().ok()?))) else {
+                    eprintln!("Invalid breakpoint '{location}', expected <file:row>");
+                    std::process::exit(1);
+                };
+                debug = true;
+                breakpoints.push((file, row));
+            }
+            _ => {
+                eprintln!("Unknown option: {option}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if sandbox {
+        permissions::enable(permissions);
+    }
+    if debug {
+        debugger::debugger::enable(Debugger::new(breakpoints, Box::new(BufReader::new(io::stdin())), Box::new(io::stderr())));
+    }
+    limits
+}
+
+fn parse_limit<T: std::str::FromStr>(name: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid value '{value}' for {name}, expected a positive number");
+        std::process::exit(1);
+    })
+}
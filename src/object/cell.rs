@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::object::collector;
+use crate::object::object::{Object, Viewable};
+
+// Mutable reference to a value held by an atom, the only value that can change after it's made.
+// Copies of an atom share the cell, so a change made through one is seen through all.
+pub struct Cell {
+    value: RefCell<Object>,
+}
+
+impl Cell {
+    pub fn new(value: Object) -> Rc<Cell> {
+        let cell = Rc::new(Cell { value: RefCell::new(value) });
+        collector::register_cell(&cell);
+        cell
+    }
+
+    pub fn get(&self) -> Object {
+        self.value.borrow().clone()
+    }
+
+    pub fn set(&self, value: Object) {
+        self.value.replace(value);
+    }
+
+    pub(super) fn try_borrow(&self) -> Option<std::cell::Ref<'_, Object>> {
+        self.value.try_borrow().ok()
+    }
+
+    pub(super) fn take(&self) -> Object {
+        self.value.replace(Object::Unit)
+    }
+
+    pub fn view(&self) -> String {
+        // The cell is held while viewing its value, so a cell that contains itself is
+        // only viewed once
+        match self.value.try_borrow_mut() {
+            Ok(value) => format!("(atom {})", value.view()),
+            Err(_) => "(atom ...)".to_string(),
+        }
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Cell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Atom")
+    }
+}
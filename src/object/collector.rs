@@ -0,0 +1,295 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+use crate::object::cell::Cell;
+use crate::object::environment::{Environment, Frame, Store};
+use crate::object::object::Object;
+use crate::object::sequence::{Generator, Sequence, State};
+use crate::object::vector::Chunk;
+
+// Live scopes and atoms before the first automatic collection, later collections happen once
+// their number has doubled since the previous one
+const INITIAL_THRESHOLD: usize = 1024;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const { RefCell::new(Heap {
+        stores: Vec::new(),
+        cells: Vec::new(),
+        threshold: INITIAL_THRESHOLD,
+        check: INITIAL_THRESHOLD,
+    }) };
+}
+
+struct Heap {
+    stores: Vec<Weak<RefCell<Frame>>>,
+    cells: Vec<Weak<Cell>>,
+    // Live scopes and atoms that start a collection
+    threshold: usize,
+    // Registered scopes and atoms, live or not, at which the live ones are counted
+    check: usize,
+}
+
+impl Heap {
+    fn len(&self) -> usize {
+        self.stores.len() + self.cells.len()
+    }
+
+    fn prune(&mut self) {
+        self.stores.retain(|store| store.strong_count() > 0);
+        self.cells.retain(|cell| cell.strong_count() > 0);
+        // Counted again after as many registrations as there are live ones, at least, so
+        // counting takes constant time per registration
+        self.check = self.len() + self.len().max(INITIAL_THRESHOLD);
+    }
+
+    // Most scopes are freed when their call returns, so only the live ones count towards the
+    // threshold and a program that makes no cycles isn't traced over and over
+    fn due(&mut self) -> bool {
+        if self.len() < self.check {
+            return false;
+        }
+        self.prune();
+        self.len() >= self.threshold
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+enum Id {
+    Store(*const ()),
+    Environment(*const ()),
+    Chunk(*const ()),
+    Record(*const ()),
+    Cell(*const ()),
+    Sequence(*const ()),
+    Generator(*const ()),
+}
+
+#[derive(Clone)]
+enum Handle {
+    Store(Store),
+    Environment(Rc<Environment>),
+    Chunk(Rc<Chunk>),
+    Record(Rc<[Object]>),
+    Cell(Rc<Cell>),
+    Sequence(Rc<Sequence>),
+    Generator(Rc<RefCell<Generator>>),
+}
+
+struct Vertex {
+    handle: Handle,
+    // Strong count when first found, before the collector held a reference of its own
+    strong: usize,
+    // References from other vertices in the graph
+    internal: usize,
+    // Stores, atoms and sequences that are borrowed right now can't be traced and are kept alive
+    pinned: bool,
+    children: Vec<Id>,
+}
+
+pub(super) fn register(store: &Store) {
+    let collect_now = HEAP.with_borrow_mut(|heap| {
+        heap.stores.push(Rc::downgrade(store));
+        heap.due()
+    });
+    if collect_now {
+        collect();
+    }
+}
+
+pub(super) fn register_cell(cell: &Rc<Cell>) {
+    let collect_now = HEAP.with_borrow_mut(|heap| {
+        heap.cells.push(Rc::downgrade(cell));
+        heap.due()
+    });
+    if collect_now {
+        collect();
+    }
+}
+
+// Number of scopes that are still allocated
+#[cfg(test)]
+pub fn live_scopes() -> usize {
+    HEAP.with_borrow(|heap| heap.stores.iter()
+        .filter(|store| store.strong_count() > 0)
+        .count())
+}
+
+// Frees scopes and atoms only kept alive by reference cycles, like a recursive function bound
+// in the scope it closes over, and returns how many were freed.
+//
+// Every cycle goes through a scope, an atom or a sequence since those are the only objects that
+// change after they're made. The collector traces the graph of scopes, atoms, sequences, closures,
+// array chunks and records, and counts the references between them. Arrays of values that can't
+// reference a scope, an atom or a sequence are left out. Whatever has more references
+// than those is referenced from outside the graph, e.g. by the evaluator, and everything reachable
+// from there is alive. The rest is garbage, so the scopes, atoms and sequences in it are cleared to
+// break the cycles.
+pub fn collect() -> usize {
+    let (stores, cells) = HEAP.with_borrow_mut(|heap| {
+        heap.prune();
+        (heap.stores.clone(), heap.cells.clone())
+    });
+
+    let mut graph = HashMap::new();
+    let mut pending = Vec::new();
+    for store in stores.iter() {
+        let strong = store.strong_count();
+        let Some(store) = store.upgrade() else {
+            continue;
+        };
+        let id = Id::Store(Rc::as_ptr(&store) as *const ());
+        graph.entry(id).or_insert_with(|| {
+            pending.push(id);
+            Vertex { handle: Handle::Store(store), strong, internal: 0, pinned: false, children: Vec::new() }
+        });
+    }
+    for cell in cells.iter() {
+        let strong = cell.strong_count();
+        let Some(cell) = cell.upgrade() else {
+            continue;
+        };
+        let id = Id::Cell(Rc::as_ptr(&cell) as *const ());
+        graph.entry(id).or_insert_with(|| {
+            pending.push(id);
+            Vertex { handle: Handle::Cell(cell), strong, internal: 0, pinned: false, children: Vec::new() }
+        });
+    }
+
+    while let Some(id) = pending.pop() {
+        let handle = graph[&id].handle.clone();
+        let mut children = Vec::new();
+        let mut visit = |child: Id, strong: usize, handle: &dyn Fn() -> Handle| {
+            match graph.get_mut(&child) {
+                Some(vertex) => vertex.internal += 1,
+                None => {
+                    graph.insert(child, Vertex { handle: handle(), strong, internal: 1, pinned: false, children: Vec::new() });
+                    pending.push(child);
+                }
+            }
+            children.push(child);
+        };
+        let mut pinned = false;
+        match &handle {
+            Handle::Store(store) => match store.try_borrow() {
+                Ok(frame) => frame.objects().for_each(|object| trace(object, &mut visit)),
+                Err(_) => pinned = true,
+            },
+            Handle::Environment(environment) => {
+                let store = &environment.store;
+                visit(Id::Store(Rc::as_ptr(store) as *const ()), Rc::strong_count(store), &|| Handle::Store(store.clone()));
+                if let Some(outer) = &environment.outer {
+                    visit(Id::Environment(Rc::as_ptr(outer) as *const ()), Rc::strong_count(outer), &|| Handle::Environment(outer.clone()));
+                }
+            }
+            Handle::Chunk(chunk) => match chunk.as_ref() {
+                Chunk::Branch(children) => children.iter().for_each(|child| trace_chunk(child, &mut visit)),
+                Chunk::Leaf(values) => values.iter().for_each(|object| trace(object, &mut visit)),
+            },
+            Handle::Record(values) => values.iter().for_each(|object| trace(object, &mut visit)),
+            Handle::Cell(cell) => match cell.try_borrow() {
+                Some(value) => trace(&value, &mut visit),
+                None => pinned = true,
+            },
+            // Computed values are kept by the sequence and the objects the next one is computed
+            // from by the generator it shares with the rest of the sequence
+            Handle::Sequence(sequence) => match sequence.try_borrow().as_deref() {
+                Some(State::Forced(Some((value, rest)))) => {
+                    trace(value, &mut visit);
+                    trace_sequence(rest, &mut visit);
+                }
+                Some(State::Forced(None)) => {}
+                Some(State::Pending(generator)) =>
+                    visit(Id::Generator(Rc::as_ptr(generator) as *const ()), Rc::strong_count(generator), &|| Handle::Generator(generator.clone())),
+                None => pinned = true,
+            },
+            Handle::Generator(generator) => match generator.try_borrow() {
+                Ok(generator) => generator.state.iter().for_each(|object| trace(object, &mut visit)),
+                Err(_) => pinned = true,
+            },
+        }
+        let vertex = graph.get_mut(&id).expect("Traced vertex is in the graph");
+        vertex.children = children;
+        vertex.pinned = pinned;
+    }
+
+    let mut alive = graph.iter()
+        .filter(|(_, vertex)| vertex.pinned || vertex.strong > vertex.internal)
+        .map(|(id, _)| *id)
+        .collect::<Vec<Id>>();
+    let mut reachable = alive.iter().copied().collect::<HashSet<Id>>();
+    while let Some(id) = alive.pop() {
+        for child in graph[&id].children.iter() {
+            if reachable.insert(*child) {
+                alive.push(*child);
+            }
+        }
+    }
+
+    let garbage = graph.iter()
+        .filter(|(id, _)| !reachable.contains(id))
+        .filter(|(_, vertex)| matches!(vertex.handle, Handle::Store(_) | Handle::Cell(_) | Handle::Sequence(_) | Handle::Generator(_)))
+        .map(|(_, vertex)| vertex.handle.clone())
+        .collect::<Vec<Handle>>();
+    // The collector still holds every vertex, so nothing is freed while the scopes, atoms and
+    // sequences are cleared
+    let mut frames = Vec::new();
+    let mut values = Vec::new();
+    let mut states = Vec::new();
+    for handle in garbage.iter() {
+        match handle {
+            Handle::Store(store) => frames.push(std::mem::take(&mut *store.borrow_mut())),
+            Handle::Cell(cell) => values.push(cell.take()),
+            Handle::Sequence(sequence) => states.push(sequence.clear()),
+            Handle::Generator(generator) => values.extend(std::mem::take(&mut generator.borrow_mut().state)),
+            _ => {}
+        }
+    }
+    drop(frames);
+    drop(values);
+    drop(states);
+    drop(graph);
+
+    let collected = garbage.len();
+    drop(garbage);
+    HEAP.with_borrow_mut(|heap| {
+        heap.prune();
+        heap.threshold = INITIAL_THRESHOLD.max(heap.len() * 2);
+    });
+    collected
+}
+
+// Whether the object may reference a scope, an atom or a sequence, which every cycle goes through
+pub(super) fn may_reference(object: &Object) -> bool {
+    match object {
+        Object::Array(array) | Object::Spread(array) => array.references(),
+        Object::Record(_, values) => values.iter().any(may_reference),
+        Object::Function(_, _, _, _) | Object::Atom(_) | Object::Sequence(_) => true,
+        _ => false,
+    }
+}
+
+fn trace(object: &Object, visit: &mut impl FnMut(Id, usize, &dyn Fn() -> Handle)) {
+    match object {
+        // Arrays of plain values can't be part of a cycle, so their chunks aren't traced
+        Object::Array(array) | Object::Spread(array) if !array.references() => {}
+        Object::Array(array) | Object::Spread(array) => trace_chunk(array.root(), visit),
+        // Copies of a record share its values, so they're traced once like chunks
+        Object::Record(_, values) =>
+            visit(Id::Record(Rc::as_ptr(values) as *const ()), Rc::strong_count(values), &|| Handle::Record(values.clone())),
+        Object::Function(_, _, _, environment) =>
+            visit(Id::Environment(Rc::as_ptr(environment) as *const ()), Rc::strong_count(environment), &|| Handle::Environment(environment.clone())),
+        Object::Atom(cell) => visit(Id::Cell(Rc::as_ptr(cell) as *const ()), Rc::strong_count(cell), &|| Handle::Cell(cell.clone())),
+        Object::Sequence(sequence) => trace_sequence(sequence, visit),
+        _ => {}
+    }
+}
+
+fn trace_sequence(sequence: &Rc<Sequence>, visit: &mut impl FnMut(Id, usize, &dyn Fn() -> Handle)) {
+    visit(Id::Sequence(Rc::as_ptr(sequence) as *const ()), Rc::strong_count(sequence), &|| Handle::Sequence(sequence.clone()));
+}
+
+// Arrays share chunks with each other, so the chunks are traced instead of the arrays
+fn trace_chunk(chunk: &Rc<Chunk>, visit: &mut impl FnMut(Id, usize, &dyn Fn() -> Handle)) {
+    visit(Id::Chunk(Rc::as_ptr(chunk) as *const ()), Rc::strong_count(chunk), &|| Handle::Chunk(chunk.clone()));
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod test {
+    use crate::evaluator::evaluator::Eval;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::cell::Cell;
+    use crate::object::collector::{collect, live_scopes};
+    use crate::object::environment::Environment;
+    use crate::object::object::{Object, Viewable};
+    use crate::parser::parser::Parser;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_collect_recursive_functions() {
+        let input = "(set (countdown (fn |n| (if (= n 0) 0 (countdown (- n 1)))))) (countdown 5)";
+        let mut environment = Environment::new();
+        collect();
+        let before = live_scopes();
+
+        for _ in 0..200 {
+            let evaluated = apply_eval(input, &mut environment);
+            assert_eq!(Object::Integer(0), evaluated);
+            apply_eval(input, &mut Environment::new());
+        }
+        collect();
+
+        assert_eq!(before, live_scopes(), "Scopes were leaked");
+        assert_eq!(Object::Integer(0), apply_eval("(countdown 3)", &mut environment));
+    }
+
+    #[test]
+    fn test_collect_records() {
+        let input = "(record Box f) (set (b (Box (fn || b)))) (@ \"f\" (put b \"f\" 1))";
+        collect();
+        let before = live_scopes();
+
+        for _ in 0..200 {
+            assert_eq!(Object::Integer(1), apply_eval(input, &mut Environment::new()));
+        }
+        collect();
+
+        assert_eq!(before, live_scopes(), "Scopes were leaked");
+    }
+
+    #[test]
+    fn test_collect_atoms() {
+        let input = "(set (a (atom 0))) (reset a (fn || (deref a))) (swap a (fn |f| [f a]))";
+        collect();
+        let before = live_scopes();
+
+        for _ in 0..200 {
+            apply_eval(input, &mut Environment::new());
+        }
+        collect();
+        assert_eq!(before, live_scopes(), "Scopes were leaked");
+
+        // An atom containing itself needs no scope to form a cycle
+        let cell = Cell::new(Object::Unit);
+        cell.set(Object::Array(vec![Object::Atom(cell.clone())].into()));
+        let weak = Rc::downgrade(&cell);
+        drop(cell);
+        collect();
+        assert!(weak.upgrade().is_none(), "Atom was leaked");
+
+        let cell = Cell::new(Object::Integer(1));
+        cell.set(Object::Array(vec![Object::Atom(cell.clone())].into()));
+        collect();
+        assert_eq!("(atom [(atom ...)])", Object::Atom(cell).view());
+    }
+
+    #[test]
+    fn test_collect_nested_arrays() {
+        let input = "(set (a [1 [2 (fn || a)]])) (len a)";
+        collect();
+        let before = live_scopes();
+
+        for _ in 0..200 {
+            assert_eq!(Object::Integer(2), apply_eval(input, &mut Environment::new()));
+        }
+        collect();
+        assert_eq!(before, live_scopes(), "Scopes were leaked");
+    }
+
+    #[test]
+    fn test_calls_with_large_array_alive() {
+        // Every call makes a scope, so the time of a call mustn't grow with the values that are alive
+        let calls = "(set (id (fn |x| x)) (i 0)) (while (< i 20000) (set (i (+ (id i) 1))))";
+        let without = fastest(|| apply_eval(calls, &mut Environment::new()));
+        let mut environment = Environment::new();
+        apply_eval("(set (values [..(range 200000)]))", &mut environment);
+        let with = fastest(|| apply_eval(calls, &mut environment));
+
+        assert!(with < without * 3, "Calls took {with:?} with a large array alive and {without:?} without");
+    }
+
+    #[test]
+    fn test_collect_sequences() {
+        let tests = [
+            // The function of the sequence closes over the scope it's bound in
+            "(set (s (map (fn |x| (+ x (len [s]))) [1 2]))) (collect s)",
+            "(set (s (iterate (fn |x| (if (is_sequence s) (+ x 1) 0)) 0))) (collect (take 3 s))",
+            // Computed values refer back to the scope
+            "(set (s (generate (fn |n| (if (< n 2) [(fn || s) (+ n 1)] ())) 0))) (collect s)",
+        ];
+        collect();
+        let before = live_scopes();
+
+        for input in tests {
+            for _ in 0..200 {
+                apply_eval(input, &mut Environment::new());
+            }
+        }
+        collect();
+        assert_eq!(before, live_scopes(), "Scopes were leaked");
+    }
+
+    #[test]
+    fn test_collect_keeps_reachable_scopes() {
+        let tests = [
+            ("(set (make (fn |x| (fn || x)))) (set (get (make 42)))", "(get)", Object::Integer(42)),
+            ("(set (even (fn |n| (if (= n 0) true (odd (- n 1))))) (odd (fn |n| (if (= n 0) false (even (- n 1))))))", "(even 4)", Object::Boolean(true)),
+            ("(set (pair [(fn |x| (* x 2)) 3]))", "((@ 0 pair) (@ 1 pair))", Object::Integer(6)),
+            ("(set (many (push [..(range 100)] (fn |x| (* x 2)))))", "((last many) 4)", Object::Integer(8)),
+            ("(record Box f) (set (a (Box (fn |x| (* x 3)))) (b a))", "((@ \"f\" b) 2)", Object::Integer(6)),
+            ("(set (a (atom 0))) (reset a (fn |x| (if (is_atom a) (* x 2) 0)))", "((deref a) 4)", Object::Integer(8)),
+            ("(set (k 3) (s (map (fn |x| (* x k)) (range 10))))", "(last (collect s))", Object::Integer(27)),
+            ("(set (s (generate (fn |n| [(fn || n) (+ n 1)]) 0))) (set (f (first (rest s))))", "(f)", Object::Integer(1)),
+            ("(set (nested [[1 (fn |x| (* x 5))]]))", "((@ 1 (@ 0 nested)) 2)", Object::Integer(10)),
+        ];
+
+        for (definitions, call, expected) in tests {
+            let mut environment = Environment::new();
+            apply_eval(definitions, &mut environment);
+            collect();
+            assert_eq!(expected, apply_eval(call, &mut environment), "Failed to evaluate: {call}");
+        }
+    }
+
+    fn fastest(mut run: impl FnMut() -> Object) -> Duration {
+        (0..3).map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        }).min().expect("Expected a run")
+    }
+
+    fn apply_eval(input: &str, environment: &mut Environment) -> Object {
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+        program.eval(environment).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input))
+    }
+}
@@ -0,0 +1,144 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::ast::ast::AST;
+use crate::object::object::Object;
+
+// Values of different types are never equal, except for Integers and Floats of the same number.
+// Functions, builtins, sequences and atoms are only equal to themselves.
+pub fn equals(left: &Object, right: &Object) -> bool {
+    order(left, right) == Ordering::Equal
+}
+
+// Total order of all values, so any values can be sorted. Values of different types are ordered
+// by their type: Unit, Undefined, Boolean, numbers, String, Array, Spread, Record, Code, Function,
+// Builtin, Constructor, Sequence and Atom. Integers and Floats are ordered by their number with NaN after all
+// other numbers, strings by their characters and arrays by their values, e.g. [1 2] < [1 2 0] < [1 3].
+// Records are ordered by the name of their type and then by their values, variants of an
+// enum in the order they're defined. Functions, builtins, constructors, sequences
+// and atoms have no order of their own, they're ordered by identity.
+pub fn order(left: &Object, right: &Object) -> Ordering {
+    match (left, right) {
+        (Object::Integer(left), Object::Integer(right)) => left.cmp(right),
+        (Object::Float(left), Object::Integer(right)) => order_numbers(*left, f64::from(*right)),
+        (Object::Integer(left), Object::Float(right)) => order_numbers(f64::from(*left), *right),
+        (Object::Float(left), Object::Float(right)) => order_numbers(*left, *right),
+        (Object::Boolean(left), Object::Boolean(right)) => left.cmp(right),
+        (Object::String(left), Object::String(right)) => left.cmp(right),
+        (Object::Array(left), Object::Array(right))
+        | (Object::Spread(left), Object::Spread(right)) => order_values(left.iter(), right.iter()),
+        (Object::Record(left_type, left), Object::Record(right_type, right)) => left_type.type_name().cmp(right_type.type_name())
+            .then(left_type.variant_of.as_ref().map(|(_, index)| index).cmp(&right_type.variant_of.as_ref().map(|(_, index)| index)))
+            .then(Rc::as_ptr(left_type).cmp(&Rc::as_ptr(right_type)))
+            .then_with(|| order_values(left.iter(), right.iter())),
+        (Object::Code(left), Object::Code(right)) => left.string().cmp(&right.string()),
+        (Object::Function(_, _, left_body, left_environment), Object::Function(_, _, right_body, right_environment)) =>
+            (Rc::as_ptr(left_body), Rc::as_ptr(left_environment)).cmp(&(Rc::as_ptr(right_body), Rc::as_ptr(right_environment))),
+        (Object::Builtin(left), Object::Builtin(right)) => (*left as usize).cmp(&(*right as usize)),
+        (Object::Constructor(left), Object::Constructor(right)) => Rc::as_ptr(left).cmp(&Rc::as_ptr(right)),
+        (Object::Sequence(left), Object::Sequence(right)) => Rc::as_ptr(left).cmp(&Rc::as_ptr(right)),
+        (Object::Atom(left), Object::Atom(right)) => Rc::as_ptr(left).cmp(&Rc::as_ptr(right)),
+        (left, right) => rank(left).cmp(&rank(right)),
+    }
+}
+
+// Lexicographic order of values, a prefix comes first
+fn order_values<'a>(left: impl ExactSizeIterator<Item=&'a Object>, right: impl ExactSizeIterator<Item=&'a Object>) -> Ordering {
+    let lengths = left.len().cmp(&right.len());
+    left.zip(right)
+        .map(|(left, right)| order(left, right))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(lengths)
+}
+
+// Whether the values are of types that make sense to compare by size, all numbers or
+// values of the same type other than functions, builtins, constructors, sequences and atoms
+pub fn comparable(left: &Object, right: &Object) -> bool {
+    match (left, right) {
+        (Object::Function(..) | Object::Builtin(_) | Object::Constructor(_) | Object::Sequence(_) | Object::Atom(_), _) => false,
+        (Object::Record(left, _), Object::Record(right, _)) => left.same_type(right),
+        (left, right) => rank(left) == rank(right),
+    }
+}
+
+fn order_numbers(left: f64, right: f64) -> Ordering {
+    left.partial_cmp(&right).unwrap_or_else(|| left.is_nan().cmp(&right.is_nan()))
+}
+
+fn rank(object: &Object) -> u8 {
+    match object {
+        Object::Unit => 0,
+        Object::Undefined => 1,
+        Object::Boolean(_) => 2,
+        Object::Integer(_) | Object::Float(_) => 3,
+        Object::String(_) => 4,
+        Object::Array(_) => 5,
+        Object::Spread(_) => 6,
+        Object::Record(..) => 7,
+        Object::Code(_) => 8,
+        Object::Function(..) => 9,
+        Object::Builtin(_) => 10,
+        Object::Constructor(_) => 11,
+        Object::Sequence(_) => 12,
+        Object::Atom(_) => 13,
+    }
+}
+
+// Value used as a key of a hash map or member of a hash set, keys are the same when their values are equal
+#[derive(Debug, Clone)]
+pub struct Key(pub Object);
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        equals(&self.0, &other.0)
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        order(&self.0, &other.0)
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash(&self.0, state);
+    }
+}
+
+fn hash<H: Hasher>(object: &Object, state: &mut H) {
+    rank(object).hash(state);
+    match object {
+        // Equal Integers and Floats hash the same, as do 0.0 and -0.0
+        Object::Integer(value) => f64::from(*value).to_bits().hash(state),
+        Object::Float(value) if *value == 0. => 0f64.to_bits().hash(state),
+        Object::Float(value) if value.is_nan() => f64::NAN.to_bits().hash(state),
+        Object::Float(value) => value.to_bits().hash(state),
+        Object::Boolean(value) => value.hash(state),
+        Object::String(value) => value.hash(state),
+        Object::Array(values) | Object::Spread(values) => {
+            values.len().hash(state);
+            values.iter().for_each(|value| hash(value, state));
+        }
+        Object::Record(record_type, values) => {
+            (record_type.type_name(), &record_type.name).hash(state);
+            values.iter().for_each(|value| hash(value, state));
+        }
+        Object::Code(node) => node.string().hash(state),
+        Object::Function(_, _, body, environment) => (Rc::as_ptr(body), Rc::as_ptr(environment)).hash(state),
+        Object::Builtin(builtin) => (*builtin as usize).hash(state),
+        Object::Constructor(record_type) => Rc::as_ptr(record_type).hash(state),
+        Object::Sequence(sequence) => Rc::as_ptr(sequence).hash(state),
+        Object::Atom(cell) => Rc::as_ptr(cell).hash(state),
+        Object::Unit | Object::Undefined => {}
+    }
+}
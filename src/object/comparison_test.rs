@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use crate::object::comparison::{equals, order, Key};
+    use crate::object::object::Object;
+
+    #[test]
+    fn test_order() {
+        let tests = [
+            (Object::Integer(1), Object::Float(1.5), Ordering::Less),
+            (Object::Float(f64::NAN), Object::Integer(1), Ordering::Greater),
+            (Object::String("ab".into()), Object::String("b".into()), Ordering::Less),
+            (array([1, 2]), array([1, 2, 0]), Ordering::Less),
+            (array([1, 3]), array([1, 2, 0]), Ordering::Greater),
+            (Object::Unit, Object::Boolean(false), Ordering::Less),
+            (Object::Boolean(true), Object::Integer(0), Ordering::Less),
+            (Object::Integer(100), Object::String("1".into()), Ordering::Less),
+            (Object::String("z".into()), array([]), Ordering::Less),
+        ];
+
+        for (left, right, expected) in tests {
+            assert_eq!(expected, order(&left, &right), "Failed to order {left:?} and {right:?}");
+            assert_eq!(expected.reverse(), order(&right, &left), "Failed to order {right:?} and {left:?}");
+        }
+    }
+
+    #[test]
+    fn test_equal_keys_hash_the_same() {
+        let tests = [
+            (Object::Integer(1), Object::Float(1.0)),
+            (Object::Float(0.0), Object::Float(-0.0)),
+            (Object::Float(f64::NAN), Object::Float(f64::NAN)),
+            (array([1, 2]), Object::Array([Object::Float(1.0), Object::Integer(2)].into())),
+        ];
+
+        for (left, right) in tests {
+            assert!(equals(&left, &right), "Expected {left:?} to equal {right:?}");
+            assert_eq!(hash(&Key(left.clone())), hash(&Key(right.clone())), "Failed to hash {left:?} and {right:?} the same");
+        }
+    }
+
+    fn array<const N: usize>(values: [i32; N]) -> Object {
+        Object::Array(values.map(Object::Integer).into())
+    }
+
+    fn hash(key: &Key) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
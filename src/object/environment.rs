@@ -1,40 +1,141 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::rc::Rc;
+use crate::ast::ast::{Layout, Node};
+use crate::object::collector;
 use crate::object::object::{Object, Viewable};
 
+pub(super) type Store = Rc<RefCell<Frame>>;
+
+#[derive(PartialEq, Debug, Default)]
+pub(super) struct Frame {
+    // Slots of the names in the layout, None until they're set
+    pub(super) slots: Vec<Option<Object>>,
+    // Starts as the layout the resolver gave the scope, names only known at runtime,
+    // e.g. from included scripts or the REPL, are added to the frame's own copy
+    layout: Rc<Layout>,
+}
+
+impl Frame {
+    pub(super) fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.slots.iter().flatten()
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Environment {
     // store: HashMap<String, Object>,
-    store: Rc<RefCell<HashMap<Rc<str>, Object>>>,
-    outer: Option<Rc<Environment>>,
+    pub(super) store: Store,
+    pub(super) outer: Option<Rc<Environment>>,
 }
 
 impl Environment {
     pub fn new() -> Environment {
+        let store = Store::default();
+        collector::register(&store);
         Environment {
-            store: Default::default(),
+            store,
             outer: None,
         }
     }
 
+    // Creates the frame for the scope the resolver annotated the given node as body of
+    pub fn scope(outer: Rc<Environment>, body: &Node) -> Environment {
+        let Some(layout) = body.resolution.scope.get() else {
+            return Environment::from(outer);
+        };
+        let store = Rc::new(RefCell::new(Frame {
+            slots: vec![None; layout.len()],
+            layout: layout.clone(),
+        }));
+        collector::register(&store);
+        Environment {
+            store,
+            outer: Some(outer),
+        }
+    }
+
+    // Layout of this scope's frame, which a program run in the scope adds its names to
+    pub fn layout(&self) -> Layout {
+        self.store.borrow().layout.as_ref().clone()
+    }
+
+    // Gives the names slots in this scope's frame, in the order of the layout
+    pub fn extend(&self, layout: &Layout) {
+        let frame = &mut *self.store.borrow_mut();
+        for name in layout.names() {
+            if frame.layout.slot(name).is_none() {
+                Rc::make_mut(&mut frame.layout).add(name);
+            }
+        }
+        frame.slots.resize(frame.layout.len(), None);
+    }
+
     pub fn get(&self, name: &Rc<str>) -> Option<Object> {
-        let store = self.store.borrow();
+        let result = {
+            let store = self.store.borrow();
+            store.layout.slot(name).and_then(|slot| store.slots[slot].clone())
+        };
 
-        match (store.get(name), &self.outer) {
-            (result @ Some(_), _) => result.map(|object| object.clone()),
+        match (result, &self.outer) {
+            (result @ Some(_), _) => result,
             (None, Some(outer)) => outer.get(name),
             (None, None) => None,
         }
     }
 
+    // Looks up a binding at the address given by the resolver by indexing the frame depth scopes
+    // out, without hashing the name. Only a slot that isn't set yet falls back to the outer
+    // scopes by name, like a lookup by name would.
+    pub fn get_local(&self, depth: usize, slot: usize, name: &Rc<str>) -> Option<Object> {
+        let mut environment = self;
+        for _ in 0..depth {
+            let Some(outer) = &environment.outer else {
+                return self.get(name);
+            };
+            environment = outer;
+        }
+
+        match environment.store.borrow().slots.get(slot) {
+            Some(Some(object)) => Some(object.clone()),
+            Some(None) => environment.outer.as_ref().and_then(|outer| outer.get(name)),
+            None => self.get(name),
+        }
+    }
+
+    pub fn contains(&self, name: &Rc<str>) -> bool {
+        self.get(name).is_some()
+    }
+
     pub fn set(&mut self, name: Rc<str>, object: Object) {
-        self.store.borrow_mut().insert(name, object);
+        let mut store = self.store.borrow_mut();
+        let slot = match store.layout.slot(&name) {
+            Some(slot) => slot,
+            None => {
+                let slot = Rc::make_mut(&mut store.layout).add(&name);
+                store.slots.resize(slot + 1, None);
+                slot
+            }
+        };
+        store.slots[slot] = Some(object);
+    }
+
+    pub fn set_local(&mut self, slot: usize, name: Rc<str>, object: Object) {
+        let mut store = self.store.borrow_mut();
+        match store.slots.get_mut(slot) {
+            Some(current) => *current = Some(object),
+            None => {
+                drop(store);
+                self.set(name, object)
+            }
+        }
     }
 
     pub fn view(&self) -> String {
         let mut string = String::new();
-        for (name, value) in self.store.borrow().iter() {
+        let store = self.store.borrow();
+        let slots = store.layout.names().iter().zip(store.slots.iter())
+            .filter_map(|(name, value)| value.as_ref().map(|value| (name, value)));
+        for (name, value) in slots {
             string += format!("({} -> {})", name, value.view()).as_str();
         }
         if let Some(outer) = &self.outer {
@@ -48,9 +149,11 @@ impl Environment {
 impl From<Rc<Environment>> for Environment {
 
     fn from(value: Rc<Environment>) -> Self {
+        let store = Store::default();
+        collector::register(&store);
         Environment {
-            store: Default::default(),
+            store,
             outer: Some(value),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,12 @@
 pub mod object;
-pub mod environment;
\ No newline at end of file
+pub mod environment;
+pub mod collector;
+pub mod sequence;
+pub mod vector;
+pub mod comparison;
+pub mod record;
+pub mod cell;
+mod collector_test;
+mod vector_test;
+
+mod comparison_test;
@@ -1,7 +1,11 @@
 use std::fmt::{Display, Formatter};
 use std::rc::Rc;
-use crate::ast::ast::Node;
+use crate::ast::ast::{float_literal, string_literal, AST, Node};
+use crate::object::cell::Cell;
 use crate::object::environment::Environment;
+use crate::object::record::RecordType;
+use crate::object::sequence::Sequence;
+use crate::object::vector::Vector;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Object {
@@ -11,10 +15,18 @@ pub enum Object {
     Float(f64),
     Boolean(bool),
     String(Rc<str>),
-    Array(Rc<[Object]>),
-    Spread(Rc<[Object]>),
+    Array(Vector),
+    Spread(Vector),
+    // Values of the fields of a record, in the order of the fields of its type
+    Record(Rc<RecordType>, Rc<[Object]>),
+    // Makes records of the type when called
+    Constructor(Rc<RecordType>),
     Function(Rc<[Node]>, Rc<Option<Node>>, Rc<Node>, Rc<Environment>),
     Builtin(fn(Box<[Object]>) -> Result<Object, String>),
+    // Quoted code, the values macros take and return
+    Code(Rc<Node>),
+    Sequence(Rc<Sequence>),
+    Atom(Rc<Cell>),
     // Null,
     Undefined,
 }
@@ -36,6 +48,30 @@ impl Object {
         }
     }
 
+    // Source code of the value that reads back as an equal value, records are written as
+    // calls to their constructors. Functions and other values without source are errors.
+    pub fn repr(&self) -> Result<String, String> {
+        let join = |values: &mut dyn Iterator<Item = &Object>| values
+            .map(|value| value.repr())
+            .collect::<Result<Vec<String>, String>>()
+            .map(|values| values.join(" "));
+        match self {
+            Object::Unit => Ok("()".to_string()),
+            Object::Integer(value) => Ok(value.to_string()),
+            Object::Float(value) if value.is_finite() => Ok(float_literal(*value)),
+            Object::Float(value) => Err(format!("{value} has no representation")),
+            Object::Boolean(value) => Ok(value.to_string()),
+            Object::String(value) => Ok(string_literal(value)),
+            Object::Array(values) => Ok(format!("[{}]", join(&mut values.iter())?)),
+            Object::Spread(values) => Ok(format!("..[{}]", join(&mut values.iter())?)),
+            Object::Record(record_type, _) if record_type.variant_of.is_some() && record_type.fields.is_empty() => Ok(record_type.name.to_string()),
+            Object::Record(record_type, values) if values.is_empty() => Ok(format!("({})", record_type.name)),
+            Object::Record(record_type, values) => Ok(format!("({} {})", record_type.name, join(&mut values.iter())?)),
+            Object::Code(node) => Ok(format!("(quote {})", node.string())),
+            object => Err(format!("{object} has no representation")),
+        }
+    }
+
     // pub fn is_truthy(object: &Object) -> bool {
     pub fn is_truthy(&self) -> bool {
         match self {
@@ -72,11 +108,32 @@ impl Viewable for Object {
                 .reduce(|acc, c| acc + " " + &c)
                 .unwrap_or("".to_string())
             ),
+            // Variants without fields are written as only their name
+            Object::Record(record_type, _) if record_type.variant_of.is_some() && record_type.fields.is_empty() => record_type.name.to_string(),
+            Object::Record(record_type, values) => format!("{}{{{}}}", record_type.name, record_type.fields.iter()
+                .zip(values.iter())
+                .map(|(field, value)| format!("{field} {}", value.view()))
+                .collect::<Vec<String>>()
+                .join(" ")
+            ),
+            Object::Constructor(record_type) if record_type.variant_of.is_some() => format!("(enum {} ({}))", record_type.type_name(), record_type.fields.iter()
+                .fold(record_type.name.to_string(), |variant, field| variant + " " + field)
+            ),
+            Object::Constructor(record_type) => format!("(record {})", std::iter::once(&record_type.name)
+                .chain(record_type.fields.iter())
+                .map(|name| name.as_ref())
+                .collect::<Vec<&str>>()
+                .join(" ")
+            ),
             // Object::Null => "null".to_string(),
             Object::Undefined => "undefined".to_string(),
             // TODO proper formatted viewable
             Object::Function(_, _, _, _) => "(fn)".to_string(),
             Object::Builtin(_) => "(builtin)".to_string(),
+            Object::Code(node) => node.string().to_string(),
+            // Viewing the values could compute infinitely many
+            Object::Sequence(_) => "(sequence)".to_string(),
+            Object::Atom(cell) => cell.view(),
             Object::Spread(values) => format!("..[{}]", values.iter()
                 .map(|object | object.view())
                 // .reduce(|acc, c| acc + ", " + &c)
@@ -96,11 +153,17 @@ impl Display for Object {
             Object::Boolean(_) => write!(f, "Boolean"),
             Object::String(_) => write!(f, "String"),
             Object::Array(_) => write!(f, "Array"),
+            // Records are of the type named by their definition
+            Object::Record(record_type, _) => write!(f, "{}", record_type.type_name()),
+            Object::Constructor(_) => write!(f, "Constructor"),
             // Object::Null => write!(f, "Null"),
             Object::Undefined => write!(f, "Undefined"),
             Object::Function(_, _, _, _) => write!(f, "Function"),
             Object::Builtin(_) => write!(f, "Builtin"),
             Object::Spread(_) => write!(f, "Spread"),
+            Object::Code(_) => write!(f, "Code"),
+            Object::Sequence(_) => write!(f, "Sequence"),
+            Object::Atom(_) => write!(f, "Atom"),
         }
     }
 }
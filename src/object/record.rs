@@ -0,0 +1,52 @@
+use std::rc::Rc;
+
+use crate::object::object::Object;
+
+// Type of the records made by one record definition. Records of different definitions
+// are different types even when their names are the same.
+//
+// Variants of enums are record types too, their values are of the type of the enum.
+#[derive(Debug)]
+pub struct RecordType {
+    pub name: Rc<str>,
+    pub fields: Box<[Rc<str>]>,
+    // Name of the enum and position of the variant in it
+    pub variant_of: Option<(Rc<str>, usize)>,
+}
+
+impl RecordType {
+
+    // Name of the type of the values, the enum of a variant or the record
+    pub fn type_name(&self) -> &Rc<str> {
+        match &self.variant_of {
+            Some((enum_name, _)) => enum_name,
+            None => &self.name,
+        }
+    }
+
+    // Whether values of the types are of the same type, variants are of the type of their enum
+    pub fn same_type(&self, other: &RecordType) -> bool {
+        match (&self.variant_of, &other.variant_of) {
+            (Some((left, _)), Some((right, _))) => Rc::ptr_eq(left, right),
+            _ => std::ptr::eq(self, other),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.as_ref() == name)
+    }
+
+    // Record with the values of the fields in order
+    pub fn construct(self: &Rc<Self>, values: Vec<Object>) -> Result<Object, String> {
+        if values.len() != self.fields.len() {
+            return Err(format!("{}: wrong number of arguments. got={}, want={}", self.name, values.len(), self.fields.len()));
+        }
+        Ok(Object::Record(self.clone(), values.into()))
+    }
+}
+
+impl PartialEq for RecordType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::object::object::Object;
+use crate::object::vector::Vector;
+
+// Produces the next value of a sequence from its state, None when it has ended. Objects the
+// step refers to are kept in the state instead of captured, so the collector can trace them.
+pub type Step = Box<dyn FnMut(&mut Vec<Object>) -> Result<Option<Object>, String>>;
+
+// Lazy sequence of values, only computed as far as they're used. Computed values are
+// kept while something refers to the sequence, so taking the first value or the rest
+// of a sequence doesn't consume it and the same values are seen every time.
+pub struct Sequence {
+    state: RefCell<State>,
+}
+
+pub(super) enum State {
+    // The generator is shared with the rest of the sequence, it's called once per value
+    Pending(Rc<RefCell<Generator>>),
+    Forced(Option<(Object, Rc<Sequence>)>),
+}
+
+pub(super) struct Generator {
+    pub(super) state: Vec<Object>,
+    step: Step,
+}
+
+impl Sequence {
+
+    pub fn new(step: Step) -> Rc<Sequence> {
+        Sequence::with_state(Vec::new(), step)
+    }
+
+    pub fn with_state(state: Vec<Object>, step: Step) -> Rc<Sequence> {
+        let generator = Generator { state, step };
+        Rc::new(Sequence { state: RefCell::new(State::Pending(Rc::new(RefCell::new(generator)))) })
+    }
+
+    pub fn from_values(values: Vector) -> Rc<Sequence> {
+        let mut index = 0;
+        Sequence::with_state(vec![Object::Array(values)], Box::new(move |state| {
+            let [Object::Array(values)] = state.as_slice() else {
+                return Ok(None);
+            };
+            index += 1;
+            Ok(values.get(index - 1).cloned())
+        }))
+    }
+
+    // The first value and the rest of the sequence, None when it's empty
+    pub fn force(&self) -> Result<Option<(Object, Rc<Sequence>)>, String> {
+        let generator = match &*self.state.borrow() {
+            State::Forced(forced) => return Ok(forced.clone()),
+            State::Pending(generator) => generator.clone(),
+        };
+
+        let Ok(mut next) = generator.try_borrow_mut() else {
+            return Err("Sequence is used while computing its next value".to_string());
+        };
+        let Generator { state, step } = &mut *next;
+        let forced = step(state)?
+            .map(|value| (value, Rc::new(Sequence { state: RefCell::new(State::Pending(generator.clone())) })));
+        drop(next);
+
+        *self.state.borrow_mut() = State::Forced(forced.clone());
+        Ok(forced)
+    }
+
+    pub fn iter(self: Rc<Self>) -> Iter {
+        Iter { next: Some(self) }
+    }
+
+    pub(super) fn try_borrow(&self) -> Option<std::cell::Ref<'_, State>> {
+        self.state.try_borrow().ok()
+    }
+
+    // Forgets the computed values and the generator, which breaks any cycle through them
+    pub(super) fn clear(&self) -> State {
+        self.state.replace(State::Forced(None))
+    }
+}
+
+pub struct Iter {
+    next: Option<Rc<Sequence>>,
+}
+
+impl Iterator for Iter {
+    type Item = Result<Object, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sequence = self.next.take()?;
+        match sequence.force() {
+            Ok(Some((value, rest))) => {
+                self.next = Some(rest);
+                Some(Ok(value))
+            }
+            Ok(None) => None,
+            Err(error) => {
+                self.next = Some(sequence);
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+// Long computed sequences would otherwise be dropped recursively, one call per value
+impl Drop for Sequence {
+    fn drop(&mut self) {
+        let State::Forced(Some((_, rest))) = std::mem::replace(self.state.get_mut(), State::Forced(None)) else {
+            return;
+        };
+        let mut next = Rc::try_unwrap(rest);
+        while let Ok(mut sequence) = next {
+            next = match std::mem::replace(sequence.state.get_mut(), State::Forced(None)) {
+                State::Forced(Some((_, rest))) => Rc::try_unwrap(rest),
+                _ => return,
+            };
+        }
+    }
+}
+
+// Sequences are only equal to themselves, comparing values could compute infinitely many
+impl PartialEq for Sequence {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Sequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sequence")
+    }
+}
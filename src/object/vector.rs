@@ -0,0 +1,248 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::Index;
+use std::rc::Rc;
+
+use crate::object::collector;
+use crate::object::object::Object;
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+// Node of the trie, branches have up to WIDTH children and leaves up to WIDTH values
+#[derive(Clone)]
+pub(crate) enum Chunk {
+    Branch(Vec<Rc<Chunk>>),
+    Leaf(Vec<Object>),
+}
+
+// Immutable array stored as a trie of chunks. Copies share every chunk they don't change,
+// so pushing, replacing a value and slicing only copy the chunks on one path, O(log n).
+//
+// A slice is a window into the values of the trie. It keeps all of them alive, and pushing
+// to it replaces the values after the window instead of adding new ones.
+#[derive(Clone)]
+pub struct Vector {
+    root: Rc<Chunk>,
+    // Position of the index bits for the children of the root, 0 when the root is a leaf
+    shift: u32,
+    // Values in the trie
+    size: usize,
+    start: usize,
+    len: usize,
+    // Whether a value that may reference a scope or an atom was ever put in the trie, the
+    // collector doesn't trace vectors without any
+    references: bool,
+}
+
+impl Vector {
+
+    pub fn new() -> Vector {
+        Vector { root: Rc::new(Chunk::Leaf(Vec::new())), shift: 0, size: 0, start: 0, len: 0, references: false }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Object> {
+        if index >= self.len {
+            return None;
+        }
+        let position = self.start + index;
+        Some(&self.leaf(position)[position & MASK])
+    }
+
+    pub fn first(&self) -> Option<&Object> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&Object> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn push(&mut self, value: Object) {
+        self.references |= collector::may_reference(&value);
+        let position = self.start + self.len;
+        self.len += 1;
+        if position < self.size {
+            replace(&mut self.root, self.shift, position, value);
+        } else if self.size == WIDTH << self.shift {
+            let root = std::mem::replace(&mut self.root, Rc::new(Chunk::Branch(Vec::new())));
+            self.root = Rc::new(Chunk::Branch(vec![root, path(self.shift, value)]));
+            self.shift += BITS;
+            self.size += 1;
+        } else {
+            append(&mut self.root, self.shift, position, value);
+            self.size += 1;
+        }
+    }
+
+    // Replaces the value at the index, false when it's out of bounds
+    pub fn set(&mut self, index: usize, value: Object) -> bool {
+        if index >= self.len {
+            return false;
+        }
+        self.references |= collector::may_reference(&value);
+        replace(&mut self.root, self.shift, self.start + index, value);
+        true
+    }
+
+    // The values from start up to end, which must be within the vector
+    pub fn slice(&self, start: usize, end: usize) -> Vector {
+        assert!(start <= end && end <= self.len, "Slice {start}..{end} is out of bounds of {}", self.len);
+        if start == end {
+            return Vector::new();
+        }
+        Vector { start: self.start + start, len: end - start, ..self.clone() }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { vector: self, index: 0, leaf: &[] }
+    }
+
+    pub(crate) fn root(&self) -> &Rc<Chunk> {
+        &self.root
+    }
+
+    pub(crate) fn references(&self) -> bool {
+        self.references
+    }
+
+    fn leaf(&self, position: usize) -> &[Object] {
+        let mut chunk = &self.root;
+        let mut shift = self.shift;
+        loop {
+            match chunk.as_ref() {
+                Chunk::Branch(children) => {
+                    chunk = &children[(position >> shift) & MASK];
+                    shift -= BITS;
+                }
+                Chunk::Leaf(values) => return values,
+            }
+        }
+    }
+}
+
+// A new chunk at the level of shift with only the value
+fn path(shift: u32, value: Object) -> Rc<Chunk> {
+    match shift {
+        0 => Rc::new(Chunk::Leaf(vec![value])),
+        _ => Rc::new(Chunk::Branch(vec![path(shift - BITS, value)])),
+    }
+}
+
+fn append(chunk: &mut Rc<Chunk>, shift: u32, position: usize, value: Object) {
+    match Rc::make_mut(chunk) {
+        Chunk::Leaf(values) => values.push(value),
+        Chunk::Branch(children) => {
+            let child = (position >> shift) & MASK;
+            match children.get_mut(child) {
+                Some(child) => append(child, shift - BITS, position, value),
+                None => children.push(path(shift - BITS, value)),
+            }
+        }
+    }
+}
+
+fn replace(chunk: &mut Rc<Chunk>, shift: u32, position: usize, value: Object) {
+    match Rc::make_mut(chunk) {
+        Chunk::Leaf(values) => values[position & MASK] = value,
+        Chunk::Branch(children) => replace(&mut children[(position >> shift) & MASK], shift - BITS, position, value),
+    }
+}
+
+impl Default for Vector {
+    fn default() -> Self {
+        Vector::new()
+    }
+}
+
+pub struct Iter<'a> {
+    vector: &'a Vector,
+    index: usize,
+    // Leaf of the previous value, values within it are read without going through the trie
+    leaf: &'a [Object],
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Object;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.vector.len {
+            return None;
+        }
+        let position = self.vector.start + self.index;
+        if self.leaf.is_empty() || position & MASK == 0 {
+            self.leaf = self.vector.leaf(position);
+        }
+        self.index += 1;
+        Some(&self.leaf[position & MASK])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vector.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a Vector {
+    type Item = &'a Object;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Object> for Vector {
+    fn from_iter<T: IntoIterator<Item=Object>>(iter: T) -> Self {
+        let mut vector = Vector::new();
+        iter.into_iter().for_each(|value| vector.push(value));
+        vector
+    }
+}
+
+impl From<Vec<Object>> for Vector {
+    fn from(values: Vec<Object>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl From<&[Object]> for Vector {
+    fn from(values: &[Object]) -> Self {
+        values.iter().cloned().collect()
+    }
+}
+
+impl<const N: usize> From<[Object; N]> for Vector {
+    fn from(values: [Object; N]) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl Index<usize> for Vector {
+    type Output = Object;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap_or_else(|| panic!("Index {index} is out of bounds of {}", self.len))
+    }
+}
+
+impl PartialEq for Vector {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Debug for Vector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod test {
+    use crate::object::object::Object;
+    use crate::object::vector::Vector;
+
+    #[test]
+    fn test_push_and_get() {
+        for length in [0, 1, 31, 32, 33, 1024, 1025, 40000] {
+            let vector = integers(0..length);
+
+            assert_eq!(length as usize, vector.len());
+            for index in 0..length {
+                assert_eq!(Some(&Object::Integer(index)), vector.get(index as usize), "Failed to get {index} of {length}");
+            }
+            assert_eq!(None, vector.get(length as usize));
+            assert_eq!((0..length).map(Object::Integer).collect::<Vec<Object>>(), vector.iter().cloned().collect::<Vec<Object>>());
+        }
+    }
+
+    #[test]
+    fn test_copies_are_unchanged() {
+        let original = integers(0..100);
+
+        let mut pushed = original.clone();
+        pushed.push(Object::Integer(100));
+        let mut replaced = original.clone();
+        assert!(replaced.set(50, Object::Unit));
+        assert!(!replaced.set(100, Object::Unit));
+
+        assert_eq!(integers(0..100), original);
+        assert_eq!(integers(0..101), pushed);
+        assert_eq!(Some(&Object::Unit), replaced.get(50));
+        assert_eq!(Some(&Object::Integer(51)), replaced.get(51));
+    }
+
+    #[test]
+    fn test_slice() {
+        let vector = integers(0..100);
+        let tests = [
+            (0, 100, integers(0..100)),
+            (1, 100, integers(1..100)),
+            (30, 70, integers(30..70)),
+            (99, 100, integers(99..100)),
+            (50, 50, integers(0..0)),
+        ];
+
+        for (start, end, expected) in tests {
+            assert_eq!(expected, vector.slice(start, end), "Failed to slice {start}..{end}");
+        }
+    }
+
+    #[test]
+    fn test_push_to_slice() {
+        let vector = integers(0..40);
+        let mut slice = vector.slice(10, 20);
+
+        slice.push(Object::Unit);
+
+        assert_eq!(11, slice.len());
+        assert_eq!(Some(&Object::Unit), slice.last());
+        assert_eq!(Some(&Object::Integer(20)), vector.get(20));
+        assert_eq!(integers(0..40), vector);
+    }
+
+    fn integers(range: std::ops::Range<i32>) -> Vector {
+        range.map(Object::Integer).collect()
+    }
+}
@@ -0,0 +1,4 @@
+pub mod optimizer;
+
+#[cfg(test)]
+mod optimizer_test;
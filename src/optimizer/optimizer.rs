@@ -0,0 +1,257 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::ast::ast::{Node, Program, Resolution};
+use crate::ast::expression::Expression;
+use crate::ast::pattern::Pattern;
+use crate::evaluator::operator_expression::apply_operator_to_objects;
+use crate::object::object::Object;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn enable() {
+    ENABLED.set(true);
+}
+
+// Optimizes the program when enabled with --optimize, run before resolving it
+pub fn apply(program: Program) -> Program {
+    if ENABLED.get() {
+        optimize(program)
+    } else {
+        program
+    }
+}
+
+// Folds operators on literal operands, removes if- and when-branches whose conditions
+// are literals and flattens nested expression literals that only sequence expressions.
+// Folded and replaced nodes keep the token of the expression they replace, so errors
+// are reported at the same position as in the original program.
+pub fn optimize(program: Program) -> Program {
+    let mut nodes = program.nodes;
+    nodes.iter_mut().for_each(optimize_node);
+    Program { nodes }
+}
+
+fn optimize_node(node: &mut Node) {
+    match &mut node.expression {
+        Expression::ExpressionLiteral(nodes)
+        | Expression::Array(nodes)
+        | Expression::Interpolation(nodes)
+        | Expression::Operator(_, nodes) => nodes.iter_mut().for_each(optimize_node),
+        Expression::Set(variables) => if let Some(variables) = Rc::get_mut(variables) {
+            variables.iter_mut().for_each(|(_, value)| optimize_node(value));
+        },
+        Expression::Index(index, operand) => {
+            optimize_node(index);
+            optimize_node(operand);
+        }
+        Expression::If(condition, consequence, alternative) => {
+            optimize_node(condition);
+            optimize_node(consequence);
+            alternative.iter_mut().for_each(|alternative| optimize_node(alternative));
+        }
+        Expression::When(branches) => branches.iter_mut().for_each(|(condition, consequence)| {
+            optimize_node(condition);
+            optimize_node(consequence);
+        }),
+        Expression::Match(value, branches) => {
+            optimize_node(value);
+            for (pattern, guard, consequence) in branches.iter_mut() {
+                optimize_pattern(pattern);
+                guard.iter_mut().for_each(|guard| optimize_node(guard));
+                optimize_node(consequence);
+            }
+        }
+        Expression::While(condition, loop_body) => {
+            optimize_node(condition);
+            loop_body.iter_mut().for_each(|loop_body| optimize_node(loop_body));
+        }
+        Expression::For(_, iterable, body) => {
+            optimize_node(iterable);
+            optimize_node(body);
+        }
+        Expression::Record(_, _) | Expression::Enum(_, _) => {}
+        Expression::Function(_, _, body) => if let Some(body) = Rc::get_mut(body) {
+            optimize_node(body);
+        },
+        Expression::Test(name, body) => {
+            optimize_node(name);
+            optimize_node(body);
+        }
+        Expression::Section(node)
+        | Expression::Include(node)
+        | Expression::Spread(node) => optimize_node(node),
+        // Quoted code is data and macros are expanded away before optimizing
+        Expression::Unquote(node) => optimize_node(node),
+        Expression::Macro(_, _, _, _)
+        | Expression::Quote(_)
+        | Expression::Quasiquote(_)
+        | Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_) => {}
+    }
+
+    if let Some(replacement) = simplify(node) {
+        *node = replacement;
+    }
+}
+
+fn optimize_pattern(pattern: &mut Pattern) {
+    match pattern {
+        Pattern::Literal(literal) => optimize_node(literal),
+        Pattern::Array(elements, rest) => {
+            elements.iter_mut().for_each(optimize_pattern);
+            rest.iter_mut().for_each(|rest| optimize_pattern(rest));
+        }
+        Pattern::Type(inner, _) => optimize_pattern(inner),
+        Pattern::Wildcard | Pattern::Binding(_) => {}
+    }
+}
+
+// The simpler node to replace the given one with, its children are already optimized
+fn simplify(node: &mut Node) -> Option<Node> {
+    match &mut node.expression {
+        Expression::Operator(operator, operands) => {
+            let operands = operands.iter()
+                .map(constant)
+                .collect::<Option<Vec<Object>>>()?;
+            if overflows(operator, &operands) {
+                return None;
+            }
+            let folded = apply_operator_to_objects(operator, operands).ok()?;
+            literal(folded, node)
+        }
+        Expression::If(condition, consequence, alternative) => {
+            if constant(condition)?.is_truthy() {
+                Some(take(consequence))
+            } else {
+                // Without an alternative the if-expression evaluates to its condition
+                Some(take(alternative.as_mut().unwrap_or(condition)))
+            }
+        }
+        Expression::When(branches) => simplify_when(branches),
+        Expression::ExpressionLiteral(nodes) => simplify_expression_literal(nodes),
+        _ => None,
+    }
+}
+
+fn simplify_when(branches: &mut Box<[(Box<Node>, Box<Node>)]>) -> Option<Node> {
+    let count = branches.len();
+    let mut kept = Vec::new();
+    for (index, (condition, consequence)) in std::mem::take(branches).into_vec().into_iter().enumerate() {
+        match constant(&condition).map(|condition| condition.is_truthy()) {
+            Some(true) => {
+                kept.push((condition, consequence));
+                break;
+            }
+            // When no branch is taken the last condition is the result, so keep it
+            Some(false) if index + 1 < count => {}
+            _ => kept.push((condition, consequence)),
+        }
+    }
+
+    let replacement = match kept.as_mut_slice() {
+        [(condition, consequence)] => match constant(condition).map(|condition| condition.is_truthy()) {
+            Some(true) => Some(take(consequence)),
+            Some(false) => Some(take(condition)),
+            None => None,
+        },
+        _ => None,
+    };
+    if replacement.is_none() {
+        *branches = kept.into_boxed_slice();
+    }
+    replacement
+}
+
+// An expression literal starting with a constant can't be a call, so it only evaluates
+// its expressions in order. Nested ones of those are spliced into it.
+fn simplify_expression_literal(nodes: &mut Box<[Node]>) -> Option<Node> {
+    match nodes.as_mut() {
+        [single] if constant(single).is_some() => return Some(take(single)),
+        [first, ..] if constant(first).is_some() => {}
+        _ => return None,
+    }
+
+    let mut flattened = Vec::new();
+    for (index, mut node) in std::mem::take(nodes).into_vec().into_iter().enumerate() {
+        match &mut node.expression {
+            Expression::ExpressionLiteral(inner) if index > 0 && is_sequence(inner) => {
+                flattened.extend(std::mem::take(inner).into_vec().into_iter().skip(1));
+            }
+            _ => flattened.push(node),
+        }
+    }
+
+    // Constants before the last expression have no effect
+    if flattened.iter().all(|node| constant(node).is_some()) {
+        return flattened.pop();
+    }
+    *nodes = flattened.into_boxed_slice();
+    None
+}
+
+fn is_sequence(nodes: &[Node]) -> bool {
+    nodes.len() > 1
+        && constant(&nodes[0]).is_some()
+        && !nodes.iter().any(|node| matches!(node.expression, Expression::Spread(_)))
+}
+
+fn constant(node: &Node) -> Option<Object> {
+    match &node.expression {
+        Expression::Integer(value) => Some(Object::Integer(*value)),
+        Expression::Float(value) => Some(Object::Float(*value)),
+        Expression::Boolean(value) => Some(Object::Boolean(*value)),
+        Expression::String(value) => Some(Object::String(value.clone())),
+        _ => None,
+    }
+}
+
+fn literal(object: Object, node: &Node) -> Option<Node> {
+    let expression = match object {
+        Object::Integer(value) => Expression::Integer(value),
+        Object::Float(value) => Expression::Float(value),
+        Object::Boolean(value) => Expression::Boolean(value),
+        Object::String(value) => Expression::String(value),
+        _ => return None,
+    };
+    Some(Node { expression, token: node.token.clone(), resolution: Resolution::default() })
+}
+
+// Integer arithmetic that would overflow is left to fail when it's evaluated, if ever
+fn overflows(operator: &str, operands: &[Object]) -> bool {
+    let integers = operands.iter()
+        .map(|operand| match operand {
+            Object::Integer(value) => Some(*value),
+            _ => None,
+        })
+        .collect::<Option<Vec<i32>>>();
+    let Some(integers) = integers else {
+        return false;
+    };
+    let result = match (operator, integers.as_slice()) {
+        ("-", [value]) => value.checked_neg(),
+        ("+", [first, rest @ ..]) => rest.iter().try_fold(*first, |left, right| left.checked_add(*right)),
+        ("-", [first, rest @ ..]) => rest.iter().try_fold(*first, |left, right| left.checked_sub(*right)),
+        ("*", _) => integers.iter().try_fold(1i32, |left, right| left.checked_mul(*right)),
+        ("/" | "//", [first, rest @ ..]) => rest.iter().try_fold(*first, |left, right| match right {
+            0 => Some(left),
+            _ => left.checked_rem(*right).and(left.checked_div(*right)),
+        }),
+        _ => Some(0),
+    };
+    result.is_none()
+}
+
+fn take(node: &mut Node) -> Node {
+    let placeholder = Node {
+        expression: Expression::ExpressionLiteral(Box::default()),
+        token: node.token.clone(),
+        resolution: Resolution::default(),
+    };
+    std::mem::replace(node, placeholder)
+}
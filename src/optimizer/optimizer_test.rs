@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod test {
+    use crate::ast::ast::{Node, Program};
+    use crate::ast::expression::Expression;
+    use crate::evaluator::evaluator::Eval;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::environment::Environment;
+    use crate::optimizer::optimizer::optimize;
+    use crate::parser::parser::Parser;
+
+    #[test]
+    fn test_optimize() {
+        let tests = [
+            ("(+ 1 2 (* 3 4))", "15"),
+            ("(+ \"a\" 1)", "\"a1\""),
+            ("(< 1 2.5)", "true"),
+            ("(! 0)", "true"),
+            ("(+ x (* 2 3))", "(+ x 6)"),
+            ("(+ 1 true)", "(+ 1 true)"),
+            ("(/ 1 0)", "(/ 1 0)"),
+            ("(+ 2147483647 1)", "(+ 2147483647 1)"),
+            ("(if true (f) (g))", "(f)"),
+            ("(if (= 1 2) (f) (g))", "(g)"),
+            ("(if 0 (f))", "0"),
+            ("(if x (f))", "(if x (f))"),
+            ("(when false (a) x (b) true (c) y (d))", "(when x (b) true (c))"),
+            ("(when false (a) 0 (b))", "0"),
+            ("(when (> 2 1) (a) x (b))", "(a)"),
+            ("(((1 2)))", "2"),
+            ("(1 (2 (f) (g)) (h))", "(1 (f) (g) (h))"),
+            ("((f) (1 (g)))", "((f) (1 (g)))"),
+            ("(fn |x| (if (< 1 2) (+ x (- 3 1))))", "(fn (+ x 2))"),
+        ];
+
+        for (input, expected) in tests {
+            let program = optimize(parse(input));
+            let actual = program.nodes.iter()
+                .map(render)
+                .collect::<Vec<String>>()
+                .join(" ");
+            assert_eq!(expected, actual, "Failed to optimize: {input}");
+        }
+    }
+
+    #[test]
+    fn test_optimize_keeps_error_positions() {
+        let tests = [
+            "(+ 1 (* 2 3) true)",
+            "(if true\n    (* 1 (- 4 2) \"a\"))",
+            "(when false 1\n      (= 1 1) (@ 5 [1 2]))",
+            "(1 (2 (+ [] 1)))",
+        ];
+
+        for input in tests {
+            let expected = parse(input).eval(&mut Environment::new()).map_err(|error| error.to_string());
+            let actual = optimize(parse(input)).eval(&mut Environment::new()).map_err(|error| error.to_string());
+            assert!(expected.is_err(), "Expected error for: {input}");
+            assert_eq!(expected, actual, "Failed to evaluate: {input}");
+        }
+    }
+
+    fn parse(input: &str) -> Program {
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input))
+    }
+
+    fn render(node: &Node) -> String {
+        let list = |head: &str, nodes: Vec<&Node>| {
+            let rendered = nodes.into_iter().map(render).collect::<Vec<String>>();
+            format!("({})", [head.to_string()].into_iter().chain(rendered).filter(|part| !part.is_empty()).collect::<Vec<String>>().join(" "))
+        };
+        match &node.expression {
+            Expression::Integer(value) => value.to_string(),
+            Expression::Float(value) => value.to_string(),
+            Expression::Boolean(value) => value.to_string(),
+            Expression::String(value) => format!("\"{value}\""),
+            Expression::Identifier(name) => name.to_string(),
+            Expression::ExpressionLiteral(_) => list("", node.children()),
+            Expression::Operator(operator, _) => list(operator, node.children()),
+            Expression::If(_, _, _) => list("if", node.children()),
+            Expression::When(_) => list("when", node.children()),
+            Expression::Function(_, _, body) => list("fn", vec![body]),
+            expression => format!("{expression:?}"),
+        }
+    }
+}
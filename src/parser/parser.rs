@@ -1,7 +1,8 @@
 use std::mem;
 
-use crate::ast::ast::{Node, Program};
+use crate::ast::ast::{Node, Program, Resolution};
 use crate::ast::expression::Expression;
+use crate::ast::pattern::Pattern;
 use crate::lexer::lexer::Lexer;
 use crate::parser::error::ParseError;
 use crate::token::token::{Token, TokenType};
@@ -103,7 +104,7 @@ impl Parser<'_> {
         if self.current_token_is(TokenType::LParen) && self.peek_token_is(TokenType::RParen) {
             let current = self.next_token();
             self.next_token();
-            return Node { expression: Expression::ExpressionLiteral(Box::default()), token: current }.into();
+            return Node { expression: Expression::ExpressionLiteral(Box::default()), token: current, resolution: Resolution::default() }.into();
         }
 
         let mut in_parenthesis = false;
@@ -130,13 +131,22 @@ impl Parser<'_> {
             TokenType::Set => self.parse_set(),
             TokenType::If => self.parse_if(),
             TokenType::When => self.parse_when(),
+            TokenType::Match => self.parse_match(),
             TokenType::While => self.parse_while(),
+            TokenType::For => self.parse_for(),
             TokenType::Include => self.parse_include(),
+            TokenType::Test => self.parse_test(),
             TokenType::Function => self.parse_function(),
+            TokenType::Macro => self.parse_macro(),
+            TokenType::Record => self.parse_record(),
+            TokenType::Enum => self.parse_enum(),
+            TokenType::Quote
+            | TokenType::Quasiquote
+            | TokenType::Unquote => self.parse_quote(),
             TokenType::Section => self.parse_scoped_section(),
             TokenType::Int => self.parse_integer_literal(),
             TokenType::Float => self.parse_float_literal(),
-            TokenType::String => self.parse_string_literal().into(),
+            TokenType::String => self.parse_string_literal(),
             TokenType::LBracket => self.parse_array_literal(),
             TokenType::Ident => self.parse_identifier().into(),
             TokenType::At => self.parse_index_operator(),
@@ -145,10 +155,17 @@ impl Parser<'_> {
             | TokenType::Minus
             | TokenType::Asterisk
             | TokenType::Slash
+            | TokenType::DoubleSlash
+            | TokenType::Percent
             | TokenType::GreaterThan
             | TokenType::LesserThan
+            | TokenType::GreaterEquals
+            | TokenType::LesserEquals
             | TokenType::Bang
-            | TokenType::Equals => self.parse_prefix_operator(),
+            | TokenType::BangEquals
+            | TokenType::Equals
+            | TokenType::And
+            | TokenType::Or => self.parse_prefix_operator(),
             TokenType::True | TokenType::False => self.parse_boolean().into(),
             _ => {
                 let current = self.next_token();
@@ -172,6 +189,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::ExpressionLiteral(Box::from(expressions)),
             token,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -181,9 +199,14 @@ impl Parser<'_> {
         let mut list = Vec::new();
 
         while self.current_token_is(TokenType::LParen) {
-            self.expect_peek(TokenType::Ident)?;
-
-            let identifier = self.parse_identifier();
+            // Macro templates may unquote the name to set
+            let identifier = if self.peek_token_is(TokenType::LParen) {
+                self.next_token();
+                self.parse_unquoted_identifier()?
+            } else {
+                self.expect_peek(TokenType::Ident)?;
+                self.parse_identifier()
+            };
 
             let value = self.parse_expression()?;
 
@@ -196,6 +219,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Set(list.into()),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -214,6 +238,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::If(condition.into(), consequence.into(), alternative),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -237,9 +262,111 @@ impl Parser<'_> {
         Node {
             expression: Expression::When(branches.into()),
             token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    fn parse_match(&mut self) -> Result<Node, ParseError> {
+        let current = self.next_token();
+        let value = self.parse_expression()?;
+
+        let mut branches = Vec::new();
+
+        while !self.current_token_is(TokenType::RParen) {
+            let pattern = self.parse_pattern()?;
+
+            let mut guard = None;
+            if self.current_token_is(TokenType::If) {
+                self.next_token();
+                guard = Box::from(self.parse_expression()?).into();
+            }
+
+            if self.current_token_is(TokenType::RParen) {
+                return ParseError{
+                    col: self.current_token.col, row: self.current_token.row,
+                    message: "Expected consequence for pattern in match-expression".to_string()
+                }.into();
+            }
+            let consequence = self.parse_expression()?;
+            branches.push((pattern, guard, consequence.into()))
+        }
+
+        Node {
+            expression: Expression::Match(value.into(), branches.into()),
+            token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        match self.current_token.token_type {
+            TokenType::Ident => {
+                let identifier = self.parse_identifier();
+                let pattern = match identifier.expression {
+                    Expression::Identifier(ref name) if name.as_ref() == "_" => Pattern::Wildcard,
+                    _ => Pattern::Binding(identifier),
+                };
+                if !self.current_token_is(TokenType::DoubleColon) {
+                    return Ok(pattern);
+                }
+                self.next_token();
+                if !self.current_token_is(TokenType::Ident) {
+                    return ParseError {
+                        col: self.current_token.col, row: self.current_token.row,
+                        message: format!("Expected type name in pattern but got {:?}", self.current_token.token_type)
+                    }.into();
+                }
+                let type_name = self.next_token().literal;
+                Ok(Pattern::Type(pattern.into(), type_name))
+            }
+            TokenType::Int
+            | TokenType::Float
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False => Ok(Pattern::Literal(self.parse_expression()?)),
+            TokenType::LParen if self.peek_token_is(TokenType::RParen) => Ok(Pattern::Literal(self.parse_expression()?)),
+            TokenType::LBracket => self.parse_array_pattern(),
+            _ => {
+                let current = self.next_token();
+                ParseError {
+                    col: current.col, row: current.row,
+                    message: format!("Could not parse pattern token type '{:?}' with literal '{}'", current.token_type, current.literal)
+                }.into()
+            }
+        }
+    }
+
+    fn parse_array_pattern(&mut self) -> Result<Pattern, ParseError> {
+        self.next_token();
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        while !self.current_token_is(TokenType::RBracket) {
+            if self.current_token_is(TokenType::DoubleDot) {
+                self.next_token();
+                let pattern = self.parse_pattern()?;
+                let (Pattern::Binding(_) | Pattern::Wildcard) = pattern else {
+                    return ParseError {
+                        col: self.current_token.col, row: self.current_token.row,
+                        message: "Expected identifier for rest of array pattern".to_string()
+                    }.into();
+                };
+                if !self.current_token_is(TokenType::RBracket) {
+                    return ParseError {
+                        col: self.current_token.col, row: self.current_token.row,
+                        message: "Expected rest to be last in array pattern".to_string()
+                    }.into();
+                }
+                rest = Some(Box::from(pattern));
+                break;
+            }
+            elements.push(self.parse_pattern()?);
+        }
+        self.next_token();
+
+        Ok(Pattern::Array(elements.into(), rest))
+    }
+
     fn parse_while(&mut self) -> Result<Node, ParseError> {
         let current = self.next_token();
         let condition = self.parse_expression()?;
@@ -252,21 +379,167 @@ impl Parser<'_> {
         Node {
             expression: Expression::While(condition.into(), loop_expression),
             token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    fn parse_for(&mut self) -> Result<Node, ParseError> {
+        let current = self.expect_peek(TokenType::Ident)?;
+        let binding = self.parse_identifier();
+        let iterable = self.parse_expression()?;
+        let body = self.parse_expression()?;
+
+        Node {
+            expression: Expression::For(binding.into(), iterable.into(), body.into()),
+            token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
     fn parse_include(&mut self) -> Result<Node, ParseError> {
         let current = self.next_token();
-        let target = self.parse_string_literal();
+        let target = self.parse_string_literal()?;
 
         Node {
             expression: Expression::Include(target.into()),
             token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    fn parse_test(&mut self) -> Result<Node, ParseError> {
+        let current = self.next_token();
+        let name = self.parse_expression()?;
+        let body = self.parse_expression()?;
+
+        Node {
+            expression: Expression::Test(name.into(), body.into()),
+            token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
     fn parse_function(&mut self) -> Result<Node, ParseError> {
         let current = self.expect_peek(TokenType::Pipe)?;
+        let (parameters, vararg) = self.parse_parameters()?;
+
+        let body = self.parse_expression()?;
+
+        Node {
+            expression: Expression::Function(parameters.into(), vararg.into(), body.into()),
+            token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    fn parse_macro(&mut self) -> Result<Node, ParseError> {
+        let current = self.expect_peek(TokenType::Ident)?;
+        let name = self.parse_identifier();
+        if !self.current_token_is(TokenType::Pipe) {
+            return ParseError {
+                col: self.current_token.col, row: self.current_token.row,
+                message: format!("Expected parameter list for macro but got {:?}", self.current_token.token_type)
+            }.into();
+        }
+        let (parameters, vararg) = self.parse_parameters()?;
+
+        let body = self.parse_expression()?;
+
+        Node {
+            expression: Expression::Macro(name.into(), parameters.into(), vararg.into(), body.into()),
+            token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    // Parses `record Name field...` up to the closing parenthesis
+    fn parse_record(&mut self) -> Result<Node, ParseError> {
+        let current = self.expect_peek(TokenType::Ident)?;
+        let name = self.parse_identifier();
+        let fields = self.parse_fields(&name)?;
+
+        Node {
+            expression: Expression::Record(name.into(), fields.into()),
+            token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    // Parses `enum Name Variant (Variant field...)...` up to the closing parenthesis
+    fn parse_enum(&mut self) -> Result<Node, ParseError> {
+        let current = self.expect_peek(TokenType::Ident)?;
+        let name = self.parse_identifier();
+
+        let mut variants: Vec<Node> = Vec::new();
+        while !self.current_token_is(TokenType::RParen) {
+            let variant = match (&self.current_token.token_type, &self.peek_token.token_type) {
+                (TokenType::Ident, _) => self.parse_identifier(),
+                (TokenType::LParen, TokenType::Ident) => {
+                    let token = self.next_token();
+                    let variant = self.parse_identifier();
+                    let fields = self.parse_fields(&variant)?;
+                    self.next_token();
+                    Node {
+                        expression: Expression::Record(variant.into(), fields.into()),
+                        token,
+                        resolution: Resolution::default(),
+                    }
+                }
+                _ => return ParseError {
+                    col: self.current_token.col, row: self.current_token.row,
+                    message: format!("Expected variant in enum but got {:?}", self.current_token.token_type)
+                }.into(),
+            };
+            let variant_name = |variant: &Node| match &variant.expression {
+                Expression::Record(name, _) => name.token.literal.clone(),
+                _ => variant.token.literal.clone(),
+            };
+            if variants.iter().any(|other| variant_name(other) == variant_name(&variant)) {
+                return ParseError {
+                    col: variant.token.col, row: variant.token.row,
+                    message: format!("Duplicate variant '{}' in enum {}", variant_name(&variant), name.token.literal)
+                }.into();
+            }
+            variants.push(variant);
+        }
+        if variants.is_empty() {
+            return ParseError {
+                col: self.current_token.col, row: self.current_token.row,
+                message: format!("Expected variants in enum {}", name.token.literal)
+            }.into();
+        }
+
+        Node {
+            expression: Expression::Enum(name.into(), variants.into()),
+            token: current,
+            resolution: Resolution::default(),
+        }.into()
+    }
+
+    // Parses the field names of a record up to the closing parenthesis
+    fn parse_fields(&mut self, name: &Node) -> Result<Vec<Node>, ParseError> {
+        let mut fields: Vec<Node> = Vec::new();
+        while !self.current_token_is(TokenType::RParen) {
+            if !self.current_token_is(TokenType::Ident) {
+                return Err(ParseError {
+                    col: self.current_token.col, row: self.current_token.row,
+                    message: format!("Expected field name in record but got {:?}", self.current_token.token_type)
+                });
+            }
+            let field = self.parse_identifier();
+            if fields.iter().any(|other| other.token.literal == field.token.literal) {
+                return Err(ParseError {
+                    col: field.token.col, row: field.token.row,
+                    message: format!("Duplicate field '{}' in record {}", field.token.literal, name.token.literal)
+                });
+            }
+            fields.push(field);
+        }
+        Ok(fields)
+    }
+
+    // Parses `|a b ...rest|` starting at the opening pipe
+    fn parse_parameters(&mut self) -> Result<(Vec<Node>, Option<Node>), ParseError> {
         self.next_token();
 
         let mut parameters = Vec::new();
@@ -278,10 +551,10 @@ impl Parser<'_> {
                     self.next_token();
                     let identifier = self.parse_identifier();
                     if !self.current_token_is(TokenType::Pipe) {
-                        return ParseError {
+                        return Err(ParseError {
                             col: self.current_token.col, row: self.current_token.row,
                             message: "Expected vararg identifier to be last in parameter list.".to_string()
-                        }.into();
+                        });
                     }
                     vararg = Some(identifier);
                     break;
@@ -291,24 +564,44 @@ impl Parser<'_> {
                     parameters.push(param)
                 }
                 _ => {
-                    return ParseError {
+                    return Err(ParseError {
                         col: self.current_token.col, row: self.current_token.row,
                         message: "Expected function parameters names.".to_string()
-                    }.into();
+                    });
                 }
             };
         }
 
         self.next_token();
+        Ok((parameters, vararg))
+    }
 
-        let body = self.parse_expression()?;
+    fn parse_quote(&mut self) -> Result<Node, ParseError> {
+        let current = self.next_token();
+        let quoted = self.parse_expression()?;
 
+        let expression = match current.token_type {
+            TokenType::Quote => Expression::Quote(quoted.into()),
+            TokenType::Quasiquote => Expression::Quasiquote(quoted.into()),
+            _ => Expression::Unquote(quoted.into()),
+        };
         Node {
-            expression: Expression::Function(parameters.into(), vararg.into(), body.into()),
+            expression,
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
+    fn parse_unquoted_identifier(&mut self) -> Result<Node, ParseError> {
+        if !self.peek_token_is(TokenType::Unquote) {
+            return ParseError {
+                col: self.peek_token.col, row: self.peek_token.row,
+                message: format!("Expected next token to be {:?} but got {:?}", TokenType::Ident, self.peek_token.token_type)
+            }.into();
+        }
+        self.parse_expression()
+    }
+
     fn parse_scoped_section(&mut self) -> Result<Node, ParseError> {
         let current = self.next_token();
 
@@ -316,7 +609,8 @@ impl Parser<'_> {
 
         Node {
             expression: Expression::Section(section.into()),
-            token: current
+            token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -325,6 +619,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Identifier(token.literal.clone()),
             token,
+            resolution: Resolution::default(),
         }
     }
 
@@ -344,6 +639,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Integer(value),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -362,15 +658,71 @@ impl Parser<'_> {
         Node {
             expression: Expression::Float(value),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
-    fn parse_string_literal(&mut self) -> Node {
+    // Strings may interpolate expressions in braces, "Hello {name}". Literal braces are written twice,
+    // "{{" and "}}", and placeholders of format such as "{}" or "{0:>5}" are kept as they are.
+    fn parse_string_literal(&mut self) -> Result<Node, ParseError> {
         let current = self.next_token();
+        // Columns of the characters of the literal are counted in the source, where escapes are longer
+        let offsets = self.lexer.take_string_offsets(&current);
+        let col_of = |index: usize| current.col + offsets.as_ref()
+            .map_or(index as u32 + 1, |offsets| offsets[index.min(offsets.len() - 1)]);
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = current.literal.char_indices().peekable();
+        while let Some((index, char)) = chars.next() {
+            let position = current.literal[..index].chars().count();
+            let col = col_of(position);
+            match char {
+                '{' if chars.next_if(|(_, next)| *next == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|(_, next)| *next == '}').is_some() => text.push('}'),
+                '}' => return ParseError {
+                    col, row: current.row,
+                    message: "Unmatched } in string, write }} for a literal }".to_string(),
+                }.into(),
+                '{' => {
+                    let start = index + 1;
+                    let mut depth = 0;
+                    let end = loop {
+                        match chars.next() {
+                            Some((end, '}')) if depth == 0 => break end,
+                            Some((_, '}')) => depth -= 1,
+                            Some((_, '{')) => depth += 1,
+                            Some(_) => {}
+                            None => return ParseError {
+                                col, row: current.row,
+                                message: "Unclosed { in string, write {{ for a literal {".to_string(),
+                            }.into(),
+                        }
+                    };
+                    let source = &current.literal[start..end];
+                    if is_placeholder(source) {
+                        text.push_str(&current.literal[index..=end]);
+                        continue;
+                    }
+                    if !text.is_empty() {
+                        parts.push(string_node(mem::take(&mut text), &current));
+                    }
+                    parts.push(parse_interpolated(source, current.row, &|index| col_of(position + 1 + index))?);
+                }
+                _ => text.push(char),
+            }
+        }
+
+        if parts.is_empty() {
+            return string_node(text, &current).into();
+        }
+        if !text.is_empty() {
+            parts.push(string_node(text, &current));
+        }
         Node {
-            expression: Expression::String(current.literal.clone()),
+            expression: Expression::Interpolation(parts.into()),
             token: current,
-        }
+            resolution: Resolution::default(),
+        }.into()
     }
 
     fn parse_array_literal(&mut self) -> Result<Node, ParseError> {
@@ -384,6 +736,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Array(expressions.into()),
             token,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -392,18 +745,28 @@ impl Parser<'_> {
         Node {
             expression: Expression::Boolean(current.token_type == TokenType::True),
             token: current,
+            resolution: Resolution::default(),
         }
     }
 
+    // A path of indices (@ i j operand) is parsed as the nested (@ j (@ i operand))
     fn parse_index_operator(&mut self) -> Result<Node, ParseError> {
         let current = self.next_token();
-        let index = self.parse_expression()?;
-        let operand = self.parse_expression()?;
+        let mut indices = vec![self.parse_expression()?];
+        let mut operand = self.parse_expression()?;
+        while !self.current_token_is(TokenType::RParen) && !self.current_token_is(TokenType::EOF) {
+            indices.push(operand);
+            operand = self.parse_expression()?;
+        }
 
-        Node {
-            expression: Expression::Index(index.into(), operand.into()),
-            token: current,
-        }.into()
+        for index in indices {
+            operand = Node {
+                expression: Expression::Index(index.into(), operand.into()),
+                token: current.clone(),
+                resolution: Resolution::default(),
+            };
+        }
+        operand.into()
     }
 
     fn parse_spread_operator(&mut self) -> Result<Node, ParseError> {
@@ -413,6 +776,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Spread(target.into()),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 
@@ -428,6 +792,7 @@ impl Parser<'_> {
         Node {
             expression: Expression::Operator(current.literal.clone(), operands.into()),
             token: current,
+            resolution: Resolution::default(),
         }.into()
     }
 }
@@ -446,6 +811,53 @@ impl<'a> From<Lexer<'a>> for Parser<'a> {
     }
 }
 
+fn string_node(text: String, token: &Token) -> Node {
+    Node {
+        expression: Expression::String(text.into()),
+        token: token.clone(),
+        resolution: Resolution::default(),
+    }
+}
+
+// Placeholders of the format builtin, empty or a position and/or a format spec
+fn is_placeholder(source: &str) -> bool {
+    let position = source.split_once(':').map_or(source, |(position, _)| position);
+    position.chars().all(|char| char.is_ascii_digit())
+}
+
+// Parses the expression of an interpolation in a string literal on the given row, col_of gives
+// the column in the program of a character of the expression
+fn parse_interpolated(source: &str, row: u32, col_of: &dyn Fn(usize) -> u32) -> Result<Node, ParseError> {
+    let relocate = |error: ParseError| ParseError {
+        col: col_of(char_index(source, error.row, error.col)),
+        row,
+        message: error.message,
+    };
+    let program = Parser::from(Lexer::from(source)).parse_program()
+        .map_err(|errors| relocate(errors.into_iter().next().expect("Failed parsing without errors")))?;
+    let [mut node] = <[Node; 1]>::try_from(program.nodes.into_vec()).map_err(|_| ParseError {
+        col: col_of(0), row,
+        message: "Expected one expression in string interpolation".to_string(),
+    })?;
+    relocate_node(&mut node, source, row, col_of);
+    Ok(node)
+}
+
+fn relocate_node(node: &mut Node, source: &str, row: u32, col_of: &dyn Fn(usize) -> u32) {
+    node.token.col = col_of(char_index(source, node.token.row, node.token.col));
+    node.token.row = row;
+    node.children_mut().into_iter().for_each(|child| relocate_node(child, source, row, col_of));
+}
+
+// Index of the character at the row and col the lexer gave it in the source
+fn char_index(source: &str, row: u32, col: u32) -> usize {
+    let line_start = source.split('\n')
+        .take(row as usize - 1)
+        .map(|line| line.chars().count() + 1)
+        .sum::<usize>();
+    line_start + col as usize - 1
+}
+
 impl From<Token> for Result<Token, ParseError> {
     fn from(value: Token) -> Self {
         Ok(value)
@@ -468,3 +880,9 @@ impl From<ParseError> for Result<Node, ParseError> {
         Err(value)
     }
 }
+
+impl From<ParseError> for Result<Pattern, ParseError> {
+    fn from(value: ParseError) -> Self {
+        Err(value)
+    }
+}
@@ -3,7 +3,9 @@ use crate::ast::expression::Expression;
 
 #[cfg(test)]
 mod parser_test {
+    use crate::ast::ast::AST;
     use crate::ast::expression::Expression;
+    use crate::ast::pattern::Pattern;
     use crate::lexer::lexer::Lexer;
     use crate::parser::parser::Parser;
     use crate::parser::parser_test::{assert_expression, assert_nodes, Expect, Expected};
@@ -95,6 +97,11 @@ mod parser_test {
             ("(< 1 2 3)", "<", [1.expect(), 2.expect(), 3.expect()]),
             ("(> 1 2 3)", ">", [1.expect(), 2.expect(), 3.expect()]),
             ("(! true 2 3)", "!", [true.expect(), 2.expect(), 3.expect()]),
+            ("(<= 1 2 3)", "<=", [1.expect(), 2.expect(), 3.expect()]),
+            ("(>= 1 2 3)", ">=", [1.expect(), 2.expect(), 3.expect()]),
+            ("(!= 1 2 3)", "!=", [1.expect(), 2.expect(), 3.expect()]),
+            ("(and true 2 3)", "and", [true.expect(), 2.expect(), 3.expect()]),
+            ("(or true 2 3)", "or", [true.expect(), 2.expect(), 3.expect()]),
         ];
 
         for (input, expected_operator, expected_operands) in prefix_test {
@@ -190,6 +197,44 @@ mod parser_test {
         assert_eq!("This is text", value.as_ref())
     }
 
+    #[test]
+    fn test_string_interpolation() {
+        let input = "\"a {b} c\"";
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap();
+
+        let Expression::Interpolation(parts) = &program.nodes[0].expression else {
+            panic!("Expected interpolation got={:?}", program.nodes[0].expression);
+        };
+        let expected = [Expression::String("a ".into()), Expression::Identifier("b".into()), Expression::String(" c".into())];
+        assert_eq!(expected.as_slice(), parts.iter().map(|part| part.expression.clone()).collect::<Vec<_>>());
+        assert_eq!((1, 5), (parts[1].token.row, parts[1].token.col), "Expected position of the expression in the program");
+    }
+
+    #[test]
+    fn test_string_interpolation_errors() {
+        let tests = [
+            ("\"a } b\"", "Row 1, Col: 4: Unmatched } in string, write }} for a literal }"),
+            ("\"a { b\"", "Row 1, Col: 4: Unclosed { in string, write {{ for a literal {"),
+            ("\"{a b}\"", "Row 1, Col: 3: Expected one expression in string interpolation"),
+            ("(+ 1 \"{(+ 1}\")", "Row 1, Col: 11: Unexpected end of file"),
+            ("\"{{a}} {(+ 1}\"", "Row 1, Col: 12: Unexpected end of file"),
+            ("\"\\\"\\n{(+ 1}\"", "Row 1, Col: 10: Unexpected end of file"),
+            ("\"{(+ \\\"a\\\" 1}\"", "Row 1, Col: 12: Unexpected end of file"),
+            ("\"{{\\t{a b}\"", "Row 1, Col: 7: Expected one expression in string interpolation"),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let Err(errors) = parser.parse_program() else {
+                panic!("Expected parse error for input: {input}");
+            };
+            assert_eq!(expected, errors[0].to_string(), "input {input}");
+        }
+    }
+
     #[test]
     fn test_array_expression() {
         let tests = [
@@ -263,6 +308,14 @@ mod parser_test {
         };
 
         assert_eq!("foobar", identifier.as_ref());
+
+        let input = "(@ 1 -1 foobar)";
+
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!("(@ -1 (@ 1 foobar))", program.string().as_ref(), "input {input}");
     }
 
     #[test]
@@ -594,6 +647,76 @@ mod parser_test {
         assert_eq!("c", param.as_ref());
     }
 
+    #[test]
+    fn test_match_expression() {
+        let input = "(match x 1 \"one\" [a ..rest] a n::Integer if (> n 1) n _ ())";
+
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(1, program.nodes.len(), "Expected 1 node in program for input: {input}");
+
+        let Expression::Match(value, branches) = &program.nodes[0].expression else {
+            panic!("Expected match-expression got={:?}", program.nodes[0].expression);
+        };
+        assert_eq!(Expression::Identifier("x".into()), value.expression);
+        assert_eq!(4, branches.len());
+
+        let (Pattern::Literal(ref literal), None, ref consequence) = branches[0] else {
+            panic!("Expected literal pattern got={:?}", branches[0]);
+        };
+        assert_eq!(Expression::Integer(1), literal.expression);
+        assert_eq!(Expression::String("one".into()), consequence.expression);
+
+        let (Pattern::Array(ref elements, Some(ref rest)), None, _) = branches[1] else {
+            panic!("Expected array pattern got={:?}", branches[1]);
+        };
+        assert_eq!(1, elements.len());
+        let Pattern::Binding(ref rest) = **rest else {
+            panic!("Expected binding pattern got={:?}", rest);
+        };
+        assert_eq!(Expression::Identifier("rest".into()), rest.expression);
+
+        let (Pattern::Type(ref inner, ref type_name), Some(_), _) = branches[2] else {
+            panic!("Expected type pattern with guard got={:?}", branches[2]);
+        };
+        assert_eq!("Integer", type_name.as_ref());
+        let Pattern::Binding(_) = **inner else {
+            panic!("Expected binding pattern got={:?}", inner);
+        };
+
+        let (Pattern::Wildcard, None, _) = branches[3] else {
+            panic!("Expected wildcard pattern got={:?}", branches[3]);
+        };
+    }
+
+    #[test]
+    fn test_match_expression_errors() {
+        let tests = [
+            "(match x 1)",
+            "(match x [..rest a] 1)",
+            "(match x n:: 1)",
+            "(match x (+ 1 2) 1)",
+            "(record 1 x)",
+            "(record Point x 2)",
+            "(record Point x x)",
+            "(record Point x",
+            "(enum Shape)",
+            "(enum Shape 1)",
+            "(enum Shape A A)",
+            "(enum Shape (A x) (A y))",
+            "(enum Shape (A 1))",
+            "(enum Shape (A x)",
+        ];
+
+        for input in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            assert!(parser.parse_program().is_err(), "Expected parse error for input: {input}");
+        }
+    }
+
     #[test]
     fn test_include_expression() {
         let input = "(include \"file.kvist\")";
@@ -609,6 +732,47 @@ mod parser_test {
         };
         assert_eq!(Expression::String("file.kvist".into()), file.expression);
     }
+
+    #[test]
+    fn test_macro_and_quote_expressions() {
+        let tests = [
+            "(macro unless |c ...body| (quasiquote (if (unquote c) () ((unquote ..body)))))",
+            "(quote (+ a 1))",
+            "(quasiquote (set ((unquote name) 1)))",
+        ];
+
+        for input in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse: {input}"));
+            assert_eq!(input, program.string().as_ref());
+        }
+    }
+
+    #[test]
+    fn test_program_string() {
+        let tests = [
+            "(set (a 1) (b 2.5))",
+            "(fn |x ...rest| (+ x (@ 0 rest)))",
+            "(when (< a 1) \"small\" true ())",
+            "(match v [n ..rest] if (> n 1) ..rest n::Integer (- n) _ false)",
+            "(while (> i 0) (§ (set (i (- i 1)))))",
+            "(test \"name\" (assert true))",
+            "(for x (range 10) (println x))",
+            "(record Point x y)",
+            "(enum Shape (Circle radius) (Rect w h) Empty)",
+            "\"Hello {name}, {(+ a 1)} {{literal}}\"",
+            "(println \"say \\\"hi\\\"\\n\" 2.0 -0.5)",
+            "\"{(+ \\\"a\\\" b)}\"",
+        ];
+
+        for input in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse: {input}"));
+            assert_eq!(input, program.string().as_ref());
+        }
+    }
 }
 
 enum Expected {
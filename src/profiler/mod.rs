@@ -0,0 +1,4 @@
+pub mod profiler;
+
+#[cfg(test)]
+mod profiler_test;
@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::write;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::ast::ast::{Node, Program};
+use crate::ast::expression::Expression;
+
+thread_local! {
+    static PROFILER: RefCell<Option<Profiler>> = const { RefCell::new(None) };
+}
+
+pub enum Output {
+    Report,
+    // Path of a file to write folded stacks to, as used by flamegraph tools
+    Folded(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+enum Key {
+    // Body of the function definition, shared by every function made from it however it's called
+    Function(*const Node),
+    Builtin(String),
+}
+
+struct Definition {
+    // Held so the address of the body stays unique while the profile is collected
+    body: Rc<Node>,
+    // Name at the first call that calls the function by name
+    name: Option<String>,
+    file: Option<String>,
+}
+
+struct Call {
+    key: Key,
+    start: Instant,
+    children: Duration,
+}
+
+#[derive(Default)]
+struct Statistics {
+    calls: u64,
+    total: Duration,
+    own: Duration,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub label: String,
+    pub calls: u64,
+    pub total: Duration,
+    pub own: Duration,
+}
+
+pub struct Profiler {
+    output: Output,
+    stack: Vec<Call>,
+    statistics: HashMap<Key, Statistics>,
+    folded: HashMap<Vec<Key>, Duration>,
+    definitions: HashMap<*const Node, Definition>,
+}
+
+impl Profiler {
+
+    pub fn new(output: Output) -> Profiler {
+        Profiler {
+            output,
+            stack: Vec::new(),
+            statistics: HashMap::new(),
+            folded: HashMap::new(),
+            definitions: HashMap::new(),
+        }
+    }
+
+    fn label(&self, key: &Key) -> String {
+        match key {
+            Key::Function(body) => {
+                let definition = &self.definitions[body];
+                let name = definition.name.as_deref().unwrap_or("(fn)");
+                let (row, col) = (definition.body.token.row, definition.body.token.col);
+                match &definition.file {
+                    Some(file) => format!("{name}@{file}:{row}:{col}"),
+                    None => format!("{name}@{row}:{col}"),
+                }
+            }
+            Key::Builtin(name) => format!("{name} (builtin)"),
+        }
+    }
+
+    fn define(&mut self, body: &Rc<Node>, file: Option<&str>) -> &mut Definition {
+        self.definitions.entry(Rc::as_ptr(body)).or_insert_with(|| Definition {
+            body: body.clone(),
+            name: None,
+            file: file.map(str::to_string),
+        })
+    }
+
+    fn add_definitions(&mut self, node: &Node, file: &str) {
+        if let Expression::Function(_, _, body) = &node.expression {
+            self.define(body, Some(file));
+        }
+        for child in node.children() {
+            self.add_definitions(child, file);
+        }
+    }
+
+    fn enter(&mut self, key: Key) {
+        self.stack.push(Call { key, start: Instant::now(), children: Duration::ZERO });
+    }
+
+    fn exit(&mut self) {
+        let Some(call) = self.stack.pop() else {
+            return;
+        };
+        let elapsed = call.start.elapsed();
+        let own = elapsed.saturating_sub(call.children);
+
+        let path = self.stack.iter()
+            .map(|call| call.key.clone())
+            .chain([call.key.clone()])
+            .collect::<Vec<Key>>();
+        *self.folded.entry(path).or_default() += own;
+
+        // Time of recursive calls is already part of the outermost call of the same function
+        let recursive = self.stack.iter().any(|outer| outer.key == call.key);
+        let statistics = self.statistics.entry(call.key).or_default();
+        statistics.calls += 1;
+        statistics.own += own;
+        if !recursive {
+            statistics.total += elapsed;
+        }
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+    }
+
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries = self.statistics.iter()
+            .map(|(key, statistics)| Entry {
+                label: self.label(key),
+                calls: statistics.calls,
+                total: statistics.total,
+                own: statistics.own,
+            })
+            .collect::<Vec<Entry>>();
+        entries.sort_by(|left, right| right.own.cmp(&left.own)
+            .then(right.calls.cmp(&left.calls))
+            .then(left.label.cmp(&right.label)));
+        entries
+    }
+
+    pub fn report(&self) -> String {
+        let entries = self.entries();
+        let width = entries.iter()
+            .map(|entry| entry.label.len())
+            .max()
+            .unwrap_or(0)
+            .max("Function".len());
+
+        let mut report = format!("{:<width$} {:>10} {:>12} {:>12}\n", "Function", "Calls", "Total (ms)", "Self (ms)");
+        for entry in entries {
+            report += format!("{:<width$} {:>10} {:>12.3} {:>12.3}\n",
+                              entry.label, entry.calls,
+                              entry.total.as_secs_f64() * 1000.,
+                              entry.own.as_secs_f64() * 1000.).as_str();
+        }
+        report
+    }
+
+    // One line per unique call stack with the time spent in its innermost call in microseconds
+    pub fn folded(&self) -> String {
+        let mut lines = self.folded.iter()
+            .map(|(path, own)| format!("{} {}", path.iter()
+                .map(|key| self.label(key))
+                .collect::<Vec<String>>()
+                .join(";"), own.as_micros()))
+            .collect::<Vec<String>>();
+        lines.sort();
+        lines.join("\n") + "\n"
+    }
+}
+
+pub fn enable(profiler: Profiler) {
+    PROFILER.with_borrow_mut(|current| *current = Some(profiler));
+}
+
+pub fn take() -> Option<Profiler> {
+    PROFILER.with_borrow_mut(|profiler| profiler.take())
+}
+
+fn call_name(node: &Node) -> String {
+    match &node.expression {
+        Expression::Identifier(name) => name.to_string(),
+        _ => "(fn)".to_string(),
+    }
+}
+
+// Records the file each function in a program is defined in, for the labels of its calls
+pub fn add_file(program: &Program, file: &str) {
+    PROFILER.with_borrow_mut(|profiler| if let Some(profiler) = profiler {
+        program.nodes.iter().for_each(|node| profiler.add_definitions(node, file));
+    });
+}
+
+pub fn enter_function(node: &Node, body: &Rc<Node>) {
+    PROFILER.with_borrow_mut(|profiler| if let Some(profiler) = profiler {
+        let definition = profiler.define(body, None);
+        if let (None, Expression::Identifier(name)) = (&definition.name, &node.expression) {
+            definition.name = Some(name.to_string());
+        }
+        profiler.enter(Key::Function(Rc::as_ptr(body)));
+    });
+}
+
+pub fn enter_builtin(node: &Node) {
+    PROFILER.with_borrow_mut(|profiler| if let Some(profiler) = profiler {
+        profiler.enter(Key::Builtin(call_name(node)));
+    });
+}
+
+pub fn exit() {
+    PROFILER.with_borrow_mut(|profiler| if let Some(profiler) = profiler {
+        profiler.exit();
+    });
+}
+
+// Writes the collected profile, called when the program finishes
+pub fn finish() {
+    let Some(profiler) = take() else {
+        return;
+    };
+    match &profiler.output {
+        Output::Report => eprint!("{}", profiler.report()),
+        Output::Folded(path) => {
+            if let Err(error) = write(path, profiler.folded()) {
+                eprintln!("Could not write profile to '{path}': {error}");
+            }
+        }
+    }
+}
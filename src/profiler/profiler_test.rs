@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod test {
+    use crate::evaluator::evaluator::Eval;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::environment::Environment;
+    use crate::parser::parser::Parser;
+    use crate::profiler::profiler::{add_file, enable, take, Output, Profiler};
+
+    #[test]
+    fn test_call_counts() {
+        let input = "\
+(set (fib (fn |n| (when (< n 3) 1 () (+ (fib (- n 1)) (fib (- n 2)))))))
+(set (double (fn |x| (* x 2))))
+(len [(fib 6) (double 1) (double 2)])";
+
+        let profiler = apply_profile(input);
+        let calls = profiler.entries().iter()
+            .map(|entry| (entry.label.clone(), entry.calls))
+            .collect::<Vec<_>>();
+
+        assert!(calls.contains(&("fib@1:20".to_string(), 15)), "Unexpected calls: {calls:?}");
+        assert!(calls.contains(&("double@2:23".to_string(), 2)), "Unexpected calls: {calls:?}");
+        assert!(calls.contains(&("len (builtin)".to_string(), 1)), "Unexpected calls: {calls:?}");
+        assert_eq!(3, calls.len(), "Unexpected calls: {calls:?}");
+    }
+
+    #[test]
+    fn test_functions_are_counted_by_definition() {
+        let input = "\
+(set (double (fn |x| (* x 2))) (twice double))
+(collect (map twice [(double 1) (twice 2)]))";
+
+        let profiler = apply_profile(input);
+        let calls = profiler.entries().iter()
+            .filter(|entry| !entry.label.ends_with("(builtin)"))
+            .map(|entry| (entry.label.clone(), entry.calls))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("double@1:23".to_string(), 4)], calls);
+    }
+
+    #[test]
+    fn test_functions_are_told_apart_by_file() {
+        enable(Profiler::new(Output::Report));
+        let mut environment = Environment::new();
+        for (file, input) in [("a.kvist", "(set (a (fn |x| x)))"), ("b.kvist", "(set (b (fn |x| x)))")] {
+            let program = Parser::from(Lexer::from(input)).parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            add_file(&program, file);
+            program.eval(&mut environment).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input));
+        }
+        let input = "(a 1) (b 2) (a 3)";
+        let program = Parser::from(Lexer::from(input)).parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+        program.eval(&mut environment).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input));
+
+        let profiler = take().expect("Expected profiler to be enabled");
+        let mut calls = profiler.entries().iter()
+            .map(|entry| (entry.label.clone(), entry.calls))
+            .collect::<Vec<_>>();
+        calls.sort();
+
+        assert_eq!(vec![("a@a.kvist:1:17".to_string(), 2), ("b@b.kvist:1:17".to_string(), 1)], calls);
+    }
+
+    #[test]
+    fn test_total_time_covers_children() {
+        let input = "\
+(set (inner (fn || (len [1 2 3]))))
+(set (outer (fn || ((inner) (inner)))))
+(outer)";
+
+        let profiler = apply_profile(input);
+        let entries = profiler.entries();
+        let outer = entries.iter().find(|entry| entry.label.starts_with("outer")).unwrap();
+        let inner = entries.iter().find(|entry| entry.label.starts_with("inner")).unwrap();
+
+        assert!(outer.total >= inner.total, "Expected cumulative time to include calls: {entries:?}");
+        assert!(outer.total >= outer.own, "Expected cumulative time to include own time: {entries:?}");
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let input = "\
+(set (inner (fn || (len [1 2 3]))))
+(set (outer (fn || (inner))))
+(outer)";
+
+        let profiler = apply_profile(input);
+        let stacks = profiler.folded().lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(vec![
+            "outer@2:20",
+            "outer@2:20;inner@1:20",
+            "outer@2:20;inner@1:20;len (builtin)",
+        ], stacks);
+    }
+
+    fn apply_profile(input: &str) -> Profiler {
+        enable(Profiler::new(Output::Report));
+
+        let lexer = Lexer::from(input);
+        let parser = Parser::from(lexer);
+        let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+        program.eval(&mut Environment::new()).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input));
+
+        take().expect("Expected profiler to be enabled")
+    }
+}
@@ -1,18 +1,25 @@
 use std::io;
 use std::io::{BufRead, BufReader, Write};
 
+use crate::ast::ast::AST;
 use crate::evaluator::evaluator::Eval;
+use crate::evaluator::limits::Limits;
+use crate::expander::expander::Expander;
 use crate::lexer::lexer::Lexer;
 use crate::object::environment::Environment;
 use crate::object::object::Viewable;
 use crate::parser::parser::Parser;
+use crate::optimizer::optimizer;
+use crate::resolver::resolver::resolve;
 
 // const PROMPT: &str = ">> ";
 
-pub fn start() {
+// Every input gets the full limits, so a runaway expression doesn't end the session
+pub fn start(limits: Limits) {
     let mut stdin = BufReader::new(io::stdin());
     let mut stdout = io::stdout();
     let mut env = Environment::new();
+    let mut expander = Expander::new();
     println!("Feel free to type in commands");
     loop {
         print!(">> ");
@@ -25,12 +32,16 @@ pub fn start() {
             continue;
         }
 
-        let input = format!("({line})");
+        // `expand <expression>` shows the expression with its macro calls expanded
+        let (input, expand_only) = match line.strip_prefix("expand ") {
+            Some(expression) => (expression.to_string(), true),
+            None => (format!("({line})"), false),
+        };
         let lexer = Lexer::from(input.as_str());
         let parser = Parser::from(lexer);
         // match parser.parse_program().map(|program| program.eval(&mut env)) {
-        let evaluation = match parser.parse_program() {
-            Ok(result) => result.eval(&mut env),
+        let program = match parser.parse_program() {
+            Ok(result) => result,
             Err(errors) => {
                 eprintln!("Failed to parse input:");
                 errors.iter()
@@ -38,6 +49,28 @@ pub fn start() {
                 continue;
             }
         };
+        let program = match expander.expand(program) {
+            Ok(program) => program,
+            Err(errors) => {
+                eprintln!("Failed to expand input:");
+                errors.iter()
+                    .for_each(|error| eprintln!("\tERROR: {error}"));
+                continue;
+            }
+        };
+        if expand_only {
+            println!("{}", program.string());
+            continue;
+        }
+        let program = optimizer::apply(program);
+        let errors = resolve(&program, &env);
+        if !errors.is_empty() {
+            eprintln!("Failed to resolve input:");
+            errors.iter()
+                .for_each(|error| eprintln!("\tERROR: {error}"));
+            continue;
+        }
+        let evaluation = program.eval_with_limits(&mut env, limits);
         match evaluation {
             Ok(object) => println!("{}", object.view()),
             Err(error) => eprintln!("Execution error:\n\tERROR: {error}")
@@ -0,0 +1,4 @@
+pub mod resolver;
+
+#[cfg(test)]
+mod resolver_test;
@@ -0,0 +1,277 @@
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use crate::ast::ast::{Address, Layout, Node, Program};
+use crate::ast::expression::Expression;
+use crate::ast::pattern::Pattern;
+use crate::evaluator::builtin::builtin_names;
+use crate::object::environment::Environment;
+
+#[derive(Debug)]
+pub struct ResolveError {
+    pub col: u32,
+    pub row: u32,
+    pub message: String,
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}, Col: {}: {}", self.row, self.col, self.message)
+    }
+}
+
+struct Scope {
+    layout: Layout,
+    // Includes can bind any name in the scope at runtime
+    includes: bool,
+}
+
+struct Resolver<'a> {
+    scopes: Vec<Scope>,
+    // With includes anywhere in the program any name might be bound at runtime
+    includes: bool,
+    environment: &'a Environment,
+    errors: Vec<ResolveError>,
+}
+
+// Annotates every identifier in the program with where its binding is found and every
+// scope with the slots of its frame, so the evaluator can skip most lookups by name.
+// The top-level scope is the frame of the environment the program will run in, which
+// gets slots for the program's top-level bindings after those it already has.
+// Names that can't be bound by the time they're evaluated are returned as errors,
+// names already bound in the environment are allowed.
+pub fn resolve(program: &Program, environment: &Environment) -> Vec<ResolveError> {
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        includes: program.nodes.iter().any(contains_include),
+        environment,
+        errors: Vec::new(),
+    };
+
+    let mut scope = Scope { layout: environment.layout(), includes: false };
+    program.nodes.iter().for_each(|node| declare(node, &mut scope));
+    environment.extend(&scope.layout);
+    resolver.scopes.push(scope);
+    program.nodes.iter().for_each(|node| resolver.resolve(node));
+
+    resolver.errors
+}
+
+fn contains_include(node: &Node) -> bool {
+    matches!(node.expression, Expression::Include(_)) || is_eval_call(node) || node.children().into_iter().any(contains_include)
+}
+
+// Source code evaluated by eval can bind any name, like an included file
+fn is_eval_call(node: &Node) -> bool {
+    let Expression::ExpressionLiteral(nodes) = &node.expression else {
+        return false;
+    };
+    matches!(nodes.first(), Some(Node { expression: Expression::Identifier(name), .. }) if name.as_ref() == "eval")
+}
+
+// Collects the names bound directly in a scope, without those of nested scopes
+fn declare(node: &Node, scope: &mut Scope) {
+    match &node.expression {
+        Expression::Set(variables) => {
+            for (identifier, value) in variables.iter() {
+                if let Expression::Identifier(name) = &identifier.expression {
+                    scope.add(name);
+                }
+                declare(value, scope);
+            }
+        }
+        Expression::Record(name, _) => if let Expression::Identifier(name) = &name.expression {
+            scope.add(name);
+        },
+        Expression::Enum(_, variants) => for variant in variants.iter() {
+            match &variant.expression {
+                Expression::Identifier(name) => scope.add(name),
+                _ => declare(variant, scope),
+            }
+        },
+        Expression::Include(target) => {
+            scope.includes = true;
+            declare(target, scope);
+        }
+        Expression::ExpressionLiteral(nodes) if is_eval_call(node) => {
+            scope.includes = true;
+            nodes.iter().for_each(|node| declare(node, scope));
+        }
+        Expression::Match(value, _) => declare(value, scope),
+        Expression::For(_, iterable, _) => declare(iterable, scope),
+        Expression::Function(_, _, _)
+        | Expression::Section(_)
+        | Expression::Test(_, _)
+        | Expression::Macro(_, _, _, _)
+        | Expression::Quote(_)
+        | Expression::Quasiquote(_) => {}
+        _ => node.children().into_iter().for_each(|child| declare(child, scope)),
+    }
+}
+
+impl Scope {
+    fn add(&mut self, name: &Rc<str>) {
+        self.layout.add(name);
+    }
+
+    fn slot(&self, name: &Rc<str>) -> Option<usize> {
+        self.layout.slot(name)
+    }
+}
+
+impl Resolver<'_> {
+
+    fn resolve(&mut self, node: &Node) {
+        match &node.expression {
+            Expression::Identifier(name) => self.resolve_identifier(node, name),
+            Expression::Set(variables) => {
+                for (identifier, value) in variables.iter() {
+                    self.resolve(value);
+                    self.bind(identifier);
+                }
+            }
+            Expression::Function(params, vararg, body) => {
+                let mut scope = Scope { layout: Layout::default(), includes: false };
+                params.iter().chain(vararg.as_ref().as_ref())
+                    .filter_map(|param| match &param.expression {
+                        Expression::Identifier(name) => Some(name),
+                        _ => None,
+                    })
+                    .for_each(|name| scope.add(name));
+                declare(body, &mut scope);
+                self.enter(scope, body, |resolver| {
+                    params.iter().chain(vararg.as_ref().as_ref()).for_each(|param| resolver.bind(param));
+                    resolver.resolve(body);
+                });
+            }
+            Expression::For(binding, iterable, body) => {
+                self.resolve(iterable);
+                let mut scope = Scope { layout: Layout::default(), includes: false };
+                if let Expression::Identifier(name) = &binding.expression {
+                    scope.add(name);
+                }
+                declare(body, &mut scope);
+                self.enter(scope, body, |resolver| {
+                    resolver.bind(binding);
+                    resolver.resolve(body);
+                });
+            }
+            Expression::Section(body) => {
+                let mut scope = Scope { layout: Layout::default(), includes: false };
+                declare(body, &mut scope);
+                self.enter(scope, body, |resolver| resolver.resolve(body));
+            }
+            Expression::Match(value, branches) => {
+                self.resolve(value);
+                for (pattern, guard, consequence) in branches.iter() {
+                    let mut scope = Scope { layout: Layout::default(), includes: false };
+                    declare_pattern(pattern, &mut scope);
+                    guard.iter().for_each(|guard| declare(guard, &mut scope));
+                    declare(consequence, &mut scope);
+                    self.enter(scope, consequence, |resolver| {
+                        resolver.resolve_pattern(pattern);
+                        guard.iter().for_each(|guard| resolver.resolve(guard));
+                        resolver.resolve(consequence);
+                    });
+                }
+            }
+            Expression::Test(name, body) => {
+                let mut scope = Scope { layout: Layout::default(), includes: false };
+                declare(name, &mut scope);
+                declare(body, &mut scope);
+                self.enter(scope, body, |resolver| {
+                    resolver.resolve(name);
+                    resolver.resolve(body);
+                });
+            }
+            Expression::Record(name, _) => self.bind(name),
+            Expression::Enum(_, variants) => variants.iter().for_each(|variant| match &variant.expression {
+                Expression::Record(name, _) => self.bind(name),
+                _ => self.bind(variant),
+            }),
+            // Macro bodies are only evaluated when expanding and quoted code is data
+            Expression::Macro(_, _, _, _) | Expression::Quote(_) => {}
+            Expression::Quasiquote(template) => self.resolve_unquotes(template),
+            _ => node.children().into_iter().for_each(|child| self.resolve(child)),
+        }
+    }
+
+    fn resolve_unquotes(&mut self, template: &Node) {
+        match &template.expression {
+            Expression::Unquote(operand) => self.resolve(operand),
+            Expression::Quote(_) => {}
+            _ => template.children().into_iter().for_each(|child| self.resolve_unquotes(child)),
+        }
+    }
+
+    fn enter(&mut self, scope: Scope, body: &Node, resolve: impl FnOnce(&mut Self)) {
+        let _ = body.resolution.scope.set(Rc::new(scope.layout.clone()));
+        self.scopes.push(scope);
+        resolve(self);
+        self.scopes.pop();
+    }
+
+    // Addresses the identifier a value is bound to, which is always in the current scope
+    fn bind(&mut self, identifier: &Node) {
+        let (Expression::Identifier(name), Some(scope)) = (&identifier.expression, self.scopes.last()) else {
+            return;
+        };
+        if let Some(slot) = scope.slot(name) {
+            identifier.resolution.address.set(Address::Local(0, slot));
+        }
+    }
+
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Binding(identifier) => self.bind(identifier),
+            Pattern::Literal(literal) => self.resolve(literal),
+            Pattern::Array(elements, rest) => {
+                elements.iter().for_each(|element| self.resolve_pattern(element));
+                rest.iter().for_each(|rest| self.resolve_pattern(rest));
+            }
+            Pattern::Type(inner, _) => self.resolve_pattern(inner),
+        }
+    }
+
+    fn resolve_identifier(&mut self, node: &Node, name: &Rc<str>) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(slot) = scope.slot(name) {
+                node.resolution.address.set(Address::Local(depth, slot));
+                return;
+            }
+            // A binding from an include would shadow the ones further out
+            if scope.includes {
+                return;
+            }
+        }
+
+        if self.includes || self.environment.contains(name) {
+            return;
+        }
+        match builtin_names().iter().position(|builtin| *builtin == name.as_ref()) {
+            Some(index) => node.resolution.address.set(Address::Builtin(index)),
+            None => self.errors.push(ResolveError {
+                col: node.token.col,
+                row: node.token.row,
+                message: format!("No binding for identifier '{name}'"),
+            }),
+        }
+    }
+}
+
+fn declare_pattern(pattern: &Pattern, scope: &mut Scope) {
+    match pattern {
+        Pattern::Binding(identifier) => {
+            if let Expression::Identifier(name) = &identifier.expression {
+                scope.add(name);
+            }
+        }
+        Pattern::Array(elements, rest) => {
+            elements.iter().for_each(|element| declare_pattern(element, scope));
+            rest.iter().for_each(|rest| declare_pattern(rest, scope));
+        }
+        Pattern::Type(inner, _) => declare_pattern(inner, scope),
+        Pattern::Wildcard | Pattern::Literal(_) => {}
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod test {
+    use crate::ast::ast::{Address, Node};
+    use crate::ast::expression::Expression;
+    use crate::evaluator::evaluator::Eval;
+    use crate::lexer::lexer::Lexer;
+    use crate::object::environment::Environment;
+    use crate::object::object::Object;
+    use crate::parser::parser::Parser;
+    use crate::resolver::resolver::resolve;
+
+    #[test]
+    fn test_resolve_addresses() {
+        let tests = [
+            ("(set (a 1)) a", vec![("a", Address::Local(0, 0)), ("a", Address::Local(0, 0))]),
+            ("(fn |x y| (+ x y))", vec![("x", Address::Local(0, 0)), ("y", Address::Local(0, 1)), ("x", Address::Local(0, 0)), ("y", Address::Local(0, 1))]),
+            ("(fn |x| (fn || ((set (y x)) y)))", vec![("x", Address::Local(0, 0)), ("y", Address::Local(0, 0)), ("x", Address::Local(1, 0)), ("y", Address::Local(0, 0))]),
+            ("(fn || (len first))", vec![("len", Address::Builtin(3)), ("first", Address::Builtin(4))]),
+            ("(set (len 1)) (fn || len)", vec![("len", Address::Local(0, 0)), ("len", Address::Local(1, 0))]),
+            ("(match [1 2] [a ..b] b)", vec![("a", Address::Local(0, 0)), ("b", Address::Local(0, 1)), ("b", Address::Local(0, 1))]),
+            ("(fn |x| ((include \"lib.kvist\") x y))", vec![("x", Address::Local(0, 0)), ("x", Address::Local(0, 0)), ("y", Address::Dynamic)]),
+            ("(fn |x| (§((include \"lib.kvist\") x)))", vec![("x", Address::Local(0, 0)), ("x", Address::Dynamic)]),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            let errors = resolve(&program, &Environment::new());
+            assert!(errors.is_empty(), "Failed to resolve: {input}: {errors:?}");
+
+            let mut addresses = Vec::new();
+            program.nodes.iter().for_each(|node| collect_addresses(node, &mut addresses));
+            let expected = expected.into_iter()
+                .map(|(name, address)| (name.to_string(), address))
+                .collect::<Vec<(String, Address)>>();
+            assert_eq!(expected, addresses, "Wrong addresses for: {input}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_top_level_after_environment_slots() {
+        // Like the repl, every program is resolved against the environment the previous ones ran in
+        let environment = Environment::new();
+        let tests = [
+            ("(set (a 1) (b 2))", vec![("a", Address::Local(0, 0)), ("b", Address::Local(0, 1))]),
+            ("(set (c 3)) (+ a c)", vec![("c", Address::Local(0, 2)), ("a", Address::Local(0, 0)), ("c", Address::Local(0, 2))]),
+            ("(set (b 4)) b", vec![("b", Address::Local(0, 1)), ("b", Address::Local(0, 1))]),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            let errors = resolve(&program, &environment);
+            assert!(errors.is_empty(), "Failed to resolve: {input}: {errors:?}");
+
+            let mut addresses = Vec::new();
+            program.nodes.iter().for_each(|node| collect_addresses(node, &mut addresses));
+            let expected = expected.into_iter()
+                .map(|(name, address)| (name.to_string(), address))
+                .collect::<Vec<(String, Address)>>();
+            assert_eq!(expected, addresses, "Wrong addresses for: {input}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let tests = [
+            ("(println y)", vec!["Row 1, Col: 10: No binding for identifier 'y'"]),
+            ("(set (f (fn || y))) (set (y 1))", vec![]),
+            ("(fn |x| x) x", vec!["Row 1, Col: 12: No binding for identifier 'x'"]),
+            ("(fn || (set (z 1))) (match 1 n n) (+ z n)", vec!["Row 1, Col: 38: No binding for identifier 'z'", "Row 1, Col: 40: No binding for identifier 'n'"]),
+            ("(include \"lib.kvist\") (println y)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            let errors = resolve(&program, &Environment::new()).iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>();
+            assert_eq!(expected, errors, "Wrong errors for: {input}");
+        }
+    }
+
+    #[test]
+    fn test_resolved_identifiers_are_read_from_their_slot() {
+        // The last identifier is readdressed to the slot of the first binding, so its value shows
+        // the frame is indexed by the address instead of looking the name up
+        let tests = [
+            ("(set (a 1) (b 2)) b", Address::Local(0, 0), 1),
+            ("((fn |x y| y) 1 2)", Address::Local(0, 0), 1),
+            ("(set (a 1)) ((fn |x| a) 2)", Address::Local(0, 0), 2),
+            ("(set (a 1)) ((fn |x| x) 2)", Address::Local(1, 0), 1),
+        ];
+
+        for (input, address, expected) in tests {
+            let lexer = Lexer::from(input);
+            let parser = Parser::from(lexer);
+            let program = parser.parse_program().unwrap_or_else(|_| panic!("Failed to parse program: {}", input));
+            let mut environment = Environment::new();
+            let errors = resolve(&program, &environment);
+            assert!(errors.is_empty(), "Failed to resolve: {input}: {errors:?}");
+
+            let mut identifiers = Vec::new();
+            program.nodes.iter().for_each(|node| collect_identifiers(node, &mut identifiers));
+            identifiers.last().expect("Expected an identifier").resolution.address.set(address);
+            let result = program.eval(&mut environment).unwrap_or_else(|_| panic!("Failed to evaluate: {}", input));
+            assert_eq!(Object::Integer(expected), result, "Wrong value for: {input}");
+        }
+    }
+
+    fn collect_identifiers<'a>(node: &'a Node, identifiers: &mut Vec<&'a Node>) {
+        if let Expression::Identifier(_) = &node.expression {
+            identifiers.push(node);
+        }
+        node.children().into_iter().for_each(|child| collect_identifiers(child, identifiers));
+    }
+
+    fn collect_addresses(node: &Node, addresses: &mut Vec<(String, Address)>) {
+        if let Expression::Identifier(name) = &node.expression {
+            addresses.push((name.to_string(), node.resolution.address.get()));
+        }
+        node.children().into_iter().for_each(|child| collect_addresses(child, addresses));
+    }
+}
@@ -1,10 +1,15 @@
 
 
 use std::fs::read_to_string;
+use crate::debugger::debugger;
 use crate::evaluator::evaluator::Eval;
+use crate::expander::expander::Expander;
 use crate::lexer::lexer::Lexer;
 use crate::object::environment::Environment;
 use crate::parser::parser::Parser;
+use crate::optimizer::optimizer;
+use crate::profiler::profiler;
+use crate::resolver::resolver::resolve;
 
 pub(crate) fn start(file: &String) {
     let mut env = Environment::new();
@@ -17,8 +22,9 @@ pub(crate) fn start(file: &String) {
     let lexer = Lexer::from(content.as_str());
     let parser = Parser::from(lexer);
 
-    let evaluation = match parser.parse_program() {
-        Ok(result) => result.eval(&mut env),
+    debugger::enter_file(file);
+    let program = match parser.parse_program() {
+        Ok(result) => result,
         Err(errors) => {
             eprintln!("Failed to parse input:");
             errors.iter()
@@ -27,6 +33,26 @@ pub(crate) fn start(file: &String) {
         }
     };
 
+    let program = match Expander::new().expand(program) {
+        Ok(program) => program,
+        Err(errors) => {
+            eprintln!("Failed to expand input:");
+            errors.iter()
+                .for_each(|error| eprintln!("\tERROR: {error}"));
+            return;
+        }
+    };
+    let program = optimizer::apply(program);
+    let errors = resolve(&program, &env);
+    if !errors.is_empty() {
+        eprintln!("Failed to resolve input:");
+        errors.iter()
+            .for_each(|error| eprintln!("\tERROR: {error}"));
+        return;
+    }
+    profiler::add_file(&program, file);
+    let evaluation = program.eval(&mut env);
+
     match evaluation {
         Ok(_) => {},
         Err(error) => eprintln!("Execution error:\n\tERROR: {error}")
@@ -0,0 +1,4 @@
+pub mod testing;
+
+#[cfg(test)]
+mod testing_test;
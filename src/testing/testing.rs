@@ -0,0 +1,179 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::ast::Node;
+use crate::ast::expression::Expression;
+use crate::evaluator::evaluator::Eval;
+use crate::expander::expander::Expander;
+use crate::lexer::lexer::Lexer;
+use crate::object::environment::Environment;
+use crate::object::object::Viewable;
+use crate::parser::parser::Parser;
+use crate::optimizer::optimizer;
+use crate::resolver::resolver::resolve;
+
+const TEST_FILE_SUFFIX: &str = "_test.kvist";
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Default)]
+pub struct FileReport {
+    pub results: Vec<TestResult>,
+    // Set when the file itself could not be parsed or its top-level expressions failed
+    pub error: Option<String>,
+}
+
+impl FileReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter()
+            .filter(|result| result.outcome == Outcome::Passed)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed() + usize::from(self.error.is_some())
+    }
+}
+
+pub(crate) fn start(paths: &[String]) {
+    let paths = if paths.is_empty() { vec![".".to_string()] } else { paths.to_vec() };
+
+    let mut files = Vec::new();
+    for path in paths {
+        discover(Path::new(&path), true, &mut files);
+    }
+    files.sort();
+
+    let mut passed = 0;
+    let mut failed = 0;
+    for file in files {
+        println!("{}", file.display());
+        let report = match read_to_string(&file) {
+            Ok(content) => run_tests(&content),
+            Err(error) => FileReport { results: Vec::new(), error: Some(format!("Could not open: {error}")) },
+        };
+
+        if let Some(error) = &report.error {
+            println!("    ERROR: {error}");
+        }
+        for result in report.results.iter() {
+            match &result.outcome {
+                Outcome::Passed => println!("    test {} ... ok", result.name),
+                Outcome::Failed(error) => {
+                    println!("    test {} ... FAILED", result.name);
+                    error.lines().for_each(|line| println!("        {line}"));
+                }
+            }
+        }
+        passed += report.passed();
+        failed += report.failed();
+    }
+
+    let status = if failed == 0 { "ok" } else { "FAILED" };
+    println!("test result: {status}. {passed} passed; {failed} failed");
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn discover(path: &Path, explicit: bool, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let hidden = path.file_name()
+            .map(|name| name.to_string_lossy().starts_with('.') && name != "." && name != "..")
+            .unwrap_or(false);
+        if hidden || (!explicit && path.ends_with("target")) {
+            return;
+        }
+        let Ok(entries) = read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            discover(&entry.path(), false, files);
+        }
+    } else if explicit || path.to_string_lossy().ends_with(TEST_FILE_SUFFIX) {
+        files.push(path.to_path_buf());
+    }
+}
+
+// Evaluates everything but the tests in the file first, then runs each top-level
+// test in its own scope on top of that, so tests can't affect each other.
+pub fn run_tests(content: &str) -> FileReport {
+    let lexer = Lexer::from(content);
+    let parser = Parser::from(lexer);
+
+    let program = match parser.parse_program() {
+        Ok(program) => program,
+        Err(errors) => return FileReport {
+            results: Vec::new(),
+            error: Some(errors.iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+        },
+    };
+
+    let program = match Expander::new().expand(program) {
+        Ok(program) => program,
+        Err(errors) => return FileReport {
+            results: Vec::new(),
+            error: Some(errors.iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+        },
+    };
+
+    let mut environment = Environment::new();
+    let program = optimizer::apply(program);
+    let errors = resolve(&program, &environment);
+    if !errors.is_empty() {
+        return FileReport {
+            results: Vec::new(),
+            error: Some(errors.iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+        };
+    }
+    let mut tests = Vec::new();
+    for node in program.nodes.iter() {
+        if let Expression::Test(name, body) = &node.expression {
+            tests.push((name, body));
+            continue;
+        }
+        if let Err(error) = node.eval(&mut environment) {
+            return FileReport { results: Vec::new(), error: Some(error.to_string()) };
+        }
+    }
+
+    let environment = Rc::new(environment);
+    let results = tests.into_iter()
+        .map(|(name, body)| run_test(name, body, &environment))
+        .collect();
+
+    FileReport { results, error: None }
+}
+
+fn run_test(name: &Node, body: &Node, environment: &Rc<Environment>) -> TestResult {
+    let mut test_environment = Environment::scope(environment.clone(), body);
+    let name = match name.eval(&mut test_environment) {
+        Ok(name) => name.view(),
+        Err(error) => return TestResult { name: name.token.literal.to_string(), outcome: Outcome::Failed(error.to_string()) },
+    };
+    let outcome = match body.eval(&mut test_environment) {
+        Ok(_) => Outcome::Passed,
+        Err(error) => Outcome::Failed(error.to_string()),
+    };
+    TestResult { name, outcome }
+}
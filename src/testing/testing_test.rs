@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod test {
+    use crate::testing::testing::{run_tests, Outcome};
+
+    #[test]
+    fn test_passing_and_failing_tests() {
+        let input = "\
+(set (double (fn |x| (* x 2))))
+(test \"doubles\" (assert_eq 4 (double 2)))
+(test \"fails\" (
+    (set (result (double 3)))
+    (assert_eq 7 result)))
+(test \"asserts\" (assert (< 1 2)))";
+
+        let report = run_tests(input);
+        assert_eq!(None, report.error);
+        assert_eq!((2, 1), (report.passed(), report.failed()));
+
+        let outcomes = report.results.iter()
+            .map(|result| (result.name.as_str(), &result.outcome))
+            .collect::<Vec<_>>();
+        assert_eq!(("doubles", &Outcome::Passed), outcomes[0]);
+        assert_eq!(("asserts", &Outcome::Passed), outcomes[2]);
+
+        let ("fails", Outcome::Failed(error)) = outcomes[1] else {
+            panic!("Expected failing test got={:?}", outcomes[1]);
+        };
+        assert!(error.starts_with("Row 5, Col: 6: assert_eq: values are not equal"), "Unexpected error: {error}");
+        assert!(error.contains("expected: 7 (Integer)\n    actual:   6 (Integer)"), "Unexpected error: {error}");
+    }
+
+    #[test]
+    fn test_isolated_environments() {
+        let input = "\
+(set (x 1))
+(test \"sets x\" ((set (x 2)) (assert_eq 2 x)))
+(test \"sees original x\" (assert_eq 1 x))";
+
+        let report = run_tests(input);
+        assert_eq!((2, 0), (report.passed(), report.failed()));
+    }
+
+    #[test]
+    fn test_file_errors() {
+        let report = run_tests("(set (x 1)");
+        assert!(report.error.is_some(), "Expected parse error");
+
+        let report = run_tests("(undefined_function) (test \"never run\" (assert true))");
+        assert!(report.error.is_some(), "Expected evaluation error");
+        assert_eq!((0, 1), (report.passed(), report.failed()));
+    }
+}
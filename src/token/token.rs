@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Token {
     pub(crate) token_type: TokenType,
     pub(crate) col: u32,
@@ -32,8 +32,13 @@ pub enum TokenType {
     Bang,
     Asterisk,
     Slash,
+    DoubleSlash,
+    Percent,
     LesserThan,
     GreaterThan,
+    LesserEquals,
+    GreaterEquals,
+    BangEquals,
 
     Equals,
 
@@ -55,6 +60,17 @@ pub enum TokenType {
     False,
     If,
     When,
+    Match,
     While,
+    For,
     Include,
+    Test,
+    Macro,
+    Record,
+    Enum,
+    Quote,
+    Quasiquote,
+    Unquote,
+    And,
+    Or,
 }
\ No newline at end of file